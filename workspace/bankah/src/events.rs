@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::jobs::TableJobOutput;
+use crate::state::SeatState;
+
+// Things that happen at a table as jobs are processed. The worker publishes these on a channel per table, and the web
+// server streams them to anyone watching that table.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableEvent {
+  JobCompleted {
+    job: String,
+    output: TableJobOutput,
+  },
+  Rolled {
+    dice: (u8, u8),
+    button: Option<u8>,
+    nonce: String,
+  },
+  Settled {
+    player: String,
+    amount: u32,
+  },
+  SeatsChanged {
+    seats: HashMap<String, SeatState>,
+  },
+}
+
+impl TableEvent {
+  // The name clients can listen for, matching the serialized tag.
+  pub fn name(&self) -> &'static str {
+    match self {
+      TableEvent::JobCompleted { .. } => "job_completed",
+      TableEvent::Rolled { .. } => "rolled",
+      TableEvent::Settled { .. } => "settled",
+      TableEvent::SeatsChanged { .. } => "seats_changed",
+    }
+  }
+}
@@ -1,10 +1,12 @@
-use crate::state::BetState;
+use crate::state::{BetState, FieldPreset, TableVariant};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct BetJob {
   pub bet: BetState,
+  #[serde(default)]
+  pub odds: Option<u32>,
   pub player: String,
   pub table: String,
   pub version: String,
@@ -17,6 +19,52 @@ pub struct RollJob {
   pub version: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateJob {
+  pub player: String,
+  #[serde(default)]
+  pub variant: TableVariant,
+  #[serde(default)]
+  pub field: FieldPreset,
+  #[serde(default)]
+  pub max_odds: Option<u32>,
+  #[serde(default)]
+  pub history: Option<usize>,
+  #[serde(default)]
+  pub buy_in: Option<u32>,
+}
+
+// Sitting down buys in with the player's whole balance unless an amount is given.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct SitJob {
+  pub table: String,
+  pub player: String,
+  #[serde(default)]
+  pub buy_in: Option<u32>,
+}
+
+// Moves chips between a player's account and their seat at a table, in either direction.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ChipsJob {
+  pub table: String,
+  pub player: String,
+  pub amount: u32,
+}
+
+// Sends chips to another player at the table, or tips the house when there is no recipient.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferJob {
+  pub table: String,
+  pub player: String,
+  #[serde(default)]
+  pub recipient: Option<String>,
+  pub amount: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct JobWapper<T> {
@@ -56,9 +104,12 @@ pub enum TableAdminJob {
 pub enum TableJob {
   Bet(JobWapper<BetJob>),
   Roll(JobWapper<RollJob>),
-  Sit(JobWapper<(String, String)>),
-  Create(JobWapper<String>),
+  Sit(JobWapper<SitJob>),
+  Create(JobWapper<CreateJob>),
   Stand(JobWapper<(String, String)>),
+  Rebuy(JobWapper<ChipsJob>),
+  CashOut(JobWapper<ChipsJob>),
+  Transfer(JobWapper<TransferJob>),
   Admin(JobWapper<TableAdminJob>),
 }
 
@@ -70,12 +121,64 @@ impl TableJob {
       TableJob::Sit(inner) => inner.id.clone(),
       TableJob::Create(inner) => inner.id.clone(),
       TableJob::Stand(inner) => inner.id.clone(),
+      TableJob::Rebuy(inner) => inner.id.clone(),
+      TableJob::CashOut(inner) => inner.id.clone(),
+      TableJob::Transfer(inner) => inner.id.clone(),
       TableJob::Admin(inner) => inner.id.clone(),
     }
   }
 
-  pub fn sit(table: String, player: String) -> Self {
-    TableJob::Sit(JobWapper::wrap((table, player)))
+  // The table a job is for, if it is for an existing one.
+  pub fn table(&self) -> Option<&String> {
+    match self {
+      TableJob::Bet(inner) => Some(&inner.job.table),
+      TableJob::Roll(inner) => Some(&inner.job.table),
+      TableJob::Sit(inner) => Some(&inner.job.table),
+      TableJob::Stand(inner) => Some(&inner.job.0),
+      TableJob::Rebuy(inner) => Some(&inner.job.table),
+      TableJob::CashOut(inner) => Some(&inner.job.table),
+      TableJob::Transfer(inner) => Some(&inner.job.table),
+      TableJob::Create(_) | TableJob::Admin(_) => None,
+    }
+  }
+
+  pub fn sit(table: String, player: String, buy_in: Option<u32>) -> Self {
+    TableJob::Sit(JobWapper::wrap(SitJob { table, player, buy_in }))
+  }
+
+  pub fn rebuy(table: String, player: String, amount: u32) -> Self {
+    TableJob::Rebuy(JobWapper::wrap(ChipsJob { table, player, amount }))
+  }
+
+  pub fn cash_out(table: String, player: String, amount: u32) -> Self {
+    TableJob::CashOut(JobWapper::wrap(ChipsJob { table, player, amount }))
+  }
+
+  pub fn transfer(table: String, player: String, recipient: Option<String>, amount: u32) -> Self {
+    TableJob::Transfer(JobWapper::wrap(TransferJob {
+      table,
+      player,
+      recipient,
+      amount,
+    }))
+  }
+
+  pub fn create(
+    player: String,
+    variant: TableVariant,
+    field: FieldPreset,
+    max_odds: Option<u32>,
+    history: Option<usize>,
+    buy_in: Option<u32>,
+  ) -> Self {
+    TableJob::Create(JobWapper::wrap(CreateJob {
+      player,
+      variant,
+      field,
+      max_odds,
+      history,
+      buy_in,
+    }))
   }
 
   pub fn stand(table: String, player: String) -> Self {
@@ -113,6 +216,19 @@ impl TableJob {
     let id = uuid::Uuid::new_v4().to_string();
     let job = BetJob {
       bet: state,
+      odds: None,
+      player,
+      table,
+      version,
+    };
+    TableJob::Bet(JobWapper { job, id, attempts: 0 })
+  }
+
+  pub fn put(state: BetState, odds: u32, player: String, table: String, version: String) -> Self {
+    let id = uuid::Uuid::new_v4().to_string();
+    let job = BetJob {
+      bet: state,
+      odds: Some(odds),
       player,
       table,
       version,
@@ -121,28 +237,31 @@ impl TableJob {
   }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum BetFailureReason {
-  InsufficientFunds,
-  InvalidComeBet,
-  MissingComeForOdds,
-  MissingPassForOdds,
-  Other,
+// Why a bet, or a move of chips to or from a seat, was turned down. The `code` is stable and meant for clients to
+// match on, the `message` is meant for players.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BetFailure {
+  pub code: String,
+  pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TableJobOutput {
   BetProcessed,
   BetStale,
-  BetFailed(BetFailureReason),
+  BetFailed(BetFailure),
   RollProcessed,
   RollStale,
   AdminOk,
   StandOk,
   FinalStandOk,
   SitOk,
+  RebuyOk,
+  CashOutOk,
+  ChipsFailed(BetFailure),
+  TransferOk,
+  TransferFailed(BetFailure),
   TableCreated(String),
 }
 
@@ -1,5 +1,6 @@
 use serde::Serialize;
 
+pub mod events;
 pub mod jobs;
 pub mod state;
 
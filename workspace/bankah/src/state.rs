@@ -29,6 +29,8 @@ pub struct SeatState {
   pub nickname: String,
   pub history: Vec<(BetState, bool, u32)>,
   pub seated_at: chrono::DateTime<chrono::Utc>,
+  #[serde(default)]
+  pub orphaned: bool,
   pub bets: Vec<BetState>,
 }
 
@@ -39,19 +41,64 @@ impl Default for SeatState {
       nickname: String::default(),
       history: Vec::with_capacity(0),
       seated_at: chrono::Utc::now(),
+      orphaned: false,
       bets: Vec::with_capacity(0),
     }
   }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TableVariant {
+  Standard,
+  Crapless,
+}
+
+impl Default for TableVariant {
+  fn default() -> Self {
+    TableVariant::Standard
+  }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FieldPaytableState {
+  pub pays: Vec<(u8, u8)>,
+}
+
+impl Default for FieldPaytableState {
+  fn default() -> Self {
+    FieldPaytableState {
+      pays: vec![(2, 2), (3, 1), (4, 1), (9, 1), (10, 1), (11, 1), (12, 2)],
+    }
+  }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldPreset {
+  Standard,
+  TripleTwelve,
+  TripleTwo,
+  // Five in the field in place of four, with 2 and 12 paid even money so the house edge matches the standard field.
+  FiveInField,
+}
+
+impl Default for FieldPreset {
+  fn default() -> Self {
+    FieldPreset::Standard
+  }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TableIndexState {
   pub id: String,
   pub name: String,
   pub population: Vec<(String, String)>,
+  #[serde(default)]
+  pub variant: TableVariant,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TableState {
   pub id: String,
   pub name: String,
@@ -61,6 +108,18 @@ pub struct TableState {
   pub rolls: Vec<(u8, u8)>,
   pub created_at: chrono::DateTime<chrono::Utc>,
   pub nonce: String,
+  #[serde(default)]
+  pub variant: TableVariant,
+  #[serde(default)]
+  pub field: FieldPaytableState,
+  #[serde(default)]
+  pub max_odds: Option<u32>,
+  #[serde(default)]
+  pub history: Option<usize>,
+  #[serde(default)]
+  pub hand: Option<HandState>,
+  #[serde(default)]
+  pub pending: Vec<PendingSettlementState>,
 }
 
 impl TableState {
@@ -87,11 +146,76 @@ impl Default for TableState {
       rolls: vec![],
       seats: HashMap::new(),
       nonce: uuid::Uuid::new_v4().to_string(),
+      variant: TableVariant::default(),
+      field: FieldPaytableState::default(),
+      max_odds: None,
+      history: None,
+      hand: None,
+      pending: vec![],
+    }
+  }
+}
+
+// A single shooter's hand. The hand in progress is kept with its table; finished hands are stored on their own, with
+// `net` precomputed so they can be ranked without aggregating.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HandState {
+  pub id: String,
+  pub table: String,
+  pub shooter: String,
+  pub nickname: String,
+  pub rolls: u32,
+  pub points_set: u32,
+  pub points_made: u32,
+  pub seven_out: bool,
+  pub won: u32,
+  pub lost: u32,
+  pub net: i64,
+  pub started_at: chrono::DateTime<chrono::Utc>,
+  pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Default for HandState {
+  fn default() -> Self {
+    HandState {
+      id: uuid::Uuid::new_v4().to_string(),
+      table: String::default(),
+      shooter: String::default(),
+      nickname: String::default(),
+      rolls: 0,
+      points_set: 0,
+      points_made: 0,
+      seven_out: false,
+      won: 0,
+      lost: 0,
+      net: 0,
+      started_at: chrono::Utc::now(),
+      finished_at: None,
     }
   }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+// Every roll is archived on its own, since tables only keep their most recent rolls.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RollArchiveState {
+  pub table: String,
+  pub dice: (u8, u8),
+  pub shooter: Option<String>,
+  pub button: Option<u8>,
+  pub rolled_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DiceStatsState {
+  pub rolls: u32,
+  pub totals: Vec<(u8, u32)>,
+  pub faces: Vec<(u8, u32)>,
+  pub hot: Vec<u8>,
+  pub cold: Vec<u8>,
+  pub since_seven: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PlayerState {
   pub id: String,
   pub oid: String,
@@ -99,4 +223,58 @@ pub struct PlayerState {
   pub nickname: String,
   pub balance: u32,
   pub tables: Vec<String>,
+  #[serde(default)]
+  pub history: Vec<HistoryState>,
+}
+
+// Movements of a player's money that happen away from their own seat.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryState {
+  Settlement(SettlementState),
+  Transfer(TransferState),
+}
+
+impl HistoryState {
+  // Entries recorded before settlements were given ids have none.
+  pub fn id(&self) -> Option<&str> {
+    let id = match self {
+      HistoryState::Settlement(settlement) => settlement.id.as_str(),
+      HistoryState::Transfer(transfer) => transfer.id.as_str(),
+    };
+
+    Some(id).filter(|id| !id.is_empty())
+  }
+}
+
+// Bets a player left working when they stood up from a table, as they were settled without them. The amount is what
+// was paid back to the player's balance.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SettlementState {
+  #[serde(default)]
+  pub id: String,
+  pub table: String,
+  pub bets: Vec<(BetState, bool, u32)>,
+  pub amount: u32,
+  pub settled_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A settlement that has been taken off the table but not yet paid to the player. It is kept with the table until the
+// player's balance has been credited, so that a failed credit can be retried by the next roll.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PendingSettlementState {
+  pub player: String,
+  pub left: bool,
+  pub settlement: SettlementState,
+}
+
+// Chips sent from one player's seat to another's at the same table. Tips to the house have no recipient.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransferState {
+  pub id: String,
+  pub table: String,
+  pub from: String,
+  pub to: Option<String>,
+  pub amount: u32,
+  pub sent_at: chrono::DateTime<chrono::Utc>,
 }
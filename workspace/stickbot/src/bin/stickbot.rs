@@ -14,32 +14,16 @@ fn main() -> Result<()> {
     let addr = std::env::var(stickbot::constants::STICKBOT_HTTP_ADDR_ENV).unwrap_or_default();
     log::info!("spawning tide server on {}, connecting services", addr);
     let services = stickbot::Services::new().await?;
-    log::info!("services ready, creating application");
-    let mut app = tide::with_state(services);
-
-    app.at("/heartbeat").get(stickbot::routes::heartbeat);
-
-    app.at("/auth/start").get(stickbot::routes::auth::start);
-    app.at("/auth/logout").get(stickbot::routes::auth::logout);
-    app.at("/auth/complete").get(stickbot::routes::auth::complete);
-    app.at("/auth/identify").get(stickbot::routes::auth::identify);
-
-    app.at("/tables").get(stickbot::routes::tables::list);
-    app.at("/tables").post(stickbot::routes::tables::create);
-    app.at("/table").get(stickbot::routes::tables::find);
 
-    app.at("/delete-account").post(stickbot::routes::account::delete);
+    // With the in-memory queue, nothing outside of this process can see queued jobs, so the worker runs alongside the
+    // web server instead of as a separate boxbot process.
+    if stickbot::env::queue_backend() == stickbot::env::QueueBackend::Memory {
+      log::info!("using in-memory job queue, spawning worker");
+      async_std::task::spawn(stickbot::worker::run(services.clone()));
+    }
 
-    app.at("/leave-table").post(stickbot::routes::tables::leave);
-    app.at("/join-table").post(stickbot::routes::tables::join);
-
-    app.at("/bets").post(stickbot::routes::bets::create);
-    app.at("/rolls").post(stickbot::routes::rolls::create);
-
-    app.at("/job").get(stickbot::routes::jobs::find);
-
-    app.at("/admin/drop-tables").get(stickbot::routes::admin::drop_all);
-    app.at("/admin/set-balance").get(stickbot::routes::admin::set_balance);
+    log::info!("services ready, creating application");
+    let app = stickbot::routes::app(services);
 
     log::info!("application ready, spawning");
     app.listen(&addr).await?;
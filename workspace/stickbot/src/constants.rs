@@ -7,6 +7,9 @@ pub const MONGO_DB_APP_NAME: &'static str = "twowaiyo:stickbot";
 pub const MONGO_DB_TABLE_COLLECTION_NAME: &'static str = "stickbot.tables";
 pub const MONGO_DB_TABLE_LIST_COLLECTION_NAME: &'static str = "stickbot.table-list";
 pub const MONGO_DB_PLAYER_COLLECTION_NAME: &'static str = "stickbot.players";
+pub const MONGO_DB_HAND_COLLECTION_NAME: &'static str = "stickbot.hands";
+pub const MONGO_DB_ROLL_ARCHIVE_COLLECTION_NAME: &'static str = "stickbot.rolls";
+pub const MONGO_DB_TRANSFER_COLLECTION_NAME: &'static str = "stickbot.transfers";
 
 pub const AUTH_O_AUTH_URI_ENV: &'static str = "STICKBOT_AUTH_0_AUTH_URI";
 pub const AUTH_O_TOKEN_URI_ENV: &'static str = "STICKBOT_AUTH_0_TOKEN_URI";
@@ -28,12 +31,28 @@ pub const REDIS_PASSWORD_ENV: &'static str = "STICKBOT_REDIS_PASSWORD";
 pub const STICKBOT_SESSION_STORE: &'static str = "stickbot:sessions";
 pub const STICKBOT_JOB_QUEUE: &'static str = "stickbot:jobs";
 pub const STICKBOT_JOB_RESULTS: &'static str = "stickbot:job_results";
+pub const STICKBOT_TABLE_EVENTS: &'static str = "stickbot:table_events";
 
 pub const EMPTY_RESPONSE: &'static str = "";
 
 pub const BOXBOT_DELAY_ENV: &'static str = "BOXBOT_WORKER_DELAY";
+pub const STICKBOT_JOB_QUEUE_BACKEND_ENV: &'static str = "STICKBOT_JOB_QUEUE_BACKEND";
+
+pub const STICKBOT_STORAGE_BACKEND_ENV: &'static str = "STICKBOT_STORAGE_BACKEND";
+pub const STICKBOT_SQLITE_PATH_ENV: &'static str = "STICKBOT_SQLITE_PATH";
+pub const STICKBOT_DEFAULT_SQLITE_PATH: &'static str = "stickbot.db";
 
 pub const STICKBOT_ADMIN_EMAILS_ENV: &'static str = "STICKBOT_ADMIN_EMAILS";
 
 pub const STICKBOT_DEFAULT_MAX_ACTIVE_TABLES_PER_PLAYER: usize = 2;
 pub const STICKBOT_MAX_ACTIVE_TABLES_PER_PLAYER_ENV: &'static str = "STICKBOT_MAX_ACTIVE_TABLES_PER_PLAYER";
+
+// How long an event stream may go quiet before something is written to it, to find out whether the client is still there.
+pub const STICKBOT_EVENT_KEEP_ALIVE: std::time::Duration = std::time::Duration::from_secs(15);
+
+pub const STICKBOT_DEFAULT_HAND_PAGE_SIZE: i64 = 10;
+pub const STICKBOT_MAX_ROLL_HISTORY: usize = 100;
+pub const STICKBOT_MAX_STATS_ROLLS: usize = 1000;
+
+pub const STICKBOT_DEFAULT_MAX_TRANSFER: u32 = 5000;
+pub const STICKBOT_MAX_TRANSFER_ENV: &'static str = "STICKBOT_MAX_TRANSFER";
@@ -6,8 +6,8 @@ use super::constants;
 
 pub use mongodb::bson;
 pub use mongodb::bson::doc;
-pub use mongodb::options::{FindOneAndReplaceOptions, FindOneAndUpdateOptions, ReturnDocument, UpdateModifications};
-pub use mongodb::{Client, Collection};
+pub use mongodb::options::{FindOneAndReplaceOptions, FindOneAndUpdateOptions, FindOptions, ReturnDocument};
+pub use mongodb::{Client, Collection, IndexModel};
 
 pub fn mongo_error(error: mongodb::error::Error) -> Error {
   Error::new(ErrorKind::Other, format!("{}", error))
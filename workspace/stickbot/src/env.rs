@@ -22,11 +22,55 @@ impl std::fmt::Display for JobStore {
   }
 }
 
+// Where jobs are queued. The in-memory queue only works when the worker runs inside the web process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueueBackend {
+  Redis,
+  Memory,
+  Sqlite,
+}
+
+pub fn queue_backend() -> QueueBackend {
+  match std::env::var(constants::STICKBOT_JOB_QUEUE_BACKEND_ENV).as_deref() {
+    Ok("memory") => QueueBackend::Memory,
+    Ok("sqlite") => QueueBackend::Sqlite,
+    _ => QueueBackend::Redis,
+  }
+}
+
+// Where tables, players and the table index are kept. Sqlite is only available when built with the `sqlite` feature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageBackend {
+  Mongo,
+  Sqlite,
+}
+
+pub fn storage_backend() -> StorageBackend {
+  match std::env::var(constants::STICKBOT_STORAGE_BACKEND_ENV).as_deref() {
+    Ok("sqlite") => StorageBackend::Sqlite,
+    _ => StorageBackend::Mongo,
+  }
+}
+
+pub fn sqlite_path() -> String {
+  std::env::var(constants::STICKBOT_SQLITE_PATH_ENV).unwrap_or(constants::STICKBOT_DEFAULT_SQLITE_PATH.to_string())
+}
+
+pub fn max_transfer() -> u32 {
+  std::env::var(constants::STICKBOT_MAX_TRANSFER_ENV)
+    .ok()
+    .and_then(|value| value.parse().ok())
+    .unwrap_or(constants::STICKBOT_DEFAULT_MAX_TRANSFER)
+}
+
 #[derive(Debug, Clone)]
 pub enum Collection {
   TableList,
   Tables,
   Players,
+  Hands,
+  RollArchive,
+  Transfers,
 }
 
 impl std::fmt::Display for Collection {
@@ -40,6 +84,13 @@ impl std::fmt::Display for Collection {
       }
       Collection::TableList => std::env::var("STICKBOT_TABLE_LIST_COLLECTION")
         .unwrap_or(constants::MONGO_DB_TABLE_LIST_COLLECTION_NAME.to_string()),
+      Collection::Hands => {
+        std::env::var("STICKBOT_HAND_COLLECTION").unwrap_or(constants::MONGO_DB_HAND_COLLECTION_NAME.to_string())
+      }
+      Collection::RollArchive => std::env::var("STICKBOT_ROLL_ARCHIVE_COLLECTION")
+        .unwrap_or(constants::MONGO_DB_ROLL_ARCHIVE_COLLECTION_NAME.to_string()),
+      Collection::Transfers => std::env::var("STICKBOT_TRANSFER_COLLECTION")
+        .unwrap_or(constants::MONGO_DB_TRANSFER_COLLECTION_NAME.to_string()),
     };
 
     write!(formatter, "{}", v)
@@ -0,0 +1,114 @@
+use http_types::{Method, Request, Url};
+
+use bankah::state::PlayerState;
+
+use crate::Services;
+
+// How many jobs are worked while waiting on a single job to complete; processors may queue follow-up jobs (e.g
+// reindexing) ahead of the one being waited on.
+const MAX_WORK_ATTEMPTS: usize = 10;
+
+pub(crate) struct Reply {
+  pub(crate) status: u16,
+  pub(crate) body: String,
+}
+
+impl Reply {
+  pub(crate) fn json(&self) -> serde_json::Value {
+    serde_json::from_str(&self.body).unwrap_or_else(|error| panic!("invalid json '{}' - {}", self.body, error))
+  }
+}
+
+// The full route table over in-memory services. Requests are handed to the app directly rather than over a socket,
+// and jobs are only processed when a test asks for them to be.
+pub(crate) struct Harness {
+  services: Services,
+  app: tide::Server<Services>,
+}
+
+impl Harness {
+  pub(crate) fn new() -> Self {
+    let services = Services::in_memory();
+    let app = crate::routes::app(services.clone());
+    Harness { services, app }
+  }
+
+  pub(crate) fn services(&self) -> &Services {
+    &self.services
+  }
+
+  // Stores a new player and signs them in, returning the player along with their session token.
+  pub(crate) async fn player(&self, balance: u32) -> (PlayerState, String) {
+    let player = PlayerState::from(&twowaiyo::Player::with_balance(balance));
+    let player = PlayerState {
+      oid: player.id.clone(),
+      ..player
+    };
+    let player = self
+      .services
+      .players()
+      .find_or_create(&player)
+      .await
+      .expect("unable to create player");
+    let token = crate::auth::Claims::for_player(&player.oid, &player.id)
+      .encode()
+      .expect("unable to encode token");
+
+    self
+      .services
+      .start_session(&token, &player.id)
+      .await
+      .expect("unable to start session");
+
+    (player, token)
+  }
+
+  pub(crate) async fn get(&self, path: &str, session: Option<&str>) -> Reply {
+    self.send(Method::Get, path, session, None).await
+  }
+
+  pub(crate) async fn post(&self, path: &str, session: Option<&str>, body: serde_json::Value) -> Reply {
+    self.send(Method::Post, path, session, Some(body)).await
+  }
+
+  async fn send(&self, method: Method, path: &str, session: Option<&str>, body: Option<serde_json::Value>) -> Reply {
+    let url = Url::parse("http://stickbot.test")
+      .and_then(|base| base.join(path))
+      .expect("invalid path");
+    let mut request = Request::new(method, url);
+
+    if let Some(token) = session {
+      request.insert_header(
+        "Cookie",
+        format!("{}={}", crate::constants::STICKBOT_COOKIE_NAME, token),
+      );
+    }
+
+    if let Some(body) = body {
+      request.set_body(http_types::Body::from_json(&body).expect("invalid body"));
+    }
+
+    let mut response: http_types::Response = self.app.respond(request).await.expect("unable to respond");
+    let body = response.body_string().await.expect("unable to read body");
+
+    Reply {
+      status: response.status().into(),
+      body,
+    }
+  }
+
+  // Works queued jobs until the one given has a result, then returns that result as seen through the api.
+  pub(crate) async fn complete(&self, session: &str, job: &str) -> serde_json::Value {
+    for _ in 0..MAX_WORK_ATTEMPTS {
+      if self.services.job_result(job).await.expect("unable to fetch").is_some() {
+        break;
+      }
+
+      crate::worker::work(&self.services).await.expect("unable to work job");
+    }
+
+    let reply = self.get(&format!("/job?id={}", job), Some(session)).await;
+    assert_eq!(reply.status, 200, "job lookup failed - {}", reply.body);
+    reply.json()
+  }
+}
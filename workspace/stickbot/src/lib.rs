@@ -1,12 +1,21 @@
 mod auth;
 mod db;
+#[cfg(test)]
+mod harness;
 mod names;
+mod pubsub;
+mod queue;
 mod redis;
 mod services;
+mod sessions;
 mod web;
 
 pub mod constants;
 pub mod env;
 pub mod processors;
 pub mod routes;
+pub mod storage;
+pub mod worker;
+pub use pubsub::Subscription;
+pub use queue::{JobQueue, MemoryQueue};
 pub use services::Services;
@@ -8,8 +8,9 @@ pub fn generate() -> Result<String> {
   getrandom::getrandom(&mut buf)?;
   let (i, j) = (buf[0], buf[1]);
 
-  let left = LEFT_DATA.lines().nth(i as usize);
-  let right = RIGHT_DATA.lines().nth(j as usize);
+  // The random bytes can land past the end of either list; wrap them around rather than failing.
+  let left = LEFT_DATA.lines().nth(i as usize % LEFT_DATA.lines().count());
+  let right = RIGHT_DATA.lines().nth(j as usize % RIGHT_DATA.lines().count());
 
   left
     .zip(right)
@@ -1,27 +1,11 @@
-use async_std::stream::StreamExt;
-
 use bankah::jobs::{JobError, TableAdminJob, TableJob, TableJobOutput};
 
-use crate::db::{doc, FindOneAndReplaceOptions, ReturnDocument};
 use crate::Services;
 
 pub async fn reindex(services: &Services, job: &TableAdminJob) -> Result<TableJobOutput, JobError> {
   log::info!("attempting to reindex table populations - {:?}", job);
-  let tables = services.tables();
-  let destination = format!("{}", crate::env::Collection::TableList);
 
-  let pipeline = vec![
-    doc! { "$project": { "id": 1, "name": 1, "seats": { "$objectToArray": "$seats" } } },
-    doc! { "$project": { "id": 1, "name": 1, "population": {
-      "$map": {
-        "input": "$seats",
-        "as": "seat",
-        "in": ["$$seat.k", "$$seat.v.nickname"],
-      },
-    } } },
-    doc! { "$merge": { "into": destination } },
-  ];
-  tables.aggregate(pipeline, None).await.map_err(|error| {
+  services.table_index().reindex().await.map_err(|error| {
     log::warn!("unable to perform aggregate - {}", error);
     JobError::Retryable
   })?;
@@ -31,42 +15,22 @@ pub async fn reindex(services: &Services, job: &TableAdminJob) -> Result<TableJo
 pub async fn cleanup(services: &Services, id: &String) -> Result<TableJobOutput, JobError> {
   log::debug!("cleaning up player '{}'", id);
 
-  let mut tables = match services
-    .tables()
-    .find(doc! { format!("seats.{}", id): { "$exists": true } }, None)
-    .await
-  {
+  let tables = match services.tables().seated(id).await {
     Err(error) => {
       log::warn!("unable to find any tables for user - {}", error);
       return Ok(TableJobOutput::AdminOk);
     }
-    Ok(cursor) => cursor,
+    Ok(tables) => tables,
   };
 
-  while let Some(doc) = tables.next().await {
-    let mut state = match doc {
-      Err(error) => {
-        log::warn!("error loading next able - {}", error);
-        continue;
-      }
-      Ok(table) => table,
-    };
-
+  for mut state in tables {
     state.seats = state
       .seats
       .into_iter()
       .filter(|(seat, _)| &seat.to_string() != id)
       .collect();
 
-    let opts = FindOneAndReplaceOptions::builder()
-      .return_document(ReturnDocument::After)
-      .build();
-
-    if let Err(error) = services
-      .tables()
-      .find_one_and_replace(crate::db::lookup_for_uuid(&state.id), &state, opts)
-      .await
-    {
+    if let Err(error) = services.tables().save(&state).await {
       log::warn!("failed cleanup '{}' on table '{}': {}", id, &state.id, error);
     }
 
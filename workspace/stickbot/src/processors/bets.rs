@@ -1,25 +1,22 @@
-use bankah::jobs::{BetFailureReason, BetJob, JobError, TableJobOutput};
-use bankah::state::{BetState, PlayerState, TableState};
-use twowaiyo::errors::{PassLineNotEstablishedViolation, PlayerBetViolation, RuleViolation};
-
-use crate::db;
-
-fn failure_from_violation(violation: &RuleViolation) -> TableJobOutput {
-  let inner = match violation {
-    RuleViolation::PlayerBetViolation(PlayerBetViolation::InsufficientFunds) => BetFailureReason::InsufficientFunds,
-    RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::ComeBet) => BetFailureReason::InvalidComeBet,
-    _ => BetFailureReason::Other,
-  };
-
-  TableJobOutput::BetFailed(inner)
-}
-
-fn apply_bet(ps: PlayerState, mut ts: TableState, bs: BetState) -> Result<TableState, RuleViolation> {
+use std::convert::TryFrom;
+
+use bankah::jobs::{BetFailure, BetJob, JobError, TableJobOutput};
+use bankah::state::{PlayerState, TableState};
+use twowaiyo::errors::RuleViolation;
+
+fn apply_bet(
+  ps: PlayerState,
+  mut ts: TableState,
+  table: twowaiyo::Table<twowaiyo::RandomRoller>,
+  bet: twowaiyo::Bet,
+  odds: Option<u32>,
+) -> Result<TableState, RuleViolation> {
   let player = twowaiyo::Player::from(&ps);
-  let bet = twowaiyo::Bet::from(&bs);
-  let table = twowaiyo::Table::from(&ts)
-    .bet(&player, &bet)
-    .map_err(|error| error.error)?;
+  let table = match odds {
+    Some(odds) => table.put(&player, &bet, odds),
+    None => table.bet(&player, &bet),
+  }
+  .map_err(|error| error.error)?;
 
   let mut next = TableState::from(&table);
 
@@ -43,17 +40,15 @@ pub async fn bet<'a>(services: &crate::Services, job: &BetJob) -> Result<TableJo
   let players = services.players();
 
   log::trace!("processing bet job '{:?}'", job);
-  let lookup = db::lookup_for_uuid(&job.table);
-
   let ts = tables
-    .find_one(lookup, None)
+    .find(&job.table)
     .await
     .map_err(|error| {
       log::warn!("unable to query for table - {}", error);
       JobError::Retryable
     })?
     .ok_or_else(|| {
-      log::warn!("unable to find table via {:?}", db::lookup_for_uuid(&job.table));
+      log::warn!("unable to find table '{}'", job.table);
       JobError::Terminal("table-not-found".into())
     })?;
 
@@ -66,8 +61,25 @@ pub async fn bet<'a>(services: &crate::Services, job: &BetJob) -> Result<TableJo
 
   log::trace!("loaded table state - {:?}", ts);
 
+  // Bets come straight from the web; one that doesn't describe a real bet is a failed bet, not a failed job.
+  let bet = match twowaiyo::Bet::try_from(&job.bet) {
+    Ok(bet) => bet,
+    Err(error) => {
+      log::warn!("rejecting invalid bet {:?} - {}", job.bet, error);
+      return Ok(TableJobOutput::BetFailed(BetFailure {
+        code: "invalid_bet".to_string(),
+        message: error.to_string(),
+      }));
+    }
+  };
+
+  let table = twowaiyo::Table::try_from(&ts).map_err(|error| {
+    log::warn!("unable to load table '{}' - {}", ts.id, error);
+    JobError::Terminal(format!("invalid-table - {}", error))
+  })?;
+
   let ps = players
-    .find_one(db::doc! { "id": job.player.to_string() }, None)
+    .find(&job.player)
     .await
     .map_err(|error| {
       log::warn!("unable to query for player - {}", error);
@@ -83,20 +95,74 @@ pub async fn bet<'a>(services: &crate::Services, job: &BetJob) -> Result<TableJo
   // At this point we've loaded everything and only need to apply the logic. This is a failable operation, but for
   // logical reasons assocaited with the game, not so much the "system". If the processing fails here, the job is
   // still considered as "success", it just carries a failed bet.
-  let next = match apply_bet(ps, ts, job.bet.clone()) {
-    Err(violation) => return Ok(failure_from_violation(&violation)),
+  let next = match apply_bet(ps, ts, table, bet, job.odds) {
+    Err(violation) => return Ok(TableJobOutput::BetFailed(super::rejection(&violation))),
     Ok(next) => next,
   };
 
-  tables
-    .find_one_and_replace(db::lookup_for_uuid(&next.id), &next, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to replace table state - {}", error);
-      JobError::Retryable
-    })?;
+  tables.save(&next).await.map_err(|error| {
+    log::warn!("unable to replace table state - {}", error);
+    JobError::Retryable
+  })?;
 
   log::info!("new table '{}': {:?}'", next.id, next);
+  super::seats_changed(services, &next).await;
 
   Ok(TableJobOutput::BetProcessed)
 }
+
+#[cfg(test)]
+mod test {
+  use super::super::{seed_player, seed_table};
+  use super::bet;
+  use crate::Services;
+  use bankah::jobs::{BetJob, TableJobOutput};
+  use bankah::state::{BetState, RaceType};
+
+  fn pass(player: &str, table: &str, version: &str) -> BetJob {
+    BetJob {
+      bet: BetState::Race(RaceType::Pass, 50, None),
+      odds: None,
+      player: player.to_string(),
+      table: table.to_string(),
+      version: version.to_string(),
+    }
+  }
+
+  #[test]
+  fn test_bet_processed() {
+    let services = Services::in_memory();
+
+    let (output, stale, seat) = async_std::task::block_on(async {
+      let player = seed_player(&services, 500).await;
+      let id = seed_table(&services, &player, 200).await;
+      let nonce = services.tables().find(&id).await.unwrap().unwrap().nonce;
+      let output = bet(&services, &pass(&player.id, &id, &nonce)).await;
+      let stale = bet(&services, &pass(&player.id, &id, "stale")).await;
+      let table = services.tables().find(&id).await.unwrap().unwrap();
+      (output, stale, table.seats.get(&player.id).cloned())
+    });
+
+    assert!(matches!(output, Ok(TableJobOutput::BetProcessed)));
+    assert!(matches!(stale, Ok(TableJobOutput::BetStale)));
+    assert_eq!(seat.as_ref().map(|s| s.balance), Some(150));
+    assert_eq!(seat.map(|s| s.bets.len()), Some(1));
+  }
+
+  #[test]
+  fn test_bet_over_balance() {
+    let services = Services::in_memory();
+
+    let output = async_std::task::block_on(async {
+      let player = seed_player(&services, 500).await;
+      let id = seed_table(&services, &player, 20).await;
+      let nonce = services.tables().find(&id).await.unwrap().unwrap().nonce;
+      bet(&services, &pass(&player.id, &id, &nonce)).await
+    });
+
+    match output {
+      Ok(TableJobOutput::BetFailed(failure)) => assert_eq!(failure.code, "insufficient_funds"),
+      other => panic!("expected a failed bet, got {:?}", other),
+    }
+  }
+}
@@ -0,0 +1,248 @@
+use std::convert::TryFrom;
+
+use bankah::jobs::{ChipsJob, JobError, TableJobOutput};
+use bankah::state::{PlayerState, TableState};
+use twowaiyo::errors::{PlayerBetViolation, RuleViolation};
+use twowaiyo::{Player, RandomRoller, Table};
+
+use super::seats::find_player;
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+  Rebuy,
+  CashOut,
+}
+
+// Moves chips between the player's account and their seat. Only the seat's balance is taken from the engine, along with
+// clearing the orphaned flag once chips are added (a player buying back in has a seat again); the rest of the stored
+// seat is left as it was.
+fn apply_chips(
+  mut ts: TableState,
+  mut ps: PlayerState,
+  table: Table<RandomRoller>,
+  direction: Direction,
+  amount: u32,
+) -> Result<(TableState, PlayerState), RuleViolation> {
+  let mut player = Player::from(&ps);
+
+  let table = match direction {
+    Direction::Rebuy => table.rebuy(&mut player, amount),
+    Direction::CashOut => table.cash_out(&mut player, amount),
+  }
+  .map_err(|error| error.error)?;
+
+  ps.balance = player.balance;
+
+  if let (Some(seat), Some(balance)) = (ts.seats.get_mut(&ps.id), table.balance(&ps.id)) {
+    seat.balance = balance;
+  }
+
+  if let (Some(seat), Direction::Rebuy) = (ts.seats.get_mut(&ps.id), direction) {
+    seat.orphaned = false;
+  }
+
+  Ok((ts, ps))
+}
+
+async fn chips(services: &crate::Services, job: &ChipsJob, direction: Direction) -> Result<TableJobOutput, JobError> {
+  log::debug!(
+    "moving chips for player '{}' at table '{}' ({:?})",
+    job.player,
+    job.table,
+    direction
+  );
+
+  let player = find_player(services, &job.player).await?;
+
+  let state = services
+    .tables()
+    .find(&job.table)
+    .await
+    .map_err(|error| {
+      log::warn!("unable to find table - {}", error);
+      JobError::Retryable
+    })?
+    .ok_or_else(|| {
+      log::warn!("unable to find table '{}'", job.table);
+      JobError::Terminal(format!("unable to find table '{}'", job.table))
+    })?;
+
+  let table = Table::try_from(&state).map_err(|error| {
+    log::warn!("unable to load table '{}' - {}", state.id, error);
+    JobError::Terminal(format!("invalid-table - {}", error))
+  })?;
+
+  let (ts, ps) = match apply_chips(state, player, table, direction, job.amount) {
+    Err(violation) => return Ok(TableJobOutput::ChipsFailed(super::rejection(&violation))),
+    Ok(next) => next,
+  };
+
+  // The account is moved by the difference rather than set outright, so anything credited since the player was read
+  // is kept. Should the table not save, the account is put back the way it was.
+  let change = i64::from(job.amount);
+  let change = match direction {
+    Direction::Rebuy => -change,
+    Direction::CashOut => change,
+  };
+
+  let adjusted = services.players().adjust(&ps.id, change).await.map_err(|error| {
+    log::warn!("unable to persist new player balance - {}", error);
+    JobError::Retryable
+  })?;
+
+  if !adjusted {
+    log::warn!("player '{}' balance changed while moving chips", ps.id);
+    let violation = RuleViolation::PlayerBetViolation(PlayerBetViolation::InsufficientFunds);
+    return Ok(TableJobOutput::ChipsFailed(super::rejection(&violation)));
+  }
+
+  if let Err(error) = services.tables().save(&ts).await {
+    log::warn!("unable to persist table updates - {}", error);
+
+    if let Err(error) = services.players().adjust(&ps.id, -change).await {
+      log::error!(
+        "unable to restore balance of player '{}' ({}) - {}",
+        ps.id,
+        -change,
+        error
+      );
+    }
+
+    return Err(JobError::Terminal(format!("table '{}' not updated - {}", ts.id, error)));
+  }
+
+  super::seats_changed(services, &ts).await;
+
+  Ok(match direction {
+    Direction::Rebuy => TableJobOutput::RebuyOk,
+    Direction::CashOut => TableJobOutput::CashOutOk,
+  })
+}
+
+pub async fn rebuy(services: &crate::Services, job: &ChipsJob) -> Result<TableJobOutput, JobError> {
+  chips(services, job, Direction::Rebuy).await
+}
+
+pub async fn cash_out(services: &crate::Services, job: &ChipsJob) -> Result<TableJobOutput, JobError> {
+  chips(services, job, Direction::CashOut).await
+}
+
+#[cfg(test)]
+mod test {
+  use super::super::{seed_player, seed_table};
+  use super::{apply_chips, rebuy, Direction};
+  use crate::storage::{MemoryStore, TableRepository};
+  use crate::Services;
+  use async_std::sync::Arc;
+  use async_trait::async_trait;
+  use bankah::jobs::{ChipsJob, JobError};
+  use bankah::state::{PlayerState, TableState};
+  use std::convert::TryFrom;
+  use std::io::{Error, ErrorKind, Result};
+  use std::sync::atomic::{AtomicBool, Ordering};
+  use twowaiyo::{Player, Table};
+
+  // Tables kept in memory, which can't be saved while `failing` is set.
+  #[derive(Clone, Default)]
+  struct FlakyTables {
+    store: MemoryStore,
+    failing: Arc<AtomicBool>,
+  }
+
+  #[async_trait]
+  impl TableRepository for FlakyTables {
+    async fn find(&self, id: &str) -> Result<Option<TableState>> {
+      TableRepository::find(&self.store, id).await
+    }
+
+    async fn save(&self, table: &TableState) -> Result<()> {
+      if self.failing.load(Ordering::SeqCst) {
+        return Err(Error::new(ErrorKind::Other, "save-failed"));
+      }
+
+      self.store.save(table).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+      TableRepository::delete(&self.store, id).await
+    }
+
+    async fn seated(&self, player: &str) -> Result<Vec<TableState>> {
+      self.store.seated(player).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+      self.store.clear().await
+    }
+  }
+
+  fn seated(balance: u32, buy_in: u32) -> (TableState, PlayerState) {
+    let mut player = Player::with_balance(balance);
+    let table = Table::default().buy_in(&mut player, buy_in).unwrap();
+    (TableState::from(&table), PlayerState::from(&player))
+  }
+
+  #[test]
+  fn test_rebuy() {
+    let (ts, ps) = seated(500, 100);
+    let table = Table::try_from(&ts).unwrap();
+    let (ts, ps) = apply_chips(ts, ps, table, Direction::Rebuy, 150).unwrap();
+    assert_eq!(ps.balance, 250);
+    assert_eq!(ts.seats.get(&ps.id).map(|seat| seat.balance), Some(250));
+  }
+
+  #[test]
+  fn test_rebuy_into_orphaned_seat() {
+    let (mut ts, ps) = seated(500, 100);
+    if let Some(seat) = ts.seats.get_mut(&ps.id) {
+      seat.balance = 0;
+      seat.orphaned = true;
+    }
+
+    let table = Table::try_from(&ts).unwrap();
+    let (ts, ps) = apply_chips(ts, ps, table, Direction::Rebuy, 50).unwrap();
+    let seat = ts.seats.get(&ps.id).expect("missing seat");
+    assert_eq!(ps.balance, 350);
+    assert_eq!(seat.balance, 50);
+    assert!(!seat.orphaned);
+  }
+
+  #[test]
+  fn test_cash_out_over_balance() {
+    let (ts, ps) = seated(500, 100);
+    let table = Table::try_from(&ts).unwrap();
+    let violation = apply_chips(ts, ps, table, Direction::CashOut, 150).unwrap_err();
+    assert_eq!(violation.code(), "insufficient_funds");
+  }
+
+  #[test]
+  fn test_failed_save_restores_balance() {
+    let tables = FlakyTables::default();
+    let services = Services::in_memory().with_tables(Arc::new(tables.clone()));
+
+    async_std::task::block_on(async {
+      let player = seed_player(&services, 500).await;
+      let table = seed_table(&services, &player, 100).await;
+      let before = services.players().find(&player.id).await.unwrap().unwrap().balance;
+
+      tables.failing.store(true, Ordering::SeqCst);
+      let job = ChipsJob {
+        table: table.clone(),
+        player: player.id.clone(),
+        amount: 150,
+      };
+      assert!(matches!(rebuy(&services, &job).await, Err(JobError::Terminal(_))));
+
+      let after = services.players().find(&player.id).await.unwrap().unwrap().balance;
+      let seat = services.tables().find(&table).await.unwrap().unwrap().seats[&player.id].balance;
+      assert_eq!(after, before);
+      assert_eq!(seat, 100);
+
+      // Once the table can be saved, the chips move as usual.
+      tables.failing.store(false, Ordering::SeqCst);
+      assert!(rebuy(&services, &job).await.is_ok());
+      let after = services.players().find(&player.id).await.unwrap().unwrap().balance;
+      assert_eq!(after, before - 150);
+    });
+  }
+}
@@ -1,8 +1,64 @@
 mod bets;
+mod chips;
 mod rolls;
 mod seats;
+mod transfers;
 
 pub mod admin;
 pub use bets::bet;
+pub use chips::{cash_out, rebuy};
 pub use rolls::roll;
 pub use seats::{create, sit, stand};
+pub use transfers::transfer;
+
+// Rule violations are not job failures; they are reported back to the player in the job's output.
+fn rejection(violation: &twowaiyo::errors::RuleViolation) -> bankah::jobs::BetFailure {
+  bankah::jobs::BetFailure {
+    code: violation.code().to_string(),
+    message: violation.to_string(),
+  }
+}
+
+// Events are published once the change they describe has been saved; failing to publish never fails the job.
+async fn publish(services: &crate::Services, table: &str, event: bankah::events::TableEvent) {
+  if let Err(error) = services.publish(table, &event).await {
+    log::warn!("unable to publish '{}' for table '{}' - {}", event.name(), table, error);
+  }
+}
+
+async fn seats_changed(services: &crate::Services, table: &bankah::state::TableState) {
+  let seats = table.seats.clone();
+  publish(services, &table.id, bankah::events::TableEvent::SeatsChanged { seats }).await
+}
+
+#[cfg(test)]
+async fn seed_player(services: &crate::Services, balance: u32) -> bankah::state::PlayerState {
+  let player = bankah::state::PlayerState::from(&twowaiyo::Player::with_balance(balance));
+  let player = bankah::state::PlayerState {
+    oid: player.id.clone(),
+    ..player
+  };
+  services
+    .players()
+    .find_or_create(&player)
+    .await
+    .expect("unable to seed player")
+}
+
+// Creates a table with the player sat at it, returning the table's id.
+#[cfg(test)]
+async fn seed_table(services: &crate::Services, player: &bankah::state::PlayerState, buy_in: u32) -> String {
+  let job = bankah::jobs::CreateJob {
+    player: player.id.clone(),
+    variant: Default::default(),
+    field: Default::default(),
+    max_odds: None,
+    history: None,
+    buy_in: Some(buy_in),
+  };
+
+  match create(services, &job).await {
+    Ok(bankah::jobs::TableJobOutput::TableCreated(id)) => id,
+    other => panic!("unable to seed table - {:?}", other),
+  }
+}
@@ -1,19 +1,87 @@
-use crate::db;
+use std::convert::TryFrom;
 
+use bankah::events::TableEvent;
 use bankah::jobs::{JobError, RollJob, TableJobOutput};
-use bankah::state::{BetState, TableState};
+use bankah::state::{
+  BetState, HandState, HistoryState, PendingSettlementState, RollArchiveState, SettlementState, TableState,
+};
 use twowaiyo::Table;
 
-fn apply_roll(mut state: TableState) -> Result<TableState, JobError> {
-  let table = Table::from(&state);
+// Everything a roll changed that needs saving: the table itself (settlements owed to players who are no longer at the
+// table are kept with it, in `pending`), and any hands it finished.
+#[derive(Debug)]
+struct Rolled {
+  table: TableState,
+  hands: Vec<HandState>,
+}
+
+fn apply_roll(mut state: TableState) -> Result<Rolled, JobError> {
+  let table = Table::try_from(&state).map_err(|error| {
+    log::warn!("unable to load table '{}' - {}", state.id, error);
+    JobError::Terminal(format!("invalid-table - {}", error))
+  })?;
   let mut rolled = table.roll();
   let mut next = TableState::from(&rolled.table);
 
+  let hands = rolled
+    .table
+    .take_hands()
+    .iter()
+    .map(|hand| HandState {
+      table: state.id.clone(),
+      nickname: state
+        .seats
+        .get(hand.shooter())
+        .map(|seat| seat.nickname.clone())
+        .unwrap_or_default(),
+      ..HandState::from(hand)
+    })
+    .collect();
+
+  let payouts = rolled.table.take_payouts();
+  let settlements: Vec<PendingSettlementState> = state
+    .seats
+    .iter()
+    .filter(|(_, seat)| seat.orphaned)
+    .filter_map(|(uuid, _)| {
+      let bets = rolled
+        .results
+        .get(uuid)
+        .map(|runs| {
+          runs
+            .iter()
+            .map(|(bet, won, amount)| (BetState::from(bet), won, amount))
+            .collect::<Vec<(BetState, bool, u32)>>()
+        })
+        .unwrap_or_default();
+      let left = !next.seats.contains_key(uuid);
+
+      if bets.is_empty() && !left {
+        return None;
+      }
+
+      let settlement = SettlementState {
+        id: uuid::Uuid::new_v4().to_string(),
+        table: state.id.clone(),
+        bets,
+        amount: payouts.get(uuid).copied().unwrap_or_default(),
+        settled_at: chrono::Utc::now(),
+      };
+
+      Some(PendingSettlementState {
+        player: uuid.clone(),
+        left,
+        settlement,
+      })
+    })
+    .collect();
+
+  // Seats the engine has let go of (orphaned seats whose last bet was settled) are dropped.
   state.seats = state
     .seats
     .into_iter()
-    .map(|(uuid, mut seat)| {
-      let matching = next.seats.remove(&uuid).unwrap_or(seat.clone());
+    .filter_map(|(uuid, mut seat)| {
+      let matching = next.seats.remove(&uuid)?;
       let mut movement = rolled.results.remove(&uuid).unwrap_or_default();
       log::trace!("applying update for seat '{}' (moves {:?})", uuid, movement);
       let mut history = movement.map(|(bet, status, amount)| (BetState::from(&bet), status, amount));
@@ -21,8 +89,9 @@ fn apply_roll(mut state: TableState) -> Result<TableState, JobError> {
       seat.balance = matching.balance;
       seat.bets = matching.bets;
       seat.history = seat.history.into_iter().chain(&mut history).collect();
+      seat.orphaned = matching.orphaned;
 
-      (uuid, seat)
+      Some((uuid, seat))
     })
     .collect();
 
@@ -30,16 +99,65 @@ fn apply_roll(mut state: TableState) -> Result<TableState, JobError> {
   state.nonce = uuid::Uuid::new_v4().to_string();
   state.roller = next.roller;
   state.rolls = next.rolls;
+  state.hand = next.hand;
+  state.pending.extend(settlements);
 
-  Ok(state)
+  Ok(Rolled { table: state, hands })
 }
 
-pub async fn roll(services: &crate::Services, job: &RollJob) -> Result<TableJobOutput, JobError> {
-  let lookup = db::lookup_for_uuid(&job.table);
+// Pays what an orphaned seat won back to the player, and records it in their history.
+async fn settle(services: &crate::Services, pending: &PendingSettlementState) -> std::io::Result<()> {
+  let entry = HistoryState::Settlement(pending.settlement.clone());
+  let left = Some(pending.settlement.table.as_str()).filter(|_| pending.left);
+
+  services
+    .players()
+    .credit(&pending.player, pending.settlement.amount, &entry, left)
+    .await
+}
+
+// Pays every settlement waiting on the table, returning the ids of those that were paid. Anything that can't be paid
+// stays with the table for the next roll to try again.
+pub(super) async fn settle_pending(services: &crate::Services, table: &TableState) -> Vec<String> {
+  let mut paid = Vec::with_capacity(table.pending.len());
+
+  for pending in &table.pending {
+    log::info!(
+      "settling orphaned seat for '{}' - {:?}",
+      pending.player,
+      pending.settlement
+    );
 
+    if let Err(error) = settle(services, pending).await {
+      log::warn!("unable to settle orphaned seat for '{}' - {}", pending.player, error);
+      continue;
+    }
+
+    paid.push(pending.settlement.id.clone());
+
+    let (player, amount) = (pending.player.clone(), pending.settlement.amount);
+    super::publish(services, &table.id, TableEvent::Settled { player, amount }).await;
+  }
+
+  paid
+}
+
+// Takes paid settlements off the table. Credits are only ever applied once, so if this fails the next roll will find
+// them already paid and try again.
+pub(super) async fn clear_pending(services: &crate::Services, id: &str, paid: &[String]) -> std::io::Result<()> {
+  let mut table = match services.tables().find(id).await? {
+    Some(table) => table,
+    None => return Ok(()),
+  };
+
+  table.pending.retain(|pending| !paid.contains(&pending.settlement.id));
+  services.tables().save(&table).await
+}
+
+pub async fn roll(services: &crate::Services, job: &RollJob) -> Result<TableJobOutput, JobError> {
   let start = services
     .tables()
-    .find_one(lookup, None)
+    .find(&job.table)
     .await
     .map_err(|error| {
       log::warn!("failed table query - {}", error);
@@ -54,16 +172,213 @@ pub async fn roll(services: &crate::Services, job: &RollJob) -> Result<TableJobO
     return Ok(TableJobOutput::RollStale);
   }
 
-  let updated = apply_roll(start)?;
+  let (shooter, button) = (start.roller.clone(), start.button);
+  let Rolled { table: updated, hands } = apply_roll(start)?;
 
-  services
-    .tables()
-    .find_one_and_replace(db::lookup_for_uuid(&updated.id), &updated, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to replace updated table - {}", error);
-      JobError::Terminal("failed-save".into())
-    })?;
+  services.tables().save(&updated).await.map_err(|error| {
+    log::warn!("unable to replace updated table - {}", error);
+    JobError::Terminal("failed-save".into())
+  })?;
+
+  // The table has already been saved at this point, so failing to archive the roll, pay out an orphaned seat or record
+  // a hand is not worth failing the job over (retrying would roll again). Unpaid settlements were saved with the table.
+  if let Some(dice) = updated.rolls.first() {
+    let archived = RollArchiveState {
+      table: updated.id.clone(),
+      dice: *dice,
+      shooter,
+      button,
+      rolled_at: chrono::Utc::now(),
+    };
+
+    if let Err(error) = services.archive().archive_roll(&archived).await {
+      log::warn!("unable to archive roll - {}", error);
+    }
+  }
+
+  if let Some(dice) = updated.rolls.first() {
+    let (button, nonce) = (updated.button, updated.nonce.clone());
+    super::publish(
+      services,
+      &updated.id,
+      TableEvent::Rolled {
+        dice: *dice,
+        button,
+        nonce,
+      },
+    )
+    .await;
+  }
+
+  super::seats_changed(services, &updated).await;
+
+  let paid = settle_pending(services, &updated).await;
+
+  if !paid.is_empty() {
+    if let Err(error) = clear_pending(services, &updated.id, &paid).await {
+      log::warn!(
+        "unable to clear paid settlements from table '{}' - {}",
+        updated.id,
+        error
+      );
+    }
+  }
+
+  if !hands.is_empty() {
+    log::info!("recording {} finished hand(s) for table '{}'", hands.len(), updated.id);
+
+    if let Err(error) = services.archive().record_hands(&hands).await {
+      log::warn!("unable to record finished hands - {}", error);
+    }
+  }
 
   Ok(TableJobOutput::RollProcessed)
 }
+
+#[cfg(test)]
+mod test {
+  use super::super::{seed_player, seed_table};
+  use super::{roll, settle};
+  use crate::storage::{MemoryStore, PlayerRepository};
+  use crate::Services;
+  use async_std::sync::Arc;
+  use async_trait::async_trait;
+  use bankah::jobs::{RollJob, TableJobOutput};
+  use bankah::state::{BetState, HistoryState, PlayerState, SeatState};
+  use std::io::{Error, ErrorKind, Result};
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  // Players kept in memory, whose balances can't be credited while `failing` is set.
+  #[derive(Clone, Default)]
+  struct FlakyPlayers {
+    store: MemoryStore,
+    failing: Arc<AtomicBool>,
+  }
+
+  #[async_trait]
+  impl PlayerRepository for FlakyPlayers {
+    async fn find(&self, id: &str) -> Result<Option<PlayerState>> {
+      PlayerRepository::find(&self.store, id).await
+    }
+
+    async fn find_or_create(&self, player: &PlayerState) -> Result<PlayerState> {
+      self.store.find_or_create(player).await
+    }
+
+    async fn set_seating(&self, id: &str, balance: u32, tables: &[String]) -> Result<bool> {
+      self.store.set_seating(id, balance, tables).await
+    }
+
+    async fn set_balance(&self, id: &str, balance: u32) -> Result<()> {
+      self.store.set_balance(id, balance).await
+    }
+
+    async fn adjust(&self, id: &str, amount: i64) -> Result<bool> {
+      self.store.adjust(id, amount).await
+    }
+
+    async fn credit(&self, id: &str, amount: u32, entry: &HistoryState, left: Option<&str>) -> Result<()> {
+      if self.failing.load(Ordering::SeqCst) {
+        return Err(Error::new(ErrorKind::Other, "credit-failed"));
+      }
+
+      self.store.credit(id, amount, entry, left).await
+    }
+
+    async fn record(&self, id: &str, entry: &HistoryState) -> Result<()> {
+      self.store.record(id, entry).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+      PlayerRepository::delete(&self.store, id).await
+    }
+  }
+
+  #[test]
+  fn test_roll_processed() {
+    let services = Services::in_memory();
+
+    let (output, stale, before, after) = async_std::task::block_on(async {
+      let player = seed_player(&services, 500).await;
+      let table = seed_table(&services, &player, 200).await;
+      let before = services.tables().find(&table).await.unwrap().unwrap();
+      let job = RollJob {
+        table: table.clone(),
+        version: before.nonce.clone(),
+      };
+      let output = roll(&services, &job).await;
+      let stale = roll(&services, &job).await;
+      let after = services.tables().find(&table).await.unwrap().unwrap();
+      (output, stale, before, after)
+    });
+
+    assert!(matches!(output, Ok(TableJobOutput::RollProcessed)));
+    assert!(matches!(stale, Ok(TableJobOutput::RollStale)));
+    assert_ne!(before.nonce, after.nonce);
+    assert_eq!(after.rolls.len(), before.rolls.len() + 1);
+  }
+
+  #[test]
+  fn test_failed_credit_is_retried() {
+    let players = FlakyPlayers::default();
+    let services = Services::in_memory().with_players(Arc::new(players.clone()));
+
+    async_std::task::block_on(async {
+      let shooter = seed_player(&services, 500).await;
+      let table = seed_table(&services, &shooter, 200).await;
+      let leaver = seed_player(&services, 500).await;
+
+      // A player who stood up with a field bet still working.
+      let mut state = services.tables().find(&table).await.unwrap().unwrap();
+      let seat = SeatState {
+        orphaned: true,
+        bets: vec![BetState::Field(50)],
+        ..SeatState::default()
+      };
+      state.seats.insert(leaver.id.clone(), seat);
+      services.tables().save(&state).await.unwrap();
+      let tables = vec![table.clone()];
+      services.players().set_seating(&leaver.id, 450, &tables).await.unwrap();
+
+      let before = services.players().find(&leaver.id).await.unwrap().unwrap().balance;
+
+      // The field bet is settled by the roll, but the leaving player can't be paid.
+      players.failing.store(true, Ordering::SeqCst);
+      let nonce = services.tables().find(&table).await.unwrap().unwrap().nonce;
+      let job = RollJob {
+        table: table.clone(),
+        version: nonce,
+      };
+      assert!(matches!(roll(&services, &job).await, Ok(TableJobOutput::RollProcessed)));
+
+      let unpaid = services.tables().find(&table).await.unwrap().unwrap();
+      let left = services.players().find(&leaver.id).await.unwrap().unwrap();
+      assert!(!unpaid.seats.contains_key(&leaver.id));
+      assert_eq!(unpaid.pending.len(), 1);
+      assert_eq!(left.balance, before);
+      assert!(left.history.is_empty());
+
+      // The next roll pays what is owed and takes it off the table.
+      players.failing.store(false, Ordering::SeqCst);
+      let pending = unpaid.pending[0].clone();
+      let job = RollJob {
+        table: table.clone(),
+        version: unpaid.nonce.clone(),
+      };
+      assert!(matches!(roll(&services, &job).await, Ok(TableJobOutput::RollProcessed)));
+
+      let paid = services.tables().find(&table).await.unwrap().unwrap();
+      let left = services.players().find(&leaver.id).await.unwrap().unwrap();
+      assert!(paid.pending.is_empty());
+      assert_eq!(left.balance, before + pending.settlement.amount);
+      assert_eq!(left.history.len(), 1);
+      assert!(!left.tables.contains(&table));
+
+      // Paying the same settlement again changes nothing.
+      settle(&services, &pending).await.unwrap();
+      let again = services.players().find(&leaver.id).await.unwrap().unwrap();
+      assert_eq!(again.balance, left.balance);
+      assert_eq!(again.history.len(), 1);
+    });
+  }
+}
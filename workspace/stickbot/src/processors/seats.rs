@@ -1,11 +1,13 @@
-use bankah::jobs::{JobError, TableAdminJob, TableJobOutput};
-use bankah::state::{PlayerState, TableState};
-use twowaiyo::{Player, Table};
+use std::convert::TryFrom;
 
-async fn find_player(services: &crate::Services, id: &String) -> Result<PlayerState, JobError> {
+use bankah::jobs::{CreateJob, JobError, SitJob, TableAdminJob, TableJobOutput};
+use bankah::state::{FieldPaytableState, PlayerState, TableState};
+use twowaiyo::{FieldPaytable, Player, Table};
+
+pub(super) async fn find_player(services: &crate::Services, id: &String) -> Result<PlayerState, JobError> {
   services
     .players()
-    .find_one(crate::db::doc! { "id": id }, None)
+    .find(id)
     .await
     .map_err(|error| {
       log::warn!("unable to query player - {}", error);
@@ -17,15 +19,33 @@ async fn find_player(services: &crate::Services, id: &String) -> Result<PlayerSt
     })
 }
 
-fn sit_player(mut ts: TableState, mut ps: PlayerState) -> std::io::Result<(TableState, PlayerState)> {
-  let mut player = Player::from(&ps);
+fn invalid_table(error: twowaiyo::errors::ConversionError) -> std::io::Error {
+  std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
 
-  let table = Table::from(&ts).sit(&mut player);
+// Without a buy-in amount the player sits with their whole balance. Buying in at a table the player already has a seat
+// at (even an orphaned one) adds to that seat, so the seat's balance is always taken from the engine.
+fn sit_player(
+  mut ts: TableState,
+  mut ps: PlayerState,
+  buy_in: Option<u32>,
+) -> std::io::Result<(TableState, PlayerState)> {
+  let mut player = Player::from(&ps);
+  let table = Table::try_from(&ts).map_err(invalid_table)?;
+
+  let table = match buy_in {
+    Some(amount) => table
+      .buy_in(&mut player, amount)
+      .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.error))?,
+    None => table.sit(&mut player),
+  };
   let next = TableState::from(&table);
 
   ps.balance = player.balance;
 
-  ps.tables = ps.tables.drain(0..).chain(Some(ts.id.to_string())).collect();
+  if !ps.tables.contains(&ts.id) {
+    ps.tables.push(ts.id.to_string());
+  }
 
   ts.roller = next.roller;
 
@@ -34,7 +54,9 @@ fn sit_player(mut ts: TableState, mut ps: PlayerState) -> std::io::Result<(Table
     .into_iter()
     .map(|(uuid, seat)| {
       let original = ts.seats.remove(&uuid);
-      let mut current = original.unwrap_or(seat);
+      let mut current = original.unwrap_or_else(|| seat.clone());
+      current.balance = seat.balance;
+      current.orphaned = seat.orphaned;
 
       let nickname = match uuid == ps.id {
         true => ps.nickname.clone(),
@@ -52,7 +74,7 @@ fn sit_player(mut ts: TableState, mut ps: PlayerState) -> std::io::Result<(Table
 
 fn stand_player(mut ts: TableState, mut ps: PlayerState) -> std::io::Result<(TableState, PlayerState)> {
   let mut player = Player::from(&ps);
-  let table = Table::from(&ts);
+  let table = Table::try_from(&ts).map_err(invalid_table)?;
   log::trace!("before player stand - {:?} {:?}", table, player);
 
   let table = table.stand(&mut player);
@@ -83,51 +105,51 @@ fn stand_player(mut ts: TableState, mut ps: PlayerState) -> std::io::Result<(Tab
   Ok((ts, ps))
 }
 
-pub async fn create(services: &crate::Services, pid: &String) -> Result<TableJobOutput, JobError> {
+pub async fn create(services: &crate::Services, job: &CreateJob) -> Result<TableJobOutput, JobError> {
+  let pid = &job.player;
   let player = find_player(&services, &pid).await?;
   let name = crate::names::generate().map_err(|error| {
     log::warn!("unable to generate random name - {}", error);
     JobError::Retryable
   })?;
-  let blank = TableState::with_name(name);
+  let blank = TableState {
+    variant: job.variant.clone(),
+    field: FieldPaytableState::from(&FieldPaytable::from(&job.field)),
+    max_odds: job.max_odds,
+    history: job
+      .history
+      .map(|history| history.clamp(1, crate::constants::STICKBOT_MAX_ROLL_HISTORY)),
+    ..TableState::with_name(name)
+  };
   log::debug!("creating blank table - {:?}", blank);
-  let (table, player) = sit_player(blank, player).map_err(|error| {
+  let (table, player) = sit_player(blank, player, job.buy_in).map_err(|error| {
     log::warn!("logic error while sitting player '{}' at new table - {}", pid, error);
     JobError::Terminal("".into())
   })?;
 
-  let query = crate::db::doc! { "id": pid };
-  let updates = crate::db::doc! { "$set": { "balance": player.balance, "tables": player.tables } };
-  let opts = crate::db::FindOneAndUpdateOptions::builder()
-    .return_document(crate::db::ReturnDocument::After)
-    .build();
-
-  services
+  let updated = services
     .players()
-    .find_one_and_update(query, updates, opts)
+    .set_seating(pid, player.balance, &player.tables)
     .await
     .map_err(|error| {
       log::warn!("unable to update player balance after create - {}", error);
       JobError::Terminal(format!("failed player balance update - {}", error))
-    })?
-    .ok_or_else(|| {
-      log::warn!("no player to update");
-      JobError::Terminal(format!("missing player '{}' during balance update", pid))
     })?;
 
+  if !updated {
+    log::warn!("no player to update");
+    return Err(JobError::Terminal(format!(
+      "missing player '{}' during balance update",
+      pid
+    )));
+  }
+
   log::debug!("inserting new table {:?}", table);
 
-  // TODO(mongo-uuid): we're using a `find_one_and_replace` w/ the upsert call here to circumvent the serialization
-  // discrepency between insertion and find/replace methods on the mongodb driver collection.
-  let tops = crate::db::FindOneAndReplaceOptions::builder().upsert(true).build();
-  services
-    .tables()
-    .find_one_and_replace(crate::db::lookup_for_uuid(&table.id), &table, Some(tops))
-    .await
-    .map_err(|error| {
-      log::warn!("unable to create new table - {:?}", error);
-      JobError::Terminal(format!("missing player '{}' during balance update", pid))
-    })?;
+  services.tables().save(&table).await.map_err(|error| {
+    log::warn!("unable to create new table - {:?}", error);
+    JobError::Terminal(format!("missing player '{}' during balance update", pid))
+  })?;
 
   // TODO: do we care if our attempt to enqueue a job fails from the web thread?
   services
@@ -142,27 +164,17 @@ pub async fn create(services: &crate::Services, pid: &String) -> Result<TableJob
   Ok(TableJobOutput::TableCreated(table.id.to_string()))
 }
 
-pub async fn sit(services: &crate::Services, entities: &(String, String)) -> Result<TableJobOutput, JobError> {
-  let (tid, pid) = entities;
+pub async fn sit(services: &crate::Services, job: &SitJob) -> Result<TableJobOutput, JobError> {
+  let (tid, pid) = (&job.table, &job.player);
   log::debug!("player '{}' is sitting down to table '{}'", pid, tid);
 
   let tables = services.tables();
   let players = services.players();
 
-  let player = players
-    .find_one(crate::db::doc! { "id": pid }, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to query player - {}", error);
-      JobError::Terminal(format!("unable to query players - {}", error))
-    })?
-    .ok_or_else(|| {
-      log::warn!("unable to find player");
-      JobError::Terminal(format!("unable to find player '{}'", pid))
-    })?;
+  let player = find_player(services, pid).await?;
 
   let state = tables
-    .find_one(crate::db::doc! { "id": tid }, None)
+    .find(tid)
     .await
     .map_err(|error| {
       log::warn!("unable to find table - {}", error);
@@ -173,39 +185,31 @@ pub async fn sit(services: &crate::Services, entities: &(String, String)) -> Res
       JobError::Terminal(format!("unable to find table '{}'", tid))
     })?;
 
-  let (ts, ps) = sit_player(state, player).map_err(|error| {
+  let (ts, ps) = sit_player(state, player, job.buy_in).map_err(|error| {
     log::warn!("logic error sitting player - {}", error);
     JobError::Terminal(format!("unable to sit player '{}'", error))
   })?;
 
-  let opts = crate::db::FindOneAndUpdateOptions::builder()
-    .return_document(crate::db::ReturnDocument::After)
-    .build();
-
-  // TODO(player-id): another example of player id peristence mismatch.
-  players
-    .find_one_and_update(
-      crate::db::doc! { "id": pid },
-      crate::db::doc! { "$set": { "balance": ps.balance, "tables": ps.tables } },
-      opts,
-    )
+  let updated = players
+    .set_seating(pid, ps.balance, &ps.tables)
     .await
     .map_err(|error| {
       log::warn!("unable to update player balance after join - {}", error);
       JobError::Terminal(format!("unable to update player '{}'", error))
-    })?
-    .ok_or_else(|| {
-      log::warn!("no player balance updated");
-      JobError::Terminal(format!("player '{}' not found, no update applied", pid))
     })?;
 
-  tables
-    .find_one_and_replace(crate::db::doc! { "id": tid }, &ts, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to create new table - {:?}", error);
-      JobError::Terminal(format!("table '{}' not updated - {}", tid, error))
-    })?;
+  if !updated {
+    log::warn!("no player balance updated");
+    return Err(JobError::Terminal(format!(
+      "player '{}' not found, no update applied",
+      pid
+    )));
+  }
+
+  tables.save(&ts).await.map_err(|error| {
+    log::warn!("unable to create new table - {:?}", error);
+    JobError::Terminal(format!("table '{}' not updated - {}", tid, error))
+  })?;
 
   // TODO: do we care if our attempt to enqueue a job fails from the web thread?
   let job = bankah::jobs::TableJob::admin(TableAdminJob::ReindexPopulations);
@@ -215,6 +219,7 @@ pub async fn sit(services: &crate::Services, entities: &(String, String)) -> Res
   });
 
   log::info!("player joined table '{}'", ts.id);
+  super::seats_changed(services, &ts).await;
 
   return Ok(TableJobOutput::SitOk);
 }
@@ -226,20 +231,10 @@ pub async fn stand(services: &crate::Services, entities: &(String, String)) -> R
   let tables = services.tables();
   let players = services.players();
 
-  let player = players
-    .find_one(crate::db::doc! { "id": pid }, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to query player - {}", error);
-      JobError::Terminal(format!("unable to query tables - {}", error))
-    })?
-    .ok_or_else(|| {
-      log::warn!("unable to find player");
-      JobError::Terminal(format!("unable to find player '{}'", pid))
-    })?;
+  let player = find_player(services, pid).await?;
 
   let table = tables
-    .find_one(crate::db::doc! { "id": tid }, None)
+    .find(tid)
     .await
     .map_err(|error| {
       log::warn!("unable to find table - {}", error);
@@ -250,57 +245,48 @@ pub async fn stand(services: &crate::Services, entities: &(String, String)) -> R
       JobError::Terminal(format!("unable to find table '{}'", tid))
     })?;
 
-  let (table, player) = stand_player(table, player).map_err(|error| {
+  let (mut table, player) = stand_player(table, player).map_err(|error| {
     log::warn!("unable to stand player - '{}'", error);
     JobError::Terminal(format!("logic error while standing player - '{}'", pid))
   })?;
 
+  // Settlements a roll could not pay are kept with the table; it can't be deleted until they have been paid.
+  let paid = super::rolls::settle_pending(services, &table).await;
+  table.pending.retain(|pending| !paid.contains(&pending.settlement.id));
+
   players
-    .update_one(
-      crate::db::doc! { "id": player.id.to_string() },
-      crate::db::doc! { "$set": { "balance": player.balance, "tables": player.tables } },
-      None,
-    )
+    .set_seating(&player.id, player.balance, &player.tables)
     .await
     .map_err(|error| {
       log::warn!("unable to persist new player balance - {}", error);
       JobError::Retryable
     })?;
 
-  if table.seats.len() == 0 {
+  if table.seats.is_empty() && table.pending.is_empty() {
     log::debug!("table '{}' is now empty, deleting", table.id);
 
-    tables
-      .delete_one(crate::db::doc! { "id": tid }, None)
-      .await
-      .map_err(|error| {
-        log::warn!("unable to persist table updates - {}", error);
-        JobError::Retryable
-      })?;
-
-    services
-      .table_index()
-      .delete_one(crate::db::doc! { "id": tid }, None)
-      .await
-      .map_err(|error| {
-        log::warn!("unable to persist table updates - {}", error);
-        JobError::Retryable
-      })?;
+    tables.delete(tid).await.map_err(|error| {
+      log::warn!("unable to persist table updates - {}", error);
+      JobError::Retryable
+    })?;
+
+    services.table_index().delete(tid).await.map_err(|error| {
+      log::warn!("unable to persist table updates - {}", error);
+      JobError::Retryable
+    })?;
 
     log::debug!("table '{}' cleanup complete", tid);
 
     return Ok(TableJobOutput::FinalStandOk);
   } else {
-    tables
-      .replace_one(crate::db::doc! { "id": tid }, &table, None)
-      .await
-      .map_err(|error| {
-        log::warn!("unable to persist table updates - {}", error);
-        JobError::Retryable
-      })?;
+    tables.save(&table).await.map_err(|error| {
+      log::warn!("unable to persist table updates - {}", error);
+      JobError::Retryable
+    })?;
   }
 
   log::debug!("table save, applying new player state for '{}'", player.id);
+  super::seats_changed(services, &table).await;
 
   log::debug!("player '{}' updated, reindexing populations", player.id);
 
@@ -315,7 +301,10 @@ pub async fn stand(services: &crate::Services, entities: &(String, String)) -> R
 
 #[cfg(test)]
 mod test {
-  use super::stand_player;
+  use super::super::{seed_player, seed_table};
+  use super::{sit, sit_player, stand, stand_player};
+  use crate::Services;
+  use bankah::jobs::{SitJob, TableJobOutput};
   use bankah::state::{PlayerState, TableState};
   use twowaiyo::{Bet, Player, Table};
 
@@ -345,4 +334,87 @@ mod test {
       "there is one bet left"
     );
   }
+
+  #[test]
+  fn test_sit_with_buy_in() {
+    let ps = PlayerState::from(&Player::with_balance(500));
+    let (ts, ps) = sit_player(TableState::default(), ps, Some(200)).unwrap();
+    assert_eq!(ps.balance, 300);
+    assert_eq!(ts.seats.get(&ps.id).map(|s| s.balance), Some(200));
+    assert_eq!(ps.tables, vec![ts.id.clone()]);
+
+    let ps = PlayerState::from(&Player::with_balance(100));
+    assert!(sit_player(TableState::default(), ps, Some(200)).is_err());
+  }
+
+  #[test]
+  fn test_sit_at_orphaned_seat() {
+    let table = Table::with_dice(vec![2, 2].into_iter());
+    let mut player = Player::with_balance(200);
+    let table = table
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap()
+      .roll()
+      .table;
+    let (ts, ps) = stand_player(TableState::from(&table), PlayerState::from(&player)).unwrap();
+    assert_eq!(ts.seats.get(&ps.id).map(|s| s.orphaned), Some(true));
+
+    let (ts, ps) = sit_player(ts, ps, Some(60)).unwrap();
+    let seat = ts.seats.get(&ps.id).expect("missing seat");
+    assert_eq!(ps.balance, 40);
+    assert_eq!(seat.balance, 60);
+    assert!(!seat.orphaned);
+    assert_eq!(seat.bets.len(), 1);
+    assert_eq!(ps.tables, vec![ts.id.clone()]);
+  }
+
+  #[test]
+  fn test_create_and_leave() {
+    let services = Services::in_memory();
+
+    let (created, player, output, left, gone) = async_std::task::block_on(async {
+      let player = seed_player(&services, 500).await;
+      let id = seed_table(&services, &player, 200).await;
+      let created = services.tables().find(&id).await.unwrap();
+      let seated = services.players().find(&player.id).await.unwrap().unwrap();
+      let output = stand(&services, &(id.clone(), player.id.clone())).await;
+      let left = services.players().find(&player.id).await.unwrap().unwrap();
+      let gone = services.tables().find(&id).await.unwrap().is_none();
+      (created, seated, output, left, gone)
+    });
+
+    assert_eq!(
+      created.and_then(|ts| ts.seats.get(&player.id).map(|s| s.balance)),
+      Some(200)
+    );
+    assert_eq!(player.balance, 300);
+    assert_eq!(player.tables.len(), 1);
+    assert!(matches!(output, Ok(TableJobOutput::FinalStandOk)));
+    assert_eq!(left.balance, 500);
+    assert!(left.tables.is_empty());
+    assert!(gone);
+  }
+
+  #[test]
+  fn test_sit_and_reindex() {
+    let services = Services::in_memory();
+
+    let (output, index) = async_std::task::block_on(async {
+      let (first, second) = (seed_player(&services, 500).await, seed_player(&services, 500).await);
+      let table = seed_table(&services, &first, 100).await;
+      let job = SitJob {
+        table,
+        player: second.id.clone(),
+        buy_in: None,
+      };
+      let output = sit(&services, &job).await;
+      services.table_index().reindex().await.unwrap();
+      (output, services.table_index().list(10).await.unwrap())
+    });
+
+    assert!(matches!(output, Ok(TableJobOutput::SitOk)));
+    assert_eq!(index.len(), 1);
+    assert_eq!(index[0].population.len(), 2);
+  }
 }
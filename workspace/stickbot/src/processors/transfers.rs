@@ -0,0 +1,153 @@
+use std::convert::TryFrom;
+
+use bankah::jobs::{BetFailure, JobError, TableJobOutput, TransferJob};
+use bankah::state::{HistoryState, PlayerState, TableState, TransferState};
+use twowaiyo::{Player, RandomRoller, Table};
+
+use super::seats::find_player;
+
+const TRANSFER_LIMIT_CODE: &'static str = "transfer_limit_exceeded";
+
+// Moves chips from the sender's seat to the recipient's seat, or to the house when there is no recipient. Only the
+// seat balances are taken from the engine; player accounts are not touched since the chips never leave the table.
+fn apply_transfer(
+  mut ts: TableState,
+  ps: &PlayerState,
+  table: Table<RandomRoller>,
+  recipient: Option<&String>,
+  amount: u32,
+  limit: u32,
+) -> Result<TableState, BetFailure> {
+  if amount > limit {
+    return Err(BetFailure {
+      code: TRANSFER_LIMIT_CODE.to_string(),
+      message: format!("transfers are limited to {}", limit),
+    });
+  }
+
+  let player = Player::from(ps);
+
+  let table = match recipient {
+    Some(recipient) => table.transfer(&player, recipient, amount),
+    None => table.tip(&player, amount),
+  }
+  .map_err(|error| super::rejection(&error.error))?;
+
+  for (id, seat) in ts.seats.iter_mut() {
+    if let Some(balance) = table.balance(id) {
+      seat.balance = balance;
+    }
+  }
+
+  Ok(ts)
+}
+
+pub async fn transfer(services: &crate::Services, job: &TransferJob) -> Result<TableJobOutput, JobError> {
+  log::debug!(
+    "transferring {} from player '{}' to {:?} at table '{}'",
+    job.amount,
+    job.player,
+    job.recipient,
+    job.table
+  );
+
+  let player = find_player(services, &job.player).await?;
+
+  let state = services
+    .tables()
+    .find(&job.table)
+    .await
+    .map_err(|error| {
+      log::warn!("unable to find table - {}", error);
+      JobError::Retryable
+    })?
+    .ok_or_else(|| {
+      log::warn!("unable to find table '{}'", job.table);
+      JobError::Terminal(format!("unable to find table '{}'", job.table))
+    })?;
+
+  let table = Table::try_from(&state).map_err(|error| {
+    log::warn!("unable to load table '{}' - {}", state.id, error);
+    JobError::Terminal(format!("invalid-table - {}", error))
+  })?;
+
+  let ts = match apply_transfer(
+    state,
+    &player,
+    table,
+    job.recipient.as_ref(),
+    job.amount,
+    services.max_transfer(),
+  ) {
+    Err(failure) => return Ok(TableJobOutput::TransferFailed(failure)),
+    Ok(ts) => ts,
+  };
+
+  services.tables().save(&ts).await.map_err(|error| {
+    log::warn!("unable to persist table updates - {}", error);
+    JobError::Terminal(format!("table '{}' not updated - {}", ts.id, error))
+  })?;
+
+  super::seats_changed(services, &ts).await;
+
+  let record = TransferState {
+    id: uuid::Uuid::new_v4().to_string(),
+    table: job.table.clone(),
+    from: player.id.clone(),
+    to: job.recipient.clone(),
+    amount: job.amount,
+    sent_at: chrono::Utc::now(),
+  };
+
+  // The table has already been updated at this point; failing to write the audit trail is logged but not retried.
+  if let Err(error) = services.archive().record_transfer(&record).await {
+    log::warn!("unable to archive transfer '{}' - {}", record.id, error);
+  }
+
+  let history = HistoryState::Transfer(record.clone());
+  let involved = std::iter::once(&record.from).chain(record.to.iter());
+
+  for id in involved {
+    if let Err(error) = services.players().record(id, &history).await {
+      log::warn!("unable to record transfer history for '{}' - {}", id, error);
+    }
+  }
+
+  Ok(TableJobOutput::TransferOk)
+}
+
+#[cfg(test)]
+mod test {
+  use super::apply_transfer;
+  use bankah::state::{PlayerState, TableState};
+  use std::convert::TryFrom;
+  use twowaiyo::{Player, Table};
+
+  fn seated() -> (TableState, PlayerState, PlayerState) {
+    let (mut alice, mut bob) = (Player::with_balance(500), Player::with_balance(500));
+    let table = Table::default().buy_in(&mut alice, 100).unwrap();
+    let table = table.buy_in(&mut bob, 100).unwrap();
+    (
+      TableState::from(&table),
+      PlayerState::from(&alice),
+      PlayerState::from(&bob),
+    )
+  }
+
+  #[test]
+  fn test_transfer() {
+    let (ts, alice, bob) = seated();
+    let table = Table::try_from(&ts).unwrap();
+    let ts = apply_transfer(ts, &alice, table, Some(&bob.id), 40, 100).unwrap();
+    assert_eq!(ts.seats.get(&alice.id).map(|seat| seat.balance), Some(60));
+    assert_eq!(ts.seats.get(&bob.id).map(|seat| seat.balance), Some(140));
+  }
+
+  #[test]
+  fn test_tip_over_limit() {
+    let (ts, alice, _) = seated();
+    let table = Table::try_from(&ts).unwrap();
+    let failure = apply_transfer(ts, &alice, table, None, 50, 25).unwrap_err();
+    assert_eq!(failure.code, "transfer_limit_exceeded");
+  }
+}
@@ -0,0 +1,229 @@
+use std::io::{Error, ErrorKind, Result};
+
+use async_std::io::{BufRead, BufReader, ReadExt};
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+
+use crate::constants;
+
+// kramer has no support for pub/sub, so the handful of commands needed here are written and read by hand. Only the
+// parts of the protocol that PUBLISH and SUBSCRIBE respond with are understood.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Reply {
+  Simple(String),
+  Error(String),
+  Integer(i64),
+  Bulk(Option<String>),
+  Array(Vec<Reply>),
+}
+
+pub(crate) fn channel(table: &str) -> String {
+  format!("{}:{}", constants::STICKBOT_TABLE_EVENTS, table)
+}
+
+pub(crate) fn encode(parts: &[&str]) -> String {
+  let mut out = format!("*{}\r\n", parts.len());
+
+  for part in parts {
+    out.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+  }
+
+  out
+}
+
+fn invalid<S>(message: S) -> Error
+where
+  S: std::fmt::Display,
+{
+  Error::new(ErrorKind::InvalidData, format!("{}", message))
+}
+
+async fn line<R>(reader: &mut R) -> Result<String>
+where
+  R: BufRead + Unpin,
+{
+  let mut line = String::new();
+
+  if reader.read_line(&mut line).await? == 0 {
+    return Err(Error::new(ErrorKind::UnexpectedEof, "redis connection closed"));
+  }
+
+  Ok(line.trim_end_matches("\r\n").to_string())
+}
+
+fn number(value: &str) -> Result<i64> {
+  value.parse().map_err(|_| invalid(format!("bad length '{}'", value)))
+}
+
+async fn scalar<R>(reader: &mut R, header: String) -> Result<Reply>
+where
+  R: BufRead + Unpin,
+{
+  let (kind, value) = header.split_at(header.len().min(1));
+
+  match kind {
+    "+" => Ok(Reply::Simple(value.to_string())),
+    "-" => Ok(Reply::Error(value.to_string())),
+    ":" => number(value).map(Reply::Integer),
+    "$" => match number(value)? {
+      length if length < 0 => Ok(Reply::Bulk(None)),
+      length => {
+        let mut buffer = vec![0u8; length as usize + 2];
+        reader.read_exact(&mut buffer).await?;
+        buffer.truncate(length as usize);
+        String::from_utf8(buffer)
+          .map(|bulk| Reply::Bulk(Some(bulk)))
+          .map_err(invalid)
+      }
+    },
+    other => Err(invalid(format!("unexpected reply type '{}'", other))),
+  }
+}
+
+// Arrays are only ever one level deep for the commands used here, so nested arrays are rejected.
+pub(crate) async fn read<R>(reader: &mut R) -> Result<Reply>
+where
+  R: BufRead + Unpin,
+{
+  let header = line(reader).await?;
+
+  match header.strip_prefix('*') {
+    Some(count) => {
+      let mut items = Vec::new();
+
+      for _ in 0..number(count)?.max(0) {
+        let header = line(reader).await?;
+        items.push(scalar(reader, header).await?);
+      }
+
+      Ok(Reply::Array(items))
+    }
+    None => scalar(reader, header).await,
+  }
+}
+
+// A dedicated connection subscribed to a single table's events. Once subscribed, redis won't accept other commands on
+// the connection, so these are never shared.
+pub struct Subscription {
+  reader: BufReader<TcpStream>,
+}
+
+impl Subscription {
+  pub(crate) async fn open(mut stream: TcpStream, channel: &str) -> Result<Self> {
+    stream.write_all(encode(&["SUBSCRIBE", channel]).as_bytes()).await?;
+    Ok(Subscription {
+      reader: BufReader::new(stream),
+    })
+  }
+
+  // Waits for the next message published to the channel, skipping subscription confirmations.
+  pub async fn next(&mut self) -> Result<String> {
+    loop {
+      if let Some(payload) = message(read(&mut self.reader).await?)? {
+        return Ok(payload);
+      }
+    }
+  }
+
+  // Like `next`, but gives up if nothing arrives within `wait`. Only waiting for data to show up is timed; once a reply
+  // has started arriving it is read in full, so giving up never leaves half a reply behind.
+  pub async fn next_within(&mut self, wait: std::time::Duration) -> Result<Option<String>> {
+    loop {
+      let reader = &mut self.reader;
+      let filled =
+        async_std::future::poll_fn(|context| std::pin::Pin::new(&mut *reader).poll_fill_buf(context).map_ok(|_| ()));
+
+      match async_std::future::timeout(wait, filled).await {
+        Err(_) => return Ok(None),
+        Ok(filled) => filled?,
+      }
+
+      if let Some(payload) = message(read(&mut self.reader).await?)? {
+        return Ok(Some(payload));
+      }
+    }
+  }
+}
+
+// The payload of a published message; other replies seen while subscribed are skipped.
+fn message(reply: Reply) -> Result<Option<String>> {
+  match reply {
+    Reply::Array(items) => match items.as_slice() {
+      [Reply::Bulk(Some(kind)), _, Reply::Bulk(Some(payload))] if kind == "message" => Ok(Some(payload.clone())),
+      other => {
+        log::debug!("skipping subscription reply - {:?}", other);
+        Ok(None)
+      }
+    },
+    Reply::Error(error) => Err(invalid(error)),
+    other => {
+      log::debug!("skipping subscription reply - {:?}", other);
+      Ok(None)
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{encode, read, Reply, Subscription};
+  use async_std::io::BufReader;
+  use async_std::net::{TcpListener, TcpStream};
+  use async_std::prelude::*;
+  use std::time::Duration;
+
+  #[test]
+  fn test_encode() {
+    assert_eq!(
+      encode(&["PUBLISH", "stickbot:table_events:a", "{}"]),
+      "*3\r\n$7\r\nPUBLISH\r\n$23\r\nstickbot:table_events:a\r\n$2\r\n{}\r\n"
+    );
+  }
+
+  #[test]
+  fn test_read_message() {
+    let raw = "*3\r\n$7\r\nmessage\r\n$1\r\na\r\n$9\r\n{\"x\":\r\n1}\r\n:2\r\n";
+    let mut reader = BufReader::new(raw.as_bytes());
+    let (message, count) = async_std::task::block_on(async { (read(&mut reader).await, read(&mut reader).await) });
+    assert_eq!(
+      message.unwrap(),
+      Reply::Array(vec![
+        Reply::Bulk(Some("message".into())),
+        Reply::Bulk(Some("a".into())),
+        Reply::Bulk(Some("{\"x\":\r\n1}".into())),
+      ])
+    );
+    assert_eq!(count.unwrap(), Reply::Integer(2));
+  }
+
+  #[test]
+  fn test_read_closed() {
+    let mut reader = BufReader::new("".as_bytes());
+    assert!(async_std::task::block_on(read(&mut reader)).is_err());
+  }
+
+  #[test]
+  fn test_next_within() {
+    let (quiet, published) = async_std::task::block_on(async {
+      let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+      let stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+      let (mut redis, _) = listener.accept().await.unwrap();
+      let mut subscription = Subscription::open(stream, "a").await.unwrap();
+
+      redis
+        .write_all(b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n")
+        .await
+        .unwrap();
+      let quiet = subscription.next_within(Duration::from_millis(50)).await.unwrap();
+
+      redis
+        .write_all(b"*3\r\n$7\r\nmessage\r\n$1\r\na\r\n$2\r\n{}\r\n")
+        .await
+        .unwrap();
+      let published = subscription.next_within(Duration::from_millis(50)).await.unwrap();
+      (quiet, published)
+    });
+
+    assert!(quiet.is_none());
+    assert_eq!(published, Some("{}".to_string()));
+  }
+}
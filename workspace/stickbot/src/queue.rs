@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::time::Duration;
+
+use async_std::channel::{Receiver, Sender};
+use async_std::sync::Mutex;
+use async_trait::async_trait;
+
+use bankah::jobs::TableJob;
+
+use crate::redis::Connection;
+
+// How long a worker waits on an empty queue before `pop` gives up and returns nothing.
+const POP_WAIT_SECONDS: u64 = 3;
+
+// Where jobs wait to be processed, and where their serialized results are kept for the web process to find.
+#[async_trait]
+pub trait JobQueue: Send + Sync {
+  async fn push(&self, job: &TableJob) -> Result<String>;
+
+  // Waits a short while for a job to become available.
+  async fn pop(&self) -> Result<Option<TableJob>>;
+
+  async fn store(&self, id: &str, result: &str) -> Result<()>;
+
+  async fn fetch(&self, id: &str) -> Result<Option<String>>;
+}
+
+fn response_string(response: &kramer::ResponseValue) -> Option<String> {
+  match response {
+    kramer::ResponseValue::String(inner) => Some(inner.clone()),
+    res => {
+      log::warn!("strange response from job queue - {:?}", res);
+      None
+    }
+  }
+}
+
+fn parse_pop(response: &kramer::ResponseValue) -> Option<TableJob> {
+  response_string(&response).and_then(|contents| serde_json::from_str::<TableJob>(&contents).ok())
+}
+
+// Jobs are kept in a redis list and results in a redis hash, shared by every web and worker process.
+pub struct RedisQueue {
+  redis: Connection,
+}
+
+impl RedisQueue {
+  pub(crate) fn new(redis: Connection) -> Self {
+    RedisQueue { redis }
+  }
+}
+
+#[async_trait]
+impl JobQueue for RedisQueue {
+  async fn push(&self, job: &TableJob) -> Result<String> {
+    let serialized = serde_json::to_string(&job).map_err(|error| {
+      log::warn!("unable to serialize job - {}", error);
+      Error::new(ErrorKind::Other, format!("{}", error))
+    })?;
+
+    let command = kramer::Command::List(kramer::ListCommand::Push(
+      (kramer::Side::Right, kramer::Insertion::Always),
+      crate::env::JobStore::Queue,
+      kramer::Arity::One(serialized),
+    ));
+
+    self.redis.command(&command).await.map(|result| {
+      log::debug!("executed queue command - {:?}", result);
+      job.id()
+    })
+  }
+
+  async fn pop(&self) -> Result<Option<TableJob>> {
+    let cmd = kramer::Command::List::<_, String>(kramer::ListCommand::Pop(
+      kramer::Side::Left,
+      crate::env::JobStore::Queue,
+      Some((None, POP_WAIT_SECONDS)),
+    ));
+
+    let result = match self.redis.command(&cmd).await {
+      Err(error) => {
+        log::warn!("unable to pop from bet queue - {}", error);
+        return Err(Error::new(ErrorKind::Other, format!("{}", error)));
+      }
+      Ok(kramer::Response::Item(kramer::ResponseValue::Empty)) => {
+        log::debug!("empty response from queue");
+        return Ok(None);
+      }
+      Ok(kramer::Response::Array(values)) => values,
+      Ok(kramer::Response::Error) => {
+        log::warn!("unable to pop from queue - redis error");
+        return Err(Error::new(ErrorKind::Other, "invalid-response"));
+      }
+      Ok(kramer::Response::Item(inner)) => {
+        log::warn!("unknown response from pop - '{:?}'", inner);
+        return Err(Error::new(ErrorKind::Other, format!("{:?}", inner)));
+      }
+    };
+
+    log::debug!("result from pop - {:?}, attempting to deserialize", result);
+
+    Ok(result.get(1).and_then(parse_pop))
+  }
+
+  async fn store(&self, id: &str, result: &str) -> Result<()> {
+    let storage = format!("{}", crate::env::JobStore::Results);
+
+    let sets = kramer::Command::Hashes(kramer::HashCommand::Set(
+      storage.as_str(),
+      kramer::Arity::One((id, result)),
+      kramer::Insertion::Always,
+    ));
+
+    self.redis.command(&sets).await.map(|_| ())
+  }
+
+  async fn fetch(&self, id: &str) -> Result<Option<String>> {
+    let storage = format!("{}", crate::env::JobStore::Results);
+
+    let command = kramer::Command::Hashes(kramer::HashCommand::Get::<_, &str>(
+      storage.as_str(),
+      Some(kramer::Arity::One(id)),
+    ));
+
+    match self.redis.command(&command).await? {
+      kramer::Response::Item(kramer::ResponseValue::String(inner)) => Ok(Some(inner)),
+      kramer::Response::Item(kramer::ResponseValue::Empty) => Ok(None),
+      other => {
+        log::warn!("strange response from job lookup - {:?}", other);
+        Err(Error::new(ErrorKind::Other, format!("{:?}", other)))
+      }
+    }
+  }
+}
+
+// Jobs and results kept in memory, only visible to the process that created the queue. Useful for running the web
+// server and worker together in a single process, and for tests.
+pub struct MemoryQueue {
+  sender: Sender<TableJob>,
+  receiver: Receiver<TableJob>,
+  results: Mutex<HashMap<String, String>>,
+  wait: Duration,
+}
+
+impl MemoryQueue {
+  pub fn new(wait: Duration) -> Self {
+    let (sender, receiver) = async_std::channel::unbounded();
+
+    MemoryQueue {
+      sender,
+      receiver,
+      results: Mutex::new(HashMap::new()),
+      wait,
+    }
+  }
+}
+
+impl Default for MemoryQueue {
+  fn default() -> Self {
+    MemoryQueue::new(Duration::from_secs(POP_WAIT_SECONDS))
+  }
+}
+
+#[async_trait]
+impl JobQueue for MemoryQueue {
+  async fn push(&self, job: &TableJob) -> Result<String> {
+    self
+      .sender
+      .send(job.clone())
+      .await
+      .map(|_| job.id())
+      .map_err(|error| Error::new(ErrorKind::Other, format!("{}", error)))
+  }
+
+  async fn pop(&self) -> Result<Option<TableJob>> {
+    match async_std::future::timeout(self.wait, self.receiver.recv()).await {
+      Err(_) => Ok(None),
+      Ok(job) => job
+        .map(Some)
+        .map_err(|error| Error::new(ErrorKind::Other, format!("{}", error))),
+    }
+  }
+
+  async fn store(&self, id: &str, result: &str) -> Result<()> {
+    let mut results = self.results.lock().await;
+    results.insert(id.to_string(), result.to_string());
+    Ok(())
+  }
+
+  async fn fetch(&self, id: &str) -> Result<Option<String>> {
+    let results = self.results.lock().await;
+    Ok(results.get(id).cloned())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{JobQueue, MemoryQueue};
+  use bankah::jobs::TableJob;
+  use std::time::Duration;
+
+  #[test]
+  fn test_memory_push_pop() {
+    let queue = MemoryQueue::new(Duration::from_millis(10));
+    let (first, second) = (TableJob::reindex(), TableJob::stand("t".into(), "p".into()));
+
+    let popped = async_std::task::block_on(async {
+      queue.push(&first).await.unwrap();
+      queue.push(&second).await.unwrap();
+      (
+        queue.pop().await.unwrap(),
+        queue.pop().await.unwrap(),
+        queue.pop().await.unwrap(),
+      )
+    });
+
+    assert_eq!(popped.0.map(|job| job.id()), Some(first.id()));
+    assert_eq!(popped.1.map(|job| job.id()), Some(second.id()));
+    assert!(popped.2.is_none());
+  }
+
+  #[test]
+  fn test_memory_results() {
+    let queue = MemoryQueue::default();
+
+    let (missing, found) = async_std::task::block_on(async {
+      let missing = queue.fetch("job").await.unwrap();
+      queue.store("job", "{}").await.unwrap();
+      (missing, queue.fetch("job").await.unwrap())
+    });
+
+    assert_eq!(missing, None);
+    assert_eq!(found, Some("{}".to_string()));
+  }
+}
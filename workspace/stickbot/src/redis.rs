@@ -1,3 +1,8 @@
+use std::io::{Error, ErrorKind, Result};
+
+use async_std::net::TcpStream;
+use async_std::sync::{Arc, Mutex, MutexGuard};
+
 use crate::constants;
 
 #[derive(Debug, Clone)]
@@ -18,3 +23,92 @@ pub fn from_env() -> Option<RedisConfig> {
     password: pass,
   })
 }
+
+pub(crate) async fn connect(config: &RedisConfig) -> Result<TcpStream> {
+  log::debug!("redis configuration - '{}', connecting", config.host);
+  let mut redis = TcpStream::connect(format!("{}:{}", config.host, config.port)).await?;
+  log::debug!("connection established - {:?}, authenticating", redis.peer_addr());
+
+  if config.password.len() > 0 {
+    let cmd = kramer::Command::Auth::<&String, &String>(kramer::AuthCredentials::Password(&config.password));
+    let result = kramer::execute(&mut redis, cmd).await?;
+    log::debug!("authentication result - {:?}", result);
+  }
+
+  Ok(redis)
+}
+
+// A single connection shared by everything that talks to redis, re-established when it breaks.
+#[derive(Clone)]
+pub(crate) struct Connection {
+  stream: Arc<Mutex<TcpStream>>,
+  config: RedisConfig,
+}
+
+impl Connection {
+  pub(crate) async fn open(config: RedisConfig) -> Result<Self> {
+    let stream = connect(&config).await?;
+    Ok(Connection {
+      stream: Arc::new(Mutex::new(stream)),
+      config,
+    })
+  }
+
+  pub(crate) fn config(&self) -> &RedisConfig {
+    &self.config
+  }
+
+  pub(crate) async fn lock(&self) -> MutexGuard<'_, TcpStream> {
+    self.stream.lock().await
+  }
+
+  pub(crate) async fn command<S, V>(&self, command: &kramer::Command<S, V>) -> Result<kramer::Response>
+  where
+    S: std::fmt::Display,
+    V: std::fmt::Display,
+  {
+    self.inner_command(command, 0).await
+  }
+
+  #[allow(unused_assignments)]
+  async fn inner_command<S, V>(&self, command: &kramer::Command<S, V>, mut attempt: u8) -> Result<kramer::Response>
+  where
+    S: std::fmt::Display,
+    V: std::fmt::Display,
+  {
+    loop {
+      if attempt > 10 {
+        log::warn!("failed redis connection after {} attempts", attempt);
+        return Err(Error::new(ErrorKind::Other, "too-many-attempts"));
+      }
+
+      log::debug!("requesting tcp write access through lock (attempt {})", attempt);
+      let mut lock = self.stream.lock().await;
+      let mut redis: &mut TcpStream = &mut lock;
+      log::debug!("lock acquired, attempting to send command");
+
+      match async_std::future::timeout(std::time::Duration::from_secs(5), kramer::execute(&mut redis, command)).await {
+        Err(timeout_error) => {
+          log::warn!("timeout error during command transfer - {}", timeout_error);
+          *lock = connect(&self.config).await?;
+          attempt += 1;
+          return Err(Error::new(ErrorKind::Other, "timeout-error"));
+        }
+        Ok(Err(error)) => {
+          log::warn!("failed executing command - {}", error);
+
+          if error.kind() == ErrorKind::BrokenPipe {
+            log::warn!("broken pipe, attempting to re-establish connection");
+            *lock = connect(&self.config).await?;
+          }
+
+          attempt += 1;
+        }
+        Ok(Ok(response)) => {
+          log::debug!("redis success - {:?} (attempt {})", response, attempt);
+          return Ok(response);
+        }
+      }
+    }
+  }
+}
@@ -1,6 +1,5 @@
 use serde::Serialize;
 
-use crate::db;
 use crate::web::{cookie as get_cookie, Body, Error, Request, Response, Result};
 
 #[derive(Serialize)]
@@ -21,13 +20,10 @@ pub async fn delete(request: Request) -> Result {
 
   log::debug!("player {} deleting account", player.id);
 
-  players
-    .delete_one(db::doc! { "id": &player.id }, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to delete player record: {}", error);
-      error
-    })?;
+  players.delete(&player.id).await.map_err(|error| {
+    log::warn!("unable to delete player record: {}", error);
+    error
+  })?;
 
   log::debug!("player document '{}' deleted, queuing cleanup job", player.id);
 
@@ -1,7 +1,6 @@
 use serde::Deserialize;
 
 use crate::constants::MONGO_DB_TABLE_COLLECTION_NAME;
-use crate::db;
 use crate::web::{cookie as get_cookie, Error, Request, Result};
 
 #[derive(Debug, Deserialize)]
@@ -30,11 +29,7 @@ pub async fn set_balance(request: Request) -> Result {
   let players = request.state().players();
 
   players
-    .update_one(
-      db::doc! { "id": query.player },
-      db::doc! { "$set": { "balance": query.amount } },
-      None,
-    )
+    .set_balance(&query.player, query.amount)
     .await
     .map_err(|error| {
       log::warn!("unable to update balance - {}", error);
@@ -58,7 +53,7 @@ pub async fn drop_all(request: Request) -> Result {
   let collection = request.state().tables();
 
   collection
-    .drop(None)
+    .clear()
     .await
     .map_err(|error| {
       log::warn!("unable to create new table - {:?}", error);
@@ -3,7 +3,6 @@ use surf;
 
 use crate::auth;
 use crate::constants;
-use crate::db;
 use crate::web::{cookie as get_cookie, Body, Error, Redirect, Request, Response, Result, Url};
 
 #[cfg(debug_assertions)]
@@ -94,28 +93,16 @@ async fn token_from_response(response: &mut surf::Response) -> Option<String> {
     .map(|body| body.access_token)
 }
 
-fn player_from_userinfo(userinfo: &UserInfo) -> std::io::Result<db::bson::Document> {
-  let id = uuid::Uuid::new_v4().to_string();
-  let oid = userinfo.sub.clone();
-  let nickname = userinfo.nickname.clone();
-  let state = bankah::state::PlayerState {
-    id,
-    oid,
-    nickname,
+fn player_from_userinfo(userinfo: &UserInfo) -> bankah::state::PlayerState {
+  bankah::state::PlayerState {
+    id: uuid::Uuid::new_v4().to_string(),
+    oid: userinfo.sub.clone(),
+    nickname: userinfo.nickname.clone(),
     balance: 10000,
     emails: vec![userinfo.email.clone()],
     tables: vec![],
-  };
-
-  bson::to_bson(&state)
-    .map(|serialized| {
-      log::debug!("serialized user state {:?}", serialized);
-      db::doc! { "$setOnInsert": serialized }
-    })
-    .map_err(|error| {
-      log::warn!("unable to generate serailized user info - {}", error);
-      std::io::Error::new(std::io::ErrorKind::Other, format!("{}", error))
-    })
+    history: vec![],
+  }
 }
 
 // ## Route
@@ -179,36 +166,21 @@ pub async fn complete(request: Request) -> Result {
   log::debug!("[complete] user - oid: '{}'. finding or creating record", user.sub);
 
   // With our loaded user data, attempt to store a new record in our players collection.
-  let collection = request.state().players();
-
-  let options = db::FindOneAndUpdateOptions::builder()
-    .upsert(true)
-    .return_document(db::ReturnDocument::After)
-    .build();
-
-  let query = db::doc! { "oid": user.sub.clone() };
-  let updates = player_from_userinfo(&user)?;
-
-  let player = collection
-    .find_one_and_update(query, updates, options)
+  let player = request
+    .state()
+    .players()
+    .find_or_create(&player_from_userinfo(&user))
     .await
     .map_err(|error| {
       log::warn!("unable to create new player - {:?}", error);
       Error::from_str(500, "player-failure")
-    })?
-    .ok_or(Error::from_str(404, "missing-player"))?;
+    })?;
 
   log::debug!("[complete] found record - '{}', building token", player.id);
 
   let jwt = auth::Claims::for_player(&user.sub, &player.id.to_string()).encode()?;
 
-  let cmd = kramer::Command::Hashes(kramer::HashCommand::Set(
-    constants::STICKBOT_SESSION_STORE,
-    kramer::Arity::One((&jwt, player.id.to_string())),
-    kramer::Insertion::Always,
-  ));
-
-  request.state().command(&cmd).await.map_err(|error| {
+  request.state().start_session(&jwt, &player.id).await.map_err(|error| {
     log::warn!("unable to persist tokent to session store - {}", error);
     error
   })?;
@@ -237,24 +209,10 @@ pub async fn complete(request: Request) -> Result {
 pub async fn logout(request: Request) -> Result {
   let cookie = get_cookie(&request).ok_or(Error::from_str(404, "no-session"))?;
 
-  let cmd = kramer::Command::Hashes::<&str, &str>(kramer::HashCommand::Del(
-    constants::STICKBOT_SESSION_STORE,
-    kramer::Arity::One(cookie.value()),
-  ));
-
-  log::trace!("removing session - {}", cmd);
-
-  request
-    .state()
-    .command(&cmd)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to clear session - {}", error);
-      error
-    })
-    .map(|result| {
-      log::trace!("session clear result - {:?}", result);
-    })?;
+  request.state().end_session(cookie.value()).await.map_err(|error| {
+    log::warn!("unable to clear session - {}", error);
+    error
+  })?;
 
   let destination = std::env::var(constants::STICKBOT_ONCORE_URL_ENV)
     .ok()
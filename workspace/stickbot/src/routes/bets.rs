@@ -16,16 +16,25 @@ struct BetPayload {
   kind: String,
   amount: u32,
   target: Option<u8>,
+  odds: Option<u32>,
   table: String,
   nonce: String,
 }
 
 impl BetPayload {
-  pub fn bet(&self) -> Option<BetState> {
+  // Odds on the pass line ride on whatever point the table has established, which is only known once the table has
+  // been loaded.
+  pub fn bet(&self, button: Option<u8>) -> Option<BetState> {
     match self.kind.as_str() {
       "come" => Some(BetState::Race(RaceType::Come, self.amount, None)),
       "pass" => Some(BetState::Race(RaceType::Pass, self.amount, None)),
-      "pass-odds" => Some(BetState::Target(TargetKind::PassOdds, self.amount, 0)),
+      "put" => self
+        .target
+        .map(|t| BetState::Race(RaceType::Pass, self.amount, Some(t))),
+      "put-come" => self
+        .target
+        .map(|t| BetState::Race(RaceType::Come, self.amount, Some(t))),
+      "pass-odds" => button.map(|point| BetState::Target(TargetKind::PassOdds, self.amount, point)),
 
       "come-odds" => self
         .target
@@ -35,8 +44,7 @@ impl BetPayload {
 
       "hardway" => self
         .target
-        .and_then(|raw| twowaiyo::Hardway::try_from(raw).ok().map(|_| raw))
-        .map(|validated| BetState::Target(TargetKind::Hardway, self.amount, validated)),
+        .map(|t| BetState::Target(TargetKind::Hardway, self.amount, t)),
 
       "place" => self.target.map(|t| BetState::Target(TargetKind::Place, self.amount, t)),
 
@@ -58,14 +66,11 @@ pub async fn create(mut request: Request) -> Result {
     .and_then(|authority| authority.player())
     .ok_or(Error::from_str(404, ""))?;
 
-  let bet = payload.bet().ok_or(Error::from_str(422, "bad-bet"))?;
   let tables = request.state().tables();
-  let search = crate::db::lookup_for_uuid(&payload.table);
-
-  log::debug!("attempting to place bet on table '{:?}': {:?}", payload.table, search);
+  log::debug!("attempting to place bet on table '{:?}'", payload.table);
 
   let state = tables
-    .find_one(search, None)
+    .find(&payload.table)
     .await
     .map_err(|error| {
       log::warn!("unable to find table - {}", error);
@@ -80,9 +85,20 @@ pub async fn create(mut request: Request) -> Result {
     return Err(Error::from_str(422, "bad-version"));
   }
 
+  let bet = payload.bet(state.button).ok_or(Error::from_str(422, "bad-bet"))?;
+
+  // Anything the engine can't turn into a real bet is rejected here rather than left for the worker.
+  twowaiyo::Bet::try_from(&bet).map_err(|error| {
+    log::warn!("rejecting invalid bet {:?} - {}", bet, error);
+    Error::from_str(422, "bad-bet")
+  })?;
+
   log::info!("player '{}' making bet '{:?}', submitting job", player.id, bet);
 
-  let job = bankah::jobs::TableJob::bet(bet, player.id.clone(), state.id.clone(), state.nonce.clone());
+  let job = match payload.odds {
+    Some(odds) => bankah::jobs::TableJob::put(bet, odds, player.id.clone(), state.id.clone(), state.nonce.clone()),
+    None => bankah::jobs::TableJob::bet(bet, player.id.clone(), state.id.clone(), state.nonce.clone()),
+  };
 
   request
     .state()
@@ -0,0 +1,58 @@
+use serde::Deserialize;
+
+use crate::constants;
+use crate::storage::HandRanking;
+use crate::web::{cookie as get_cookie, Body, Error, Request, Response, Result};
+
+#[derive(Debug, Default, Deserialize)]
+struct HandQuery {
+  #[serde(default)]
+  table: Option<String>,
+  #[serde(default)]
+  limit: Option<i64>,
+}
+
+// Finished hands, optionally for a single table, best first by the given ranking.
+async fn ranked(request: Request, ranking: HandRanking) -> Result {
+  let cookie = get_cookie(&request).ok_or(Error::from_str(404, "no-cook"))?;
+  request
+    .state()
+    .authority(cookie.value())
+    .await
+    .and_then(|authority| authority.player())
+    .ok_or(Error::from_str(404, ""))?;
+
+  let query = request.query::<HandQuery>().unwrap_or_else(|error| {
+    log::debug!("no hand query, using defaults - {}", error);
+    HandQuery::default()
+  });
+
+  let limit = query
+    .limit
+    .unwrap_or(constants::STICKBOT_DEFAULT_HAND_PAGE_SIZE)
+    .clamp(1, constants::STICKBOT_DEFAULT_HAND_PAGE_SIZE);
+
+  let page = request
+    .state()
+    .archive()
+    .hands(query.table.as_deref(), ranking, limit as usize)
+    .await
+    .map_err(|error| {
+      log::warn!("unable to query hands - {}", error);
+      Error::from_str(500, "load-hands")
+    })?;
+
+  Body::from_json(&page).map(|body| Response::builder(200).body(body).build())
+}
+
+// ## Route
+// The hands with the most rolls thrown before the shooter gave up the dice.
+pub async fn longest(request: Request) -> Result {
+  ranked(request, HandRanking::Longest).await
+}
+
+// ## Route
+// The hands that won the most for the table as a whole, after what was lost during them.
+pub async fn best(request: Request) -> Result {
+  ranked(request, HandRanking::Best).await
+}
@@ -20,28 +20,16 @@ pub async fn find(request: Request) -> Result {
     .ok_or(Error::from_str(404, ""))?;
 
   log::debug!("player '{}' checking on job '{}'", player.id, query.id);
-  let storage = format!("{}", crate::env::JobStore::Results);
-
-  let command = kramer::Command::Hashes(kramer::HashCommand::Get::<_, &str>(
-    storage.as_str(),
-    Some(kramer::Arity::One(query.id.as_str())),
-  ));
-
-  let response = request.state().command(&command).await.map_err(|error| {
-    log::warn!("unable to fetch requested job - {}", error);
-    Error::from_str(500, "bad-lookup")
-  })?;
-
-  let payload = match &response {
-    kramer::Response::Item(kramer::ResponseValue::String(inner)) => inner.clone(),
-    kramer::Response::Item(kramer::ResponseValue::Empty) => {
+  let payload = match request.state().job_result(&query.id).await {
+    Ok(Some(payload)) => payload,
+    Ok(None) => {
       log::debug!("nothing in job result store for '{}' yet", query.id);
       return Body::from_json(&JobResult::empty(query.id) as &JobResult<u8>)
         .map(|bod| Response::builder(200).body(bod).build());
     }
-    other => {
-      log::warn!("strange response from job lookup - {:?}", other);
-      return Ok(Response::builder(404).build());
+    Err(error) => {
+      log::warn!("unable to fetch requested job - {}", error);
+      return Err(Error::from_str(500, "bad-lookup"));
     }
   };
 
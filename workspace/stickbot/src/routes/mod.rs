@@ -4,6 +4,7 @@ pub mod account;
 pub mod admin;
 pub mod auth;
 pub mod bets;
+pub mod hands;
 pub mod jobs;
 pub mod rolls;
 pub mod tables;
@@ -26,6 +27,47 @@ impl Default for Heartbeat {
   }
 }
 
+// The full route table of the web api, shared by the stickbot binary and the route tests.
+pub fn app(services: crate::Services) -> tide::Server<crate::Services> {
+  let mut app = tide::with_state(services);
+
+  app.at("/heartbeat").get(heartbeat);
+
+  app.at("/auth/start").get(auth::start);
+  app.at("/auth/logout").get(auth::logout);
+  app.at("/auth/complete").get(auth::complete);
+  app.at("/auth/identify").get(auth::identify);
+
+  app.at("/tables").get(tables::list);
+  app.at("/tables").post(tables::create);
+  app.at("/table").get(tables::find);
+  app.at("/table/rules").get(tables::rules);
+  app.at("/table/stats").get(tables::stats);
+  app.at("/table/events").get(tide::sse::endpoint(tables::events));
+
+  app.at("/delete-account").post(account::delete);
+
+  app.at("/leave-table").post(tables::leave);
+  app.at("/join-table").post(tables::join);
+  app.at("/table/rebuy").post(tables::rebuy);
+  app.at("/table/cash-out").post(tables::cash_out);
+  app.at("/table/transfer").post(tables::transfer);
+  app.at("/table/tip").post(tables::tip);
+
+  app.at("/bets").post(bets::create);
+  app.at("/rolls").post(rolls::create);
+
+  app.at("/hands/longest").get(hands::longest);
+  app.at("/hands/best").get(hands::best);
+
+  app.at("/job").get(jobs::find);
+
+  app.at("/admin/drop-tables").get(admin::drop_all);
+  app.at("/admin/set-balance").get(admin::set_balance);
+
+  app
+}
+
 pub async fn heartbeat(request: Request) -> Result {
   let body = Body::from_json(&Heartbeat {
     version: format!("{}", request.state()),
@@ -45,3 +87,130 @@ pub async fn heartbeat(request: Request) -> Result {
   let response = Response::builder(200).body(body).build();
   Ok(response)
 }
+
+#[cfg(test)]
+mod test {
+  use crate::harness::Harness;
+  use serde_json::json;
+
+  #[test]
+  fn test_requires_session() {
+    let harness = Harness::new();
+
+    let (missing, invalid) = async_std::task::block_on(async {
+      (
+        harness.get("/tables", None).await,
+        harness.get("/tables", Some("not-a-session")).await,
+      )
+    });
+
+    assert_eq!(missing.status, 404);
+    assert_eq!(invalid.status, 404);
+  }
+
+  #[test]
+  fn test_logout_ends_session() {
+    let harness = Harness::new();
+
+    let (before, logout, after) = async_std::task::block_on(async {
+      let (_, session) = harness.player(1000).await;
+      let before = harness.get("/tables", Some(&session)).await;
+      let logout = harness.get("/auth/logout", Some(&session)).await;
+      (before, logout, harness.get("/tables", Some(&session)).await)
+    });
+
+    assert_eq!(before.status, 200);
+    assert_eq!(before.json(), json!([]));
+    assert_eq!(logout.status, 302);
+    assert_eq!(after.status, 404);
+  }
+
+  #[test]
+  fn test_join_while_seated() {
+    let harness = Harness::new();
+
+    let joined = async_std::task::block_on(async {
+      let (_, session) = harness.player(1000).await;
+      let created = harness.post("/tables", Some(&session), json!({ "buy_in": 500 })).await;
+      let job = created.json()["job"].as_str().unwrap().to_string();
+      let result = harness.complete(&session, &job).await;
+      let table = result["output"]["table_created"].as_str().unwrap().to_string();
+      harness
+        .post("/join-table", Some(&session), json!({ "id": table, "buy_in": 100 }))
+        .await
+    });
+
+    assert_eq!(joined.status, 422);
+    assert_eq!(joined.body, "AlreadySeated");
+  }
+
+  #[test]
+  fn test_table_lifecycle() {
+    let harness = Harness::new();
+
+    async_std::task::block_on(async {
+      let (player, session) = harness.player(1000).await;
+
+      let created = harness.post("/tables", Some(&session), json!({ "buy_in": 500 })).await;
+      assert_eq!(created.status, 200, "{}", created.body);
+      assert_eq!(created.json()["output"], json!(null));
+      let job = created.json()["job"].as_str().unwrap().to_string();
+      let result = harness.complete(&session, &job).await;
+      assert_eq!(result["id"], json!(job));
+      let table = result["output"]["table_created"].as_str().unwrap().to_string();
+
+      let found = harness.get(&format!("/table?id={}", table), Some(&session)).await;
+      assert_eq!(found.status, 200);
+      assert_eq!(found.json()["roller"], json!(player.id));
+      assert_eq!(found.json()["seats"][&player.id]["balance"], json!(500));
+
+      let bet = json!({ "kind": "field", "amount": 100, "table": table, "nonce": found.json()["nonce"] });
+      let placed = harness.post("/bets", Some(&session), bet).await;
+      assert_eq!(placed.status, 200, "{}", placed.body);
+      let job = placed.json()["job"].as_str().unwrap().to_string();
+      assert_eq!(harness.complete(&session, &job).await["output"], json!("bet_processed"));
+
+      let found = harness
+        .get(&format!("/table?id={}", table), Some(&session))
+        .await
+        .json();
+      assert_eq!(found["seats"][&player.id]["balance"], json!(400));
+
+      // Creating the table queued a reindex ahead of the bet, so the table is listed by now.
+      let listed = harness.get("/tables", Some(&session)).await.json();
+      assert_eq!(listed[0]["id"], json!(table));
+
+      let roll = json!({ "table": table, "nonce": found["nonce"] });
+      let rolled = harness.post("/rolls", Some(&session), roll).await;
+      assert_eq!(rolled.status, 200, "{}", rolled.body);
+      let job = rolled.json()["job"].as_str().unwrap().to_string();
+      assert_eq!(
+        harness.complete(&session, &job).await["output"],
+        json!("roll_processed")
+      );
+
+      let found = harness
+        .get(&format!("/table?id={}", table), Some(&session))
+        .await
+        .json();
+      assert_eq!(found["rolls"].as_array().map(|rolls| rolls.len()), Some(1));
+
+      let left = harness
+        .post("/leave-table", Some(&session), json!({ "id": table }))
+        .await;
+      assert_eq!(left.status, 200, "{}", left.body);
+      let job = left.json()["job"].as_str().unwrap().to_string();
+      assert_eq!(
+        harness.complete(&session, &job).await["output"],
+        json!("final_stand_ok")
+      );
+
+      let gone = harness.get(&format!("/table?id={}", table), Some(&session)).await;
+      assert_eq!(gone.status, 404);
+
+      let player = harness.services().players().find(&player.id).await.unwrap().unwrap();
+      assert!(player.tables.is_empty());
+      assert!(player.balance >= 900);
+    });
+  }
+}
@@ -1,6 +1,5 @@
 use serde::{Deserialize, Serialize};
 
-use crate::db;
 use crate::web::{cookie as get_cookie, Body, Error, Request, Response, Result};
 
 #[derive(Debug, Serialize)]
@@ -26,11 +25,10 @@ pub async fn create(mut request: Request) -> Result {
 
   log::debug!("player '{}' to roll on table '{}'", player.id, payload.table);
 
-  let lookup = db::lookup_for_uuid(&payload.table);
   let table = request
     .state()
     .tables()
-    .find_one(lookup, None)
+    .find(&payload.table)
     .await?
     .ok_or(Error::from_str(404, "not-found"))?;
 
@@ -51,15 +49,10 @@ pub async fn create(mut request: Request) -> Result {
 
   log::debug!("found table '{}' setting new nonce '{}'", next.id, nonce);
 
-  request
-    .state()
-    .tables()
-    .find_one_and_replace(db::lookup_for_uuid(&payload.table), &next, None)
-    .await
-    .map_err(|error| {
-      log::warn!("unable to update nonce of table - {}", error);
-      error
-    })?;
+  request.state().tables().save(&next).await.map_err(|error| {
+    log::warn!("unable to update nonce of table - {}", error);
+    error
+  })?;
 
   let job = bankah::jobs::TableJob::roll(next.id.clone(), nonce);
 
@@ -1,20 +1,29 @@
-use async_std::stream::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 use crate::constants;
-use crate::db::doc;
-use crate::web::{cookie as get_cookie, Body, Error, Request, Response, Result};
+use crate::web::{cookie as get_cookie, Body, Error, Request, Response, Result, Sender};
 
+use bankah::events::TableEvent;
 use bankah::jobs::TableJob;
-use bankah::state::PlayerState;
+use bankah::state::{DiceStatsState, FieldPreset, PlayerState, TableVariant};
 
 #[derive(Debug, Serialize)]
 enum JoinFailure {
+  AlreadySeated,
   TooManyActiveTables,
   InsufficientFunds,
 }
 
-fn join_failure(ps: &PlayerState) -> Option<JoinFailure> {
+// Players already holding a seat at the table (including an orphaned one) add chips to it with a rebuy instead.
+fn join_failure(ps: &PlayerState, table: Option<&str>, buy_in: Option<u32>) -> Option<JoinFailure> {
+  if table
+    .map(|id| ps.tables.iter().any(|seated| seated == id))
+    .unwrap_or(false)
+  {
+    return Some(JoinFailure::AlreadySeated);
+  }
+
   let max = std::env::var(constants::STICKBOT_MAX_ACTIVE_TABLES_PER_PLAYER_ENV)
     .ok()
     .and_then(|v| v.parse::<usize>().ok())
@@ -28,6 +37,10 @@ fn join_failure(ps: &PlayerState) -> Option<JoinFailure> {
     return Some(JoinFailure::InsufficientFunds);
   }
 
+  if buy_in.map(|amount| amount == 0 || amount > ps.balance).unwrap_or(false) {
+    return Some(JoinFailure::InsufficientFunds);
+  }
+
   return None;
 }
 
@@ -43,31 +56,60 @@ pub async fn list(request: Request) -> Result {
     .ok_or(Error::from_str(404, ""))?;
 
   log::trace!("listing tables for '{:?}'", player);
-  let collection = request.state().table_index();
-
-  let mut tables = collection.find(None, None).await.map_err(|error| {
+  let page = request.state().table_index().list(10).await.map_err(|error| {
     log::warn!("unable to query tables - {}", error);
     Error::from_str(500, "load-tables")
   })?;
 
-  let mut page = Vec::with_capacity(10);
+  Body::from_json(&page).map(|body| Response::builder(200).body(body).build())
+}
 
-  while let Some(doc) = tables.next().await {
-    if let Ok(state) = doc {
-      page.push(state)
-    }
+#[derive(Debug, Deserialize)]
+struct TableActionPayload {
+  pub id: String,
+}
 
-    if page.len() >= 10 {
-      break;
-    }
-  }
+#[derive(Debug, Deserialize)]
+struct TableJoinPayload {
+  pub id: String,
+  #[serde(default)]
+  pub buy_in: Option<u32>,
+}
 
-  Body::from_json(&page).map(|body| Response::builder(200).body(body).build())
+#[derive(Debug, Deserialize)]
+struct TableChipsPayload {
+  pub id: String,
+  pub amount: u32,
 }
 
 #[derive(Debug, Deserialize)]
-struct TableActionPayload {
+struct TableTransferPayload {
   pub id: String,
+  pub recipient: String,
+  pub amount: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TableCreatePayload {
+  #[serde(default)]
+  pub variant: TableVariant,
+  #[serde(default)]
+  pub field: FieldPreset,
+  #[serde(default)]
+  pub max_odds: Option<u32>,
+  #[serde(default)]
+  pub history: Option<usize>,
+  #[serde(default)]
+  pub buy_in: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct TableRules {
+  variant: TableVariant,
+  field: Vec<(u8, u8)>,
+  field_house_edge: f64,
+  max_odds: Option<u32>,
+  history: usize,
 }
 
 // ## Route
@@ -91,24 +133,108 @@ pub async fn find(request: Request) -> Result {
   let table = request
     .state()
     .tables()
-    .find_one(crate::db::doc! { "id": &query.id }, None)
+    .find(&query.id)
     .await
     .map_err(|error| {
       log::warn!("[info] unable to perform lookup - {}", error);
       Error::from_str(500, "bad service")
     })?
     .ok_or_else(|| {
-      log::warn!("unable to find table {:?}", crate::db::lookup_for_uuid(&query.id));
+      log::warn!("unable to find table '{}'", query.id);
       Error::from_str(404, "no-table")
     })?;
 
   Body::from_json(&table).map(|body| Response::builder(200).body(body).build())
 }
 
+// ## Route
+// Returns the rules of a specific table, including the house edge of its field paytable.
+pub async fn rules(request: Request) -> Result {
+  let cookie = get_cookie(&request).ok_or(Error::from_str(404, "no-cook"))?;
+  request
+    .state()
+    .authority(cookie.value())
+    .await
+    .and_then(|authority| authority.player())
+    .ok_or(Error::from_str(404, ""))?;
+
+  let query = request.query::<TableActionPayload>().map_err(|error| {
+    log::warn!("invalid lookup - {}", error);
+    Error::from_str(404, "not-found")
+  })?;
+
+  let table = request
+    .state()
+    .tables()
+    .find(&query.id)
+    .await
+    .map_err(|error| {
+      log::warn!("unable to perform lookup - {}", error);
+      Error::from_str(500, "bad service")
+    })?
+    .ok_or_else(|| Error::from_str(404, "no-table"))?;
+
+  let field = twowaiyo::FieldPaytable::from(&table.field);
+  let loaded = twowaiyo::Table::try_from(&table).map_err(|error| {
+    log::warn!("unable to load table '{}' - {}", table.id, error);
+    Error::from_str(500, "bad-table")
+  })?;
+  let rules = TableRules {
+    history: loaded.history(),
+    variant: table.variant,
+    field_house_edge: field.house_edge(),
+    field: table.field.pays,
+    max_odds: table.max_odds,
+  };
+
+  Body::from_json(&rules).map(|body| Response::builder(200).body(body).build())
+}
+
+// ## Route
+// Dice statistics for a table, built from every roll in the archive rather than the few kept with the table itself.
+pub async fn stats(request: Request) -> Result {
+  let cookie = get_cookie(&request).ok_or(Error::from_str(404, "no-cook"))?;
+  request
+    .state()
+    .authority(cookie.value())
+    .await
+    .and_then(|authority| authority.player())
+    .ok_or(Error::from_str(404, ""))?;
+
+  let query = request.query::<TableActionPayload>().map_err(|error| {
+    log::warn!("invalid lookup - {}", error);
+    Error::from_str(404, "not-found")
+  })?;
+
+  let rolls = request
+    .state()
+    .archive()
+    .rolls(&query.id, constants::STICKBOT_MAX_STATS_ROLLS)
+    .await
+    .map_err(|error| {
+      log::warn!("unable to query roll archive - {}", error);
+      Error::from_str(500, "bad service")
+    })?;
+
+  let mut stats = twowaiyo::DiceStats::default();
+
+  for archived in rolls {
+    if !stats.record(&IntoIterator::into_iter([archived.dice.0, archived.dice.1]).collect()) {
+      log::warn!(
+        "skipping archived roll with invalid dice {:?} on '{}'",
+        archived.dice,
+        query.id
+      );
+    }
+  }
+
+  Body::from_json(&DiceStatsState::from(&stats)).map(|body| Response::builder(200).body(body).build())
+}
+
 // ## Route
 // Joins a table.
 pub async fn join(mut request: Request) -> Result {
-  let query = request.body_json::<TableActionPayload>().await?;
+  let query = request.body_json::<TableJoinPayload>().await?;
   let cookie = get_cookie(&request).ok_or(Error::from_str(404, ""))?;
   let player = request
     .state()
@@ -117,12 +243,12 @@ pub async fn join(mut request: Request) -> Result {
     .and_then(|auth| auth.player())
     .ok_or(Error::from_str(404, "no-player"))?;
 
-  if let Some(reason) = join_failure(&player) {
+  if let Some(reason) = join_failure(&player, Some(&query.id), query.buy_in) {
     let body = Body::from_string(format!("{:?}", reason));
     return Ok(Response::builder(422).body(body).build());
   }
 
-  let job = TableJob::sit(query.id.to_string(), player.id.to_string());
+  let job = TableJob::sit(query.id.to_string(), player.id.to_string(), query.buy_in);
   let id = request.state().queue(&job).await.map_err(|error| {
     log::warn!("unable to queue sit job - {}", error);
     error
@@ -132,8 +258,14 @@ pub async fn join(mut request: Request) -> Result {
 }
 
 // ## Route
-// Creates a new table and sits the player.
-pub async fn create(request: Request) -> Result {
+// Creates a new table and sits the player. The request body is optional; when provided it may specify the variant of
+// craps, the field paytable and the odds limit that will be used at the new table, as well as how much of the player's
+// balance to buy in with.
+pub async fn create(mut request: Request) -> Result {
+  let payload = request.body_json::<TableCreatePayload>().await.unwrap_or_else(|error| {
+    log::debug!("no table creation payload, using defaults - {}", error);
+    TableCreatePayload::default()
+  });
   let cookie = get_cookie(&request).ok_or(Error::from_str(404, ""))?;
   let player = request
     .state()
@@ -142,12 +274,19 @@ pub async fn create(request: Request) -> Result {
     .and_then(|auth| auth.player())
     .ok_or(Error::from_str(404, "no-player"))?;
 
-  if let Some(reason) = join_failure(&player) {
+  if let Some(reason) = join_failure(&player, None, payload.buy_in) {
     let body = Body::from_string(format!("{:?}", reason));
     return Ok(Response::builder(422).body(body).build());
   }
 
-  let job = TableJob::Create(bankah::jobs::JobWapper::wrap(player.id.to_string()));
+  let job = TableJob::create(
+    player.id.to_string(),
+    payload.variant,
+    payload.field,
+    payload.max_odds,
+    payload.history,
+    payload.buy_in,
+  );
   let id = request.state().queue(&job).await.map_err(|error| {
     log::warn!("unable to queue table creation job - '{}'", error);
     error
@@ -185,3 +324,154 @@ pub async fn leave(mut request: Request) -> Result {
   let res = bankah::JobResponse { job: id, output: None };
   Body::from_json(&res).map(|body| Response::builder(200).body(body).build())
 }
+
+// Chips can only be moved for a table the player is sitting at; whether the amount is covered is left to the job.
+async fn chips(mut request: Request, rebuy: bool) -> Result {
+  let query = request.body_json::<TableChipsPayload>().await?;
+  let cookie = get_cookie(&request).ok_or(Error::from_str(404, "unauth"))?;
+  let player = request
+    .state()
+    .authority(cookie.value())
+    .await
+    .and_then(|auth| auth.player())
+    .ok_or(Error::from_str(404, "no-player"))?;
+
+  if query.amount == 0 || (rebuy && query.amount > player.balance) {
+    let body = Body::from_string(format!("{:?}", JoinFailure::InsufficientFunds));
+    return Ok(Response::builder(422).body(body).build());
+  }
+
+  if !player.tables.contains(&query.id) {
+    return Err(Error::from_str(422, "not-seated"));
+  }
+
+  let job = match rebuy {
+    true => TableJob::rebuy(query.id.to_string(), player.id.to_string(), query.amount),
+    false => TableJob::cash_out(query.id.to_string(), player.id.to_string(), query.amount),
+  };
+
+  let id = request.state().queue(&job).await.map_err(|error| {
+    log::warn!("unable to queue chips job - {}", error);
+    error
+  })?;
+  let res = bankah::JobResponse { job: id, output: None };
+  Body::from_json(&res).map(|body| Response::builder(200).body(body).build())
+}
+
+// ## Route
+// Buys more chips for the player's seat from their account balance.
+pub async fn rebuy(request: Request) -> Result {
+  chips(request, true).await
+}
+
+// ## Route
+// Returns part of the player's seat balance to their account without leaving the table.
+pub async fn cash_out(request: Request) -> Result {
+  chips(request, false).await
+}
+
+async fn send(request: &Request, table: String, recipient: Option<String>, amount: u32) -> Result {
+  let cookie = get_cookie(request).ok_or(Error::from_str(404, "unauth"))?;
+  let player = request
+    .state()
+    .authority(cookie.value())
+    .await
+    .and_then(|auth| auth.player())
+    .ok_or(Error::from_str(404, "no-player"))?;
+
+  if amount == 0 {
+    let body = Body::from_string(format!("{:?}", JoinFailure::InsufficientFunds));
+    return Ok(Response::builder(422).body(body).build());
+  }
+
+  if !player.tables.contains(&table) {
+    return Err(Error::from_str(422, "not-seated"));
+  }
+
+  let job = TableJob::transfer(table, player.id.to_string(), recipient, amount);
+
+  let id = request.state().queue(&job).await.map_err(|error| {
+    log::warn!("unable to queue transfer job - {}", error);
+    error
+  })?;
+  let res = bankah::JobResponse { job: id, output: None };
+  Body::from_json(&res).map(|body| Response::builder(200).body(body).build())
+}
+
+// ## Route
+// Sends chips from the player's seat to another player seated at the same table.
+pub async fn transfer(mut request: Request) -> Result {
+  let query = request.body_json::<TableTransferPayload>().await?;
+  send(&request, query.id, Some(query.recipient), query.amount).await
+}
+
+// ## Route
+// Tips the dealer from the player's seat; the chips go to the house.
+pub async fn tip(mut request: Request) -> Result {
+  let query = request.body_json::<TableChipsPayload>().await?;
+  send(&request, query.id, None, query.amount).await
+}
+
+// ## Route
+// Streams the events published for a table as server-sent events, named after the event's kind. The stream ends when
+// the connection to redis is lost, or once an event can't be delivered to the client.
+pub async fn events(request: Request, sender: Sender) -> Result<()> {
+  let cookie = get_cookie(&request).ok_or(Error::from_str(404, "no-cook"))?;
+  request
+    .state()
+    .authority(cookie.value())
+    .await
+    .and_then(|authority| authority.player())
+    .ok_or(Error::from_str(404, ""))?;
+
+  let query = request.query::<TableActionPayload>().map_err(|error| {
+    log::warn!("invalid lookup - {}", error);
+    Error::from_str(404, "not-found")
+  })?;
+
+  request
+    .state()
+    .tables()
+    .find(&query.id)
+    .await
+    .map_err(|error| {
+      log::warn!("unable to perform lookup - {}", error);
+      Error::from_str(500, "bad service")
+    })?
+    .ok_or_else(|| Error::from_str(404, "not-found"))?;
+
+  let mut subscription = request.state().subscribe(&query.id).await.map_err(|error| {
+    log::warn!("unable to subscribe to table events - {}", error);
+    Error::from_str(500, "bad service")
+  })?;
+
+  log::debug!("streaming events for table '{}'", query.id);
+
+  loop {
+    // Nothing is written to a quiet stream otherwise, and writing is the only way to notice the client has gone.
+    let payload = match subscription.next_within(constants::STICKBOT_EVENT_KEEP_ALIVE).await? {
+      Some(payload) => payload,
+      None => {
+        if let Err(error) = sender.send("keep_alive", "", None).await {
+          log::debug!("event stream for table '{}' closed - {}", query.id, error);
+          return Ok(());
+        }
+
+        continue;
+      }
+    };
+
+    let name = match serde_json::from_str::<TableEvent>(&payload) {
+      Ok(event) => event.name(),
+      Err(error) => {
+        log::warn!("skipping unreadable table event - {}", error);
+        continue;
+      }
+    };
+
+    if let Err(error) = sender.send(name, &payload, None).await {
+      log::debug!("event stream for table '{}' closed - {}", query.id, error);
+      return Ok(());
+    }
+  }
+}
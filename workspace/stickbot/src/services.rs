@@ -1,118 +1,130 @@
 use std::io::{Error, ErrorKind, Result};
 
+use async_std::io::BufReader;
 use async_std::net::TcpStream;
+use async_std::prelude::*;
 use async_std::sync::Arc;
-use async_std::sync::Mutex;
 
 use crate::auth;
 use crate::constants;
 use crate::db;
-
-async fn connect_redis(config: &crate::redis::RedisConfig) -> Result<TcpStream> {
-  log::debug!("redis configuration - '{}', connecting", config.host);
-  let mut redis = TcpStream::connect(format!("{}:{}", config.host, config.port)).await?;
-  log::debug!("connection established - {:?}, authenticating", redis.peer_addr());
-
-  if config.password.len() > 0 {
-    let cmd = kramer::Command::Auth::<&String, &String>(kramer::AuthCredentials::Password(&config.password));
-    let result = kramer::execute(&mut redis, cmd).await?;
-    log::debug!("authentication result - {:?}", result);
-  }
-
-  Ok(redis)
+use crate::env::{QueueBackend, StorageBackend};
+use crate::pubsub;
+use crate::queue::{JobQueue, MemoryQueue, RedisQueue};
+use crate::sessions::{MemorySessions, RedisSessions, SessionStore};
+use crate::storage::{
+  ArchiveRepository, MemoryStore, MongoStore, PlayerRepository, TableIndexRepository, TableRepository,
+};
+
+fn unavailable(name: &str) -> Error {
+  Error::new(ErrorKind::Other, format!("{}-unavailable", name))
 }
 
-fn response_string(response: &kramer::ResponseValue) -> Option<String> {
-  match response {
-    kramer::ResponseValue::String(inner) => Some(inner.clone()),
-    res => {
-      log::warn!("strange response from job queue - {:?}", res);
-      None
-    }
-  }
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_disabled() -> Error {
+  Error::new(ErrorKind::Other, "sqlite-disabled, rebuild with `--features sqlite`")
 }
 
-fn parse_pop(response: &kramer::ResponseValue) -> Option<bankah::jobs::TableJob> {
-  response_string(&response).and_then(|contents| serde_json::from_str::<bankah::jobs::TableJob>(&contents).ok())
-}
+type Repositories = (
+  Arc<dyn TableRepository>,
+  Arc<dyn PlayerRepository>,
+  Arc<dyn TableIndexRepository>,
+  Arc<dyn ArchiveRepository>,
+);
 
 #[derive(Clone)]
 pub struct Services {
-  db: db::Client,
-  redis: Arc<Mutex<TcpStream>>,
-  rc: crate::redis::RedisConfig,
+  redis: Option<crate::redis::Connection>,
+  jobs: Arc<dyn JobQueue>,
+  sessions: Arc<dyn SessionStore>,
+  tables: Arc<dyn TableRepository>,
+  players: Arc<dyn PlayerRepository>,
+  index: Arc<dyn TableIndexRepository>,
+  archive: Arc<dyn ArchiveRepository>,
+  max_transfer: u32,
   version: String,
 }
 
 impl Services {
-  pub fn collection<T, N>(&self, name: N) -> db::Collection<T>
-  where
-    N: AsRef<str>,
-  {
-    let db = self.db.database(constants::MONGO_DB_DATABASE_NAME);
-    db.collection::<T>(name.as_ref())
+  pub fn table_index(&self) -> &dyn TableIndexRepository {
+    self.index.as_ref()
+  }
+
+  pub fn tables(&self) -> &dyn TableRepository {
+    self.tables.as_ref()
   }
 
-  pub fn table_index(&self) -> db::Collection<bankah::state::TableIndexState> {
-    self.collection(&format!("{}", crate::env::Collection::TableList))
+  pub fn players(&self) -> &dyn PlayerRepository {
+    self.players.as_ref()
   }
 
-  pub fn tables(&self) -> db::Collection<bankah::state::TableState> {
-    self.collection(&format!("{}", crate::env::Collection::Tables))
+  pub fn archive(&self) -> &dyn ArchiveRepository {
+    self.archive.as_ref()
   }
 
-  pub fn players(&self) -> db::Collection<bankah::state::PlayerState> {
-    self.collection(&format!("{}", crate::env::Collection::Players))
+  // The most that can be sent from one seat in a single transfer or tip.
+  pub fn max_transfer(&self) -> u32 {
+    self.max_transfer
   }
 
   pub async fn pop(&self) -> Result<Option<bankah::jobs::TableJob>> {
-    let cmd = kramer::Command::List::<_, String>(kramer::ListCommand::Pop(
-      kramer::Side::Left,
-      crate::env::JobStore::Queue,
-      Some((None, 3)),
-    ));
+    self.jobs.pop().await
+  }
 
-    let result = match self.command(&cmd).await {
-      Err(error) => {
-        log::warn!("unable to pop from bet queue - {}", error);
-        return Err(Error::new(ErrorKind::Other, format!("{}", error)));
-      }
-      Ok(kramer::Response::Item(kramer::ResponseValue::Empty)) => {
-        log::debug!("empty response from queue");
-        return Ok(None);
-      }
-      Ok(kramer::Response::Array(values)) => values,
-      Ok(kramer::Response::Error) => {
-        log::warn!("unable to pop from queue - redis error");
-        return Err(Error::new(ErrorKind::Other, "invalid-response"));
-      }
-      Ok(kramer::Response::Item(inner)) => {
-        log::warn!("unknown response from pop - '{:?}'", inner);
-        return Err(Error::new(ErrorKind::Other, format!("{:?}", inner)));
-      }
-    };
+  pub async fn queue(&self, job: &bankah::jobs::TableJob) -> Result<String> {
+    self.jobs.push(job).await
+  }
 
-    log::debug!("result from pop - {:?}, attempting to deserialize", result);
+  // Results are stored serialized, exactly as they will be returned to whoever asks for them.
+  pub async fn store_result(&self, id: &str, result: &str) -> Result<()> {
+    self.jobs.store(id, result).await
+  }
 
-    Ok(result.get(1).and_then(parse_pop))
+  pub async fn job_result(&self, id: &str) -> Result<Option<String>> {
+    self.jobs.fetch(id).await
   }
 
-  pub async fn queue(&self, job: &bankah::jobs::TableJob) -> Result<String> {
-    let serialized = serde_json::to_string(&job).map_err(|error| {
-      log::warn!("unable to serialize job - {}", error);
+  // Publishing is best-effort; nobody may be listening, and a missed event only means a client falls back to polling.
+  // Without redis, nobody can be listening.
+  pub async fn publish(&self, table: &str, event: &bankah::events::TableEvent) -> Result<()> {
+    let redis = match self.redis.as_ref() {
+      Some(redis) => redis,
+      None => return Ok(()),
+    };
+    let serialized = serde_json::to_string(&event).map_err(|error| {
+      log::warn!("unable to serialize event - {}", error);
       Error::new(ErrorKind::Other, format!("{}", error))
     })?;
+    let command = pubsub::encode(&["PUBLISH", &pubsub::channel(table), &serialized]);
 
-    let command = kramer::Command::List(kramer::ListCommand::Push(
-      (kramer::Side::Right, kramer::Insertion::Always),
-      crate::env::JobStore::Queue,
-      kramer::Arity::One(serialized),
-    ));
+    let mut lock = redis.lock().await;
+    let redis: &mut TcpStream = &mut lock;
+    redis.write_all(command.as_bytes()).await?;
 
-    self.command(&command).await.map(|result| {
-      log::debug!("executed queue command - {:?}", result);
-      job.id()
-    })
+    match pubsub::read(&mut BufReader::new(redis)).await? {
+      pubsub::Reply::Integer(listeners) => {
+        log::debug!("published '{}' to {} listener(s)", event.name(), listeners);
+        Ok(())
+      }
+      other => Err(Error::new(
+        ErrorKind::Other,
+        format!("unexpected publish reply - {:?}", other),
+      )),
+    }
+  }
+
+  pub async fn subscribe(&self, table: &str) -> Result<pubsub::Subscription> {
+    let config = self.redis.as_ref().ok_or_else(|| unavailable("redis"))?.config();
+    let redis = crate::redis::connect(config).await?;
+    pubsub::Subscription::open(redis, &pubsub::channel(table)).await
+  }
+
+  pub async fn start_session(&self, token: &str, player: &str) -> Result<()> {
+    self.sessions.create(token, player).await
+  }
+
+  pub async fn end_session(&self, token: &str) -> Result<()> {
+    self.sessions.remove(token).await
   }
 
   pub async fn authority<T>(&self, token: T) -> Option<auth::Authority>
@@ -120,30 +132,20 @@ impl Services {
     T: std::fmt::Display,
   {
     let token = format!("{}", token);
-    let cmd = kramer::Command::Hashes::<&str, &str>(kramer::HashCommand::Get(
-      constants::STICKBOT_SESSION_STORE,
-      Some(kramer::Arity::One(&token)),
-    ));
-
-    // TODO: should the storage value be used? is a hash the right move here?
-    match self
-      .command(&cmd)
-      .await
-      .map_err(|error| {
-        log::warn!("unable to issue redis session command - {error}");
-        error
-      })
-      .ok()?
-    {
-      kramer::Response::Item(kramer::ResponseValue::String(inner)) => Some(inner),
-      other => {
-        log::warn!("session-store lookup missing or invalid - {:?}", other);
-        None
+
+    match self.sessions.find(&token).await {
+      Ok(Some(_)) => (),
+      Ok(None) => {
+        log::warn!("session-store lookup missing");
+        return None;
+      }
+      Err(error) => {
+        log::warn!("unable to query session store - {error}");
+        return None;
       }
-    }?;
+    }
 
     let claims = auth::Claims::decode(&token).ok()?;
-    let collection = self.players();
     let admins = std::env::var(constants::STICKBOT_ADMIN_EMAILS_ENV).unwrap_or_default();
 
     log::debug!(
@@ -153,23 +155,19 @@ impl Services {
       claims.id
     );
 
-    // TODO(player-id): the player id is serialized as a string when peristing into the players collection during the
-    // completion of the oauth flow.
-    collection
-      .find_one(db::doc! { "oid": claims.oid, "id": claims.id}, None)
+    self
+      .players()
+      .find(&claims.id)
       .await
       .map_err(|error| {
         log::warn!("unable to query player collection - {error}");
         error
       })
       .ok()
-      .or_else(|| {
-        log::warn!("unable to find player matching query...");
-        None
-      })
       .and_then(|maybe_player| maybe_player)
+      .filter(|player| player.oid == claims.oid)
       .or_else(|| {
-        log::warn!("[mongo-problem?] unable to make player from query result.");
+        log::warn!("unable to find player matching query...");
         None
       })
       .map(
@@ -185,53 +183,16 @@ impl Services {
     S: std::fmt::Display,
     V: std::fmt::Display,
   {
-    self.inner_command(command, 0).await
-  }
-
-  #[allow(unused_assignments)]
-  async fn inner_command<S, V>(&self, command: &kramer::Command<S, V>, mut attempt: u8) -> Result<kramer::Response>
-  where
-    S: std::fmt::Display,
-    V: std::fmt::Display,
-  {
-    loop {
-      if attempt > 10 {
-        log::warn!("failed redis connection after {} attempts", attempt);
-        return Err(Error::new(ErrorKind::Other, "too-many-attempts"));
-      }
-
-      log::debug!("requesting tcp write access through lock (attempt {})", attempt);
-      let mut lock = self.redis.lock().await;
-      let mut redis: &mut TcpStream = &mut lock;
-      log::debug!("lock acquired, attempting to send command");
-
-      match async_std::future::timeout(std::time::Duration::from_secs(5), kramer::execute(&mut redis, command)).await {
-        Err(timeout_error) => {
-          log::warn!("timeout error during command transfer - {}", timeout_error);
-          *lock = connect_redis(&self.rc).await?;
-          attempt += 1;
-          return Err(Error::new(ErrorKind::Other, "timeout-error"));
-        }
-        Ok(Err(error)) => {
-          log::warn!("failed executing command - {}", error);
-
-          if error.kind() == ErrorKind::BrokenPipe {
-            log::warn!("broken pipe, attempting to re-establish connection");
-            *lock = connect_redis(&self.rc).await?;
-          }
-
-          attempt += 1;
-        }
-        Ok(Ok(response)) => {
-          log::debug!("redis success - {:?} (attempt {})", response, attempt);
-          return Ok(response);
-        }
-      }
-    }
+    self
+      .redis
+      .as_ref()
+      .ok_or_else(|| unavailable("redis"))?
+      .command(command)
+      .await
   }
 
   pub async fn status(&self) -> Result<()> {
-    let redis = self.redis.lock().await;
+    let redis = self.redis.as_ref().ok_or_else(|| unavailable("redis"))?.lock().await;
     redis.peer_addr().map(|addr| {
       log::debug!("addr - {:?}", addr);
       ()
@@ -239,26 +200,114 @@ impl Services {
   }
 
   pub async fn new() -> Result<Self> {
-    let mc = std::env::var(constants::MONGO_DB_ENV_URL).map_err(|error| {
-      log::warn!("unable to find mongo config '{}' in environment", error);
-      Error::new(ErrorKind::Other, "missing-redis-config")
-    })?;
-    let rc = crate::redis::from_env().ok_or(Error::new(ErrorKind::Other, "missing-redis-config"))?;
+    let (storage, queue) = (crate::env::storage_backend(), crate::env::queue_backend());
+
+    // Redis is only required when it holds the job queue. Without it, sessions are kept in memory and table events go
+    // unpublished.
+    let redis = match (crate::redis::from_env(), queue) {
+      (Some(rc), _) => {
+        log::info!("connecting to redis...");
+        Some(crate::redis::Connection::open(rc).await?)
+      }
+      (None, QueueBackend::Redis) => return Err(Error::new(ErrorKind::Other, "missing-redis-config")),
+      (None, _) => {
+        log::info!("no redis configured, sessions will be kept in memory and table events will not be published");
+        None
+      }
+    };
+
+    // The queue and storage share a single database file when either of them is kept in sqlite.
+    #[cfg(feature = "sqlite")]
+    let sqlite = match storage == StorageBackend::Sqlite || queue == QueueBackend::Sqlite {
+      true => {
+        let path = crate::env::sqlite_path();
+        log::info!("opening sqlite database '{}'...", path);
+        Some(Arc::new(crate::storage::SqliteStore::open(path)?))
+      }
+      false => None,
+    };
 
-    log::info!("connecting to mongo...");
-    let mongo = db::connect(mc).await?;
+    let jobs: Arc<dyn JobQueue> = match queue {
+      QueueBackend::Redis => Arc::new(RedisQueue::new(redis.clone().ok_or_else(|| unavailable("redis"))?)),
+      QueueBackend::Memory => Arc::new(MemoryQueue::default()),
+      #[cfg(feature = "sqlite")]
+      QueueBackend::Sqlite => sqlite.clone().ok_or_else(|| unavailable("sqlite"))?,
+      #[cfg(not(feature = "sqlite"))]
+      QueueBackend::Sqlite => return Err(sqlite_disabled()),
+    };
 
-    log::info!("connecting to redis...");
-    let redis = connect_redis(&rc).await?;
+    let (tables, players, index, archive): Repositories = match storage {
+      StorageBackend::Mongo => {
+        let mc = std::env::var(constants::MONGO_DB_ENV_URL).map_err(|error| {
+          log::warn!("unable to find mongo config '{}' in environment", error);
+          Error::new(ErrorKind::Other, "missing-mongo-config")
+        })?;
+
+        log::info!("connecting to mongo...");
+        let mongo = db::connect(mc).await?;
+        let store = Arc::new(MongoStore::new(mongo));
+        store.ensure_indexes().await?;
+        (store.clone(), store.clone(), store.clone(), store)
+      }
+      #[cfg(feature = "sqlite")]
+      StorageBackend::Sqlite => {
+        let store = sqlite.clone().ok_or_else(|| unavailable("sqlite"))?;
+        (store.clone(), store.clone(), store.clone(), store)
+      }
+      #[cfg(not(feature = "sqlite"))]
+      StorageBackend::Sqlite => return Err(sqlite_disabled()),
+    };
+
+    // Sessions are kept in redis when there is one, otherwise alongside everything else when that is a sqlite file.
+    let sessions: Arc<dyn SessionStore> = match redis.clone() {
+      Some(redis) => Arc::new(RedisSessions::new(redis)),
+      #[cfg(feature = "sqlite")]
+      None if storage == StorageBackend::Sqlite => sqlite.ok_or_else(|| unavailable("sqlite"))?,
+      None => Arc::new(MemorySessions::default()),
+    };
 
     log::info!("services ready!");
     Ok(Services {
-      db: mongo,
-      rc: rc,
-      redis: Arc::new(Mutex::new(redis)),
+      sessions,
+      redis,
+      jobs,
+      tables,
+      players,
+      index,
+      archive,
+      max_transfer: crate::env::max_transfer(),
       version: std::option_env!("TWOWAIYO_VERSION").unwrap_or("dev").to_string(),
     })
   }
+
+  // Swaps out where players are kept, so tests can see how failures are handled.
+  #[cfg(test)]
+  pub(crate) fn with_players(self, players: Arc<dyn PlayerRepository>) -> Self {
+    Services { players, ..self }
+  }
+
+  #[cfg(test)]
+  pub(crate) fn with_tables(self, tables: Arc<dyn TableRepository>) -> Self {
+    Services { tables, ..self }
+  }
+
+  // Everything kept in memory, with no mongo or redis connection. Table events are unavailable; this is meant for
+  // tests.
+  pub fn in_memory() -> Self {
+    let store = Arc::new(MemoryStore::default());
+
+    Services {
+      redis: None,
+      jobs: Arc::new(MemoryQueue::default()),
+      sessions: Arc::new(MemorySessions::default()),
+      tables: store.clone(),
+      players: store.clone(),
+      index: store.clone(),
+      archive: store,
+      max_transfer: constants::STICKBOT_DEFAULT_MAX_TRANSFER,
+      version: "memory".to_string(),
+    }
+  }
 }
 
 impl std::fmt::Display for Services {
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+use async_std::sync::Mutex;
+use async_trait::async_trait;
+
+use crate::constants;
+use crate::redis::Connection;
+
+// Session tokens handed out at the end of the oauth flow, mapped to the id of the player they were issued for.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+  async fn create(&self, token: &str, player: &str) -> Result<()>;
+
+  async fn find(&self, token: &str) -> Result<Option<String>>;
+
+  async fn remove(&self, token: &str) -> Result<()>;
+}
+
+// Sessions kept in a redis hash, shared by every web process.
+pub struct RedisSessions {
+  redis: Connection,
+}
+
+impl RedisSessions {
+  pub(crate) fn new(redis: Connection) -> Self {
+    RedisSessions { redis }
+  }
+}
+
+#[async_trait]
+impl SessionStore for RedisSessions {
+  async fn create(&self, token: &str, player: &str) -> Result<()> {
+    let cmd = kramer::Command::Hashes(kramer::HashCommand::Set(
+      constants::STICKBOT_SESSION_STORE,
+      kramer::Arity::One((token, player)),
+      kramer::Insertion::Always,
+    ));
+
+    self.redis.command(&cmd).await.map(|_| ())
+  }
+
+  async fn find(&self, token: &str) -> Result<Option<String>> {
+    let cmd = kramer::Command::Hashes::<&str, &str>(kramer::HashCommand::Get(
+      constants::STICKBOT_SESSION_STORE,
+      Some(kramer::Arity::One(token)),
+    ));
+
+    // TODO: should the storage value be used? is a hash the right move here?
+    match self.redis.command(&cmd).await? {
+      kramer::Response::Item(kramer::ResponseValue::String(inner)) => Ok(Some(inner)),
+      kramer::Response::Item(kramer::ResponseValue::Empty) => Ok(None),
+      other => {
+        log::warn!("session-store lookup invalid - {:?}", other);
+        Err(Error::new(ErrorKind::Other, format!("{:?}", other)))
+      }
+    }
+  }
+
+  async fn remove(&self, token: &str) -> Result<()> {
+    let cmd = kramer::Command::Hashes::<&str, &str>(kramer::HashCommand::Del(
+      constants::STICKBOT_SESSION_STORE,
+      kramer::Arity::One(token),
+    ));
+
+    log::trace!("removing session - {}", cmd);
+
+    self.redis.command(&cmd).await.map(|result| {
+      log::trace!("session clear result - {:?}", result);
+    })
+  }
+}
+
+// Sessions kept in memory, only recognized by the process that issued them.
+#[derive(Default)]
+pub struct MemorySessions {
+  sessions: Mutex<HashMap<String, String>>,
+}
+
+#[async_trait]
+impl SessionStore for MemorySessions {
+  async fn create(&self, token: &str, player: &str) -> Result<()> {
+    let mut sessions = self.sessions.lock().await;
+    sessions.insert(token.to_string(), player.to_string());
+    Ok(())
+  }
+
+  async fn find(&self, token: &str) -> Result<Option<String>> {
+    let sessions = self.sessions.lock().await;
+    Ok(sessions.get(token).cloned())
+  }
+
+  async fn remove(&self, token: &str) -> Result<()> {
+    self.sessions.lock().await.remove(token);
+    Ok(())
+  }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::io::Result;
+
+use async_std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+
+use bankah::state::{
+  HandState, HistoryState, PlayerState, RollArchiveState, TableIndexState, TableState, TransferState,
+};
+
+use super::{ArchiveRepository, HandRanking, PlayerRepository, TableIndexRepository, TableRepository};
+
+// Tables, players, the table index and the archives kept in memory. Clones share the same storage.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+  tables: Arc<Mutex<HashMap<String, TableState>>>,
+  players: Arc<Mutex<HashMap<String, PlayerState>>>,
+  index: Arc<Mutex<HashMap<String, TableIndexState>>>,
+  hands: Arc<Mutex<Vec<HandState>>>,
+  rolls: Arc<Mutex<Vec<RollArchiveState>>>,
+  transfers: Arc<Mutex<Vec<TransferState>>>,
+}
+
+#[async_trait]
+impl TableRepository for MemoryStore {
+  async fn find(&self, id: &str) -> Result<Option<TableState>> {
+    Ok(self.tables.lock().await.get(id).cloned())
+  }
+
+  async fn save(&self, table: &TableState) -> Result<()> {
+    self.tables.lock().await.insert(table.id.clone(), table.clone());
+    Ok(())
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self.tables.lock().await.remove(id);
+    Ok(())
+  }
+
+  async fn seated(&self, player: &str) -> Result<Vec<TableState>> {
+    let tables = self.tables.lock().await;
+    Ok(
+      tables
+        .values()
+        .filter(|table| table.seats.contains_key(player))
+        .cloned()
+        .collect(),
+    )
+  }
+
+  async fn clear(&self) -> Result<()> {
+    self.tables.lock().await.clear();
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl PlayerRepository for MemoryStore {
+  async fn find(&self, id: &str) -> Result<Option<PlayerState>> {
+    Ok(self.players.lock().await.get(id).cloned())
+  }
+
+  async fn find_or_create(&self, player: &PlayerState) -> Result<PlayerState> {
+    let mut players = self.players.lock().await;
+
+    if let Some(existing) = players.values().find(|existing| existing.oid == player.oid) {
+      return Ok(existing.clone());
+    }
+
+    players.insert(player.id.clone(), player.clone());
+    Ok(player.clone())
+  }
+
+  async fn set_seating(&self, id: &str, balance: u32, tables: &[String]) -> Result<bool> {
+    let mut players = self.players.lock().await;
+
+    Ok(match players.get_mut(id) {
+      Some(player) => {
+        player.balance = balance;
+        player.tables = tables.to_vec();
+        true
+      }
+      None => false,
+    })
+  }
+
+  async fn set_balance(&self, id: &str, balance: u32) -> Result<()> {
+    if let Some(player) = self.players.lock().await.get_mut(id) {
+      player.balance = balance;
+    }
+
+    Ok(())
+  }
+
+  async fn adjust(&self, id: &str, amount: i64) -> Result<bool> {
+    let mut players = self.players.lock().await;
+
+    let player = match players.get_mut(id) {
+      Some(player) => player,
+      None => return Ok(false),
+    };
+
+    Ok(match super::adjusted(player.balance, amount) {
+      Some(balance) => {
+        player.balance = balance;
+        true
+      }
+      None => false,
+    })
+  }
+
+  async fn credit(&self, id: &str, amount: u32, entry: &HistoryState, left: Option<&str>) -> Result<()> {
+    if let Some(player) = self.players.lock().await.get_mut(id) {
+      if super::recorded(player, entry) {
+        return Ok(());
+      }
+
+      player.balance += amount;
+      player.history.push(entry.clone());
+      player.tables.retain(|table| Some(table.as_str()) != left);
+    }
+
+    Ok(())
+  }
+
+  async fn record(&self, id: &str, entry: &HistoryState) -> Result<()> {
+    if let Some(player) = self.players.lock().await.get_mut(id) {
+      if !super::recorded(player, entry) {
+        player.history.push(entry.clone());
+      }
+    }
+
+    Ok(())
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self.players.lock().await.remove(id);
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl TableIndexRepository for MemoryStore {
+  async fn list(&self, limit: usize) -> Result<Vec<TableIndexState>> {
+    let index = self.index.lock().await;
+    Ok(index.values().take(limit).cloned().collect())
+  }
+
+  // Like the mongo index, entries for tables that no longer exist are left alone.
+  async fn reindex(&self) -> Result<()> {
+    let tables = self.tables.lock().await;
+    let mut index = self.index.lock().await;
+
+    for table in tables.values() {
+      index.insert(table.id.clone(), super::index_entry(table));
+    }
+
+    Ok(())
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self.index.lock().await.remove(id);
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl ArchiveRepository for MemoryStore {
+  async fn record_hands(&self, hands: &[HandState]) -> Result<()> {
+    self.hands.lock().await.extend_from_slice(hands);
+    Ok(())
+  }
+
+  async fn hands(&self, table: Option<&str>, ranking: HandRanking, limit: usize) -> Result<Vec<HandState>> {
+    let mut hands = self
+      .hands
+      .lock()
+      .await
+      .iter()
+      .filter(|hand| table.map(|table| hand.table == table).unwrap_or(true))
+      .cloned()
+      .collect::<Vec<HandState>>();
+
+    match ranking {
+      HandRanking::Longest => hands.sort_by_key(|hand| std::cmp::Reverse((hand.rolls, hand.points_made))),
+      HandRanking::Best => hands.sort_by_key(|hand| std::cmp::Reverse((hand.net, hand.rolls))),
+    }
+
+    hands.truncate(limit);
+    Ok(hands)
+  }
+
+  async fn archive_roll(&self, roll: &RollArchiveState) -> Result<()> {
+    self.rolls.lock().await.push(roll.clone());
+    Ok(())
+  }
+
+  async fn rolls(&self, table: &str, limit: usize) -> Result<Vec<RollArchiveState>> {
+    let rolls = self.rolls.lock().await;
+    let mut rolls = rolls
+      .iter()
+      .filter(|roll| roll.table == table)
+      .cloned()
+      .collect::<Vec<RollArchiveState>>();
+    let skipped = rolls.len().saturating_sub(limit);
+    Ok(rolls.split_off(skipped))
+  }
+
+  async fn record_transfer(&self, transfer: &TransferState) -> Result<()> {
+    self.transfers.lock().await.push(transfer.clone());
+    Ok(())
+  }
+}
@@ -0,0 +1,125 @@
+use std::io::Result;
+
+use async_trait::async_trait;
+
+use bankah::state::{
+  HandState, HistoryState, PlayerState, RollArchiveState, TableIndexState, TableState, TransferState,
+};
+
+mod memory;
+mod mongo;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use memory::MemoryStore;
+pub(crate) use mongo::MongoStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+#[async_trait]
+pub trait TableRepository: Send + Sync {
+  async fn find(&self, id: &str) -> Result<Option<TableState>>;
+
+  // Replaces the stored table, inserting it if it doesn't exist yet.
+  async fn save(&self, table: &TableState) -> Result<()>;
+
+  async fn delete(&self, id: &str) -> Result<()>;
+
+  // Every table the player has a seat at.
+  async fn seated(&self, player: &str) -> Result<Vec<TableState>>;
+
+  async fn clear(&self) -> Result<()>;
+}
+
+#[async_trait]
+pub trait PlayerRepository: Send + Sync {
+  async fn find(&self, id: &str) -> Result<Option<PlayerState>>;
+
+  // Players are looked up by the id their identity provider gave them; the first time they're seen, the given player
+  // is stored as-is.
+  async fn find_or_create(&self, player: &PlayerState) -> Result<PlayerState>;
+
+  // Updates the player's balance along with the tables they are sitting at. Returns false if there is no such player.
+  async fn set_seating(&self, id: &str, balance: u32, tables: &[String]) -> Result<bool>;
+
+  async fn set_balance(&self, id: &str, balance: u32) -> Result<()>;
+
+  // Adds the amount to the player's balance, or takes it away when negative. Nothing is changed if that would leave
+  // the balance below zero; returns whether the balance was changed.
+  async fn adjust(&self, id: &str, amount: i64) -> Result<bool>;
+
+  // Adds to the player's balance and history together. When `left` is given, that table is also removed from the
+  // tables the player is sitting at. Entries with an id are only ever applied once; crediting one that is already in
+  // the player's history does nothing, so a credit can safely be retried.
+  async fn credit(&self, id: &str, amount: u32, entry: &HistoryState, left: Option<&str>) -> Result<()>;
+
+  // Adds to the player's history alone, leaving their balance as it is. As with credits, an entry with an id that is
+  // already in the player's history is not added again.
+  async fn record(&self, id: &str, entry: &HistoryState) -> Result<()>;
+
+  async fn delete(&self, id: &str) -> Result<()>;
+}
+
+#[async_trait]
+pub trait TableIndexRepository: Send + Sync {
+  async fn list(&self, limit: usize) -> Result<Vec<TableIndexState>>;
+
+  // Rebuilds the index entries from the tables as they are currently stored.
+  async fn reindex(&self) -> Result<()>;
+
+  async fn delete(&self, id: &str) -> Result<()>;
+}
+
+// How finished hands are ordered when they are listed, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandRanking {
+  // The most rolls thrown before the shooter gave up the dice, then the most points made.
+  Longest,
+  // The most won for the table as a whole after what was lost, then the most rolls.
+  Best,
+}
+
+// Records kept for looking back on rather than for play: finished hands, every roll thrown and chips sent between
+// players.
+#[async_trait]
+pub trait ArchiveRepository: Send + Sync {
+  async fn record_hands(&self, hands: &[HandState]) -> Result<()>;
+
+  // Finished hands, optionally only those played at a single table.
+  async fn hands(&self, table: Option<&str>, ranking: HandRanking, limit: usize) -> Result<Vec<HandState>>;
+
+  async fn archive_roll(&self, roll: &RollArchiveState) -> Result<()>;
+
+  // The latest `limit` rolls archived for the table, in the order they were thrown.
+  async fn rolls(&self, table: &str, limit: usize) -> Result<Vec<RollArchiveState>>;
+
+  async fn record_transfer(&self, transfer: &TransferState) -> Result<()>;
+}
+
+// The balance after adjusting it by `amount`, unless that would take it below zero (or past the largest balance).
+pub(crate) fn adjusted(balance: u32, amount: i64) -> Option<u32> {
+  use std::convert::TryFrom;
+  u32::try_from(i64::from(balance) + amount).ok()
+}
+
+// Whether the entry has already been recorded in the player's history.
+pub(crate) fn recorded(player: &PlayerState, entry: &HistoryState) -> bool {
+  entry
+    .id()
+    .map(|id| player.history.iter().any(|existing| existing.id() == Some(id)))
+    .unwrap_or(false)
+}
+
+// The index entry for a table; names of the players seated there, keyed by their ids.
+pub(crate) fn index_entry(table: &TableState) -> TableIndexState {
+  TableIndexState {
+    id: table.id.clone(),
+    name: table.name.clone(),
+    population: table
+      .seats
+      .iter()
+      .map(|(id, seat)| (id.clone(), seat.nickname.clone()))
+      .collect(),
+    variant: table.variant.clone(),
+  }
+}
@@ -0,0 +1,379 @@
+use std::io::Result;
+
+use async_std::stream::StreamExt;
+use async_trait::async_trait;
+
+use bankah::state::{
+  HandState, HistoryState, PlayerState, RollArchiveState, TableIndexState, TableState, TransferState,
+};
+
+use super::{ArchiveRepository, HandRanking, PlayerRepository, TableIndexRepository, TableRepository};
+use crate::db::{self, doc, mongo_error};
+
+pub(crate) struct MongoStore {
+  client: db::Client,
+}
+
+impl MongoStore {
+  pub(crate) fn new(client: db::Client) -> Self {
+    MongoStore { client }
+  }
+
+  // Creates the indexes the queries here rely on; creating an index that already exists does nothing.
+  pub(crate) async fn ensure_indexes(&self) -> Result<()> {
+    let by_table = db::IndexModel::builder().keys(doc! { "table": 1 }).build();
+
+    self
+      .roll_archive()
+      .create_index(by_table, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  fn collection<T>(&self, name: crate::env::Collection) -> db::Collection<T> {
+    let db = self.client.database(crate::constants::MONGO_DB_DATABASE_NAME);
+    db.collection::<T>(&format!("{}", name))
+  }
+
+  fn tables(&self) -> db::Collection<TableState> {
+    self.collection(crate::env::Collection::Tables)
+  }
+
+  fn players(&self) -> db::Collection<PlayerState> {
+    self.collection(crate::env::Collection::Players)
+  }
+
+  fn index(&self) -> db::Collection<TableIndexState> {
+    self.collection(crate::env::Collection::TableList)
+  }
+
+  fn hands(&self) -> db::Collection<HandState> {
+    self.collection(crate::env::Collection::Hands)
+  }
+
+  fn roll_archive(&self) -> db::Collection<RollArchiveState> {
+    self.collection(crate::env::Collection::RollArchive)
+  }
+
+  fn transfers(&self) -> db::Collection<TransferState> {
+    self.collection(crate::env::Collection::Transfers)
+  }
+}
+
+fn bson_error(error: db::bson::ser::Error) -> std::io::Error {
+  std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}", error))
+}
+
+#[async_trait]
+impl TableRepository for MongoStore {
+  async fn find(&self, id: &str) -> Result<Option<TableState>> {
+    self
+      .tables()
+      .find_one(doc! { "id": id }, None)
+      .await
+      .map_err(mongo_error)
+  }
+
+  async fn save(&self, table: &TableState) -> Result<()> {
+    // TODO(mongo-uuid): we're using a `find_one_and_replace` w/ the upsert call here to circumvent the serialization
+    // discrepency between insertion and find/replace methods on the mongodb driver collection.
+    let options = db::FindOneAndReplaceOptions::builder().upsert(true).build();
+
+    self
+      .tables()
+      .find_one_and_replace(db::lookup_for_uuid(&table.id), table, Some(options))
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self
+      .tables()
+      .delete_one(doc! { "id": id }, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn seated(&self, player: &str) -> Result<Vec<TableState>> {
+    let mut cursor = self
+      .tables()
+      .find(doc! { format!("seats.{}", player): { "$exists": true } }, None)
+      .await
+      .map_err(mongo_error)?;
+    let mut tables = Vec::new();
+
+    while let Some(doc) = cursor.next().await {
+      match doc {
+        Ok(table) => tables.push(table),
+        Err(error) => log::warn!("unable to load next table - {}", error),
+      }
+    }
+
+    Ok(tables)
+  }
+
+  async fn clear(&self) -> Result<()> {
+    self.tables().drop(None).await.map_err(mongo_error)
+  }
+}
+
+// Matches the player, unless the entry is already in their history; matching nothing is what keeps retries from paying
+// (or recording) twice.
+fn unrecorded(id: &str, entry: &HistoryState) -> db::bson::Document {
+  let mut filter = doc! { "id": id };
+
+  if let Some(entry) = entry.id() {
+    filter.insert(
+      "$nor",
+      vec![
+        doc! { "history.settlement.id": entry },
+        doc! { "history.transfer.id": entry },
+      ],
+    );
+  }
+
+  filter
+}
+
+#[async_trait]
+impl PlayerRepository for MongoStore {
+  async fn find(&self, id: &str) -> Result<Option<PlayerState>> {
+    self
+      .players()
+      .find_one(doc! { "id": id }, None)
+      .await
+      .map_err(mongo_error)
+  }
+
+  async fn find_or_create(&self, player: &PlayerState) -> Result<PlayerState> {
+    let options = db::FindOneAndUpdateOptions::builder()
+      .upsert(true)
+      .return_document(db::ReturnDocument::After)
+      .build();
+    let serialized = db::bson::to_bson(player).map_err(bson_error)?;
+
+    self
+      .players()
+      .find_one_and_update(
+        doc! { "oid": &player.oid },
+        doc! { "$setOnInsert": serialized },
+        options,
+      )
+      .await
+      .map_err(mongo_error)?
+      .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "missing-player"))
+  }
+
+  async fn set_seating(&self, id: &str, balance: u32, tables: &[String]) -> Result<bool> {
+    let options = db::FindOneAndUpdateOptions::builder()
+      .return_document(db::ReturnDocument::After)
+      .build();
+
+    // TODO(player-id): the player id is serialized as a string when peristing into the players collection during the
+    // completion of the oauth flow.
+    self
+      .players()
+      .find_one_and_update(
+        doc! { "id": id },
+        doc! { "$set": { "balance": balance, "tables": tables } },
+        options,
+      )
+      .await
+      .map(|updated| updated.is_some())
+      .map_err(mongo_error)
+  }
+
+  async fn set_balance(&self, id: &str, balance: u32) -> Result<()> {
+    self
+      .players()
+      .update_one(doc! { "id": id }, doc! { "$set": { "balance": balance } }, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn adjust(&self, id: &str, amount: i64) -> Result<bool> {
+    let filter = match amount < 0 {
+      true => doc! { "id": id, "balance": { "$gte": -amount } },
+      false => doc! { "id": id },
+    };
+
+    self
+      .players()
+      .update_one(filter, doc! { "$inc": { "balance": amount } }, None)
+      .await
+      .map(|result| result.modified_count > 0)
+      .map_err(mongo_error)
+  }
+
+  async fn credit(&self, id: &str, amount: u32, entry: &HistoryState, left: Option<&str>) -> Result<()> {
+    let record = db::bson::to_bson(entry).map_err(bson_error)?;
+    let mut updates = doc! {
+      "$inc": { "balance": amount },
+      "$push": { "history": record },
+    };
+
+    if let Some(table) = left {
+      updates.insert("$pull", doc! { "tables": table });
+    }
+
+    self
+      .players()
+      .update_one(unrecorded(id, entry), updates, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn record(&self, id: &str, entry: &HistoryState) -> Result<()> {
+    let record = db::bson::to_bson(entry).map_err(bson_error)?;
+
+    self
+      .players()
+      .update_one(unrecorded(id, entry), doc! { "$push": { "history": record } }, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self
+      .players()
+      .delete_one(doc! { "id": id }, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+}
+
+#[async_trait]
+impl TableIndexRepository for MongoStore {
+  async fn list(&self, limit: usize) -> Result<Vec<TableIndexState>> {
+    let mut cursor = self.index().find(None, None).await.map_err(mongo_error)?;
+    let mut page = Vec::with_capacity(limit);
+
+    while let Some(doc) = cursor.next().await {
+      if let Ok(state) = doc {
+        page.push(state)
+      }
+
+      if page.len() >= limit {
+        break;
+      }
+    }
+
+    Ok(page)
+  }
+
+  async fn reindex(&self) -> Result<()> {
+    let destination = format!("{}", crate::env::Collection::TableList);
+
+    let pipeline = vec![
+      doc! { "$project": { "id": 1, "name": 1, "variant": 1, "seats": { "$objectToArray": "$seats" } } },
+      doc! { "$project": { "id": 1, "name": 1, "variant": 1, "population": {
+        "$map": {
+          "input": "$seats",
+          "as": "seat",
+          "in": ["$$seat.k", "$$seat.v.nickname"],
+        },
+      } } },
+      doc! { "$merge": { "into": destination } },
+    ];
+
+    self
+      .tables()
+      .aggregate(pipeline, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self
+      .index()
+      .delete_one(doc! { "id": id }, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+}
+
+#[async_trait]
+impl ArchiveRepository for MongoStore {
+  async fn record_hands(&self, hands: &[HandState]) -> Result<()> {
+    if hands.is_empty() {
+      return Ok(());
+    }
+
+    self
+      .hands()
+      .insert_many(hands, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn hands(&self, table: Option<&str>, ranking: HandRanking, limit: usize) -> Result<Vec<HandState>> {
+    let sort = match ranking {
+      HandRanking::Longest => doc! { "rolls": -1, "points_made": -1 },
+      HandRanking::Best => doc! { "net": -1, "rolls": -1 },
+    };
+    let filter = table.map(|table| doc! { "table": table });
+    let options = db::FindOptions::builder().sort(sort).limit(limit as i64).build();
+    let mut cursor = self.hands().find(filter, options).await.map_err(mongo_error)?;
+    let mut page = Vec::with_capacity(limit);
+
+    while let Some(doc) = cursor.next().await {
+      match doc {
+        Ok(hand) => page.push(hand),
+        Err(error) => log::warn!("unable to deserialize hand - {}", error),
+      }
+    }
+
+    Ok(page)
+  }
+
+  async fn archive_roll(&self, roll: &RollArchiveState) -> Result<()> {
+    self
+      .roll_archive()
+      .insert_one(roll, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+
+  async fn rolls(&self, table: &str, limit: usize) -> Result<Vec<RollArchiveState>> {
+    let options = db::FindOptions::builder()
+      .sort(doc! { "_id": -1 })
+      .limit(limit as i64)
+      .build();
+    let mut cursor = self
+      .roll_archive()
+      .find(doc! { "table": table }, options)
+      .await
+      .map_err(mongo_error)?;
+    let mut rolls = Vec::new();
+
+    while let Some(doc) = cursor.next().await {
+      match doc {
+        Ok(roll) => rolls.push(roll),
+        Err(error) => log::warn!("unable to deserialize archived roll - {}", error),
+      }
+    }
+
+    rolls.reverse();
+    Ok(rolls)
+  }
+
+  async fn record_transfer(&self, transfer: &TransferState) -> Result<()> {
+    self
+      .transfers()
+      .insert_one(transfer, None)
+      .await
+      .map(|_| ())
+      .map_err(mongo_error)
+  }
+}
@@ -0,0 +1,794 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rusqlite::{params, OptionalExtension, TransactionBehavior};
+
+use bankah::jobs::TableJob;
+use bankah::state::{
+  HandState, HistoryState, PlayerState, RollArchiveState, TableIndexState, TableState, TransferState,
+};
+
+use super::{ArchiveRepository, HandRanking, PlayerRepository, TableIndexRepository, TableRepository};
+use crate::queue::JobQueue;
+use crate::sessions::SessionStore;
+
+// How long `pop` waits for a job to show up, and how often it checks while waiting.
+const POP_WAIT: Duration = Duration::from_secs(3);
+const POP_INTERVAL: Duration = Duration::from_millis(100);
+
+// Applied in order, each one exactly once; the number applied so far is kept in the database's `user_version`. New
+// migrations are added to the end of this list, existing ones are never changed.
+const MIGRATIONS: &[&str] = &[
+  "
+  CREATE TABLE tables (id TEXT PRIMARY KEY NOT NULL, state TEXT NOT NULL);
+  CREATE TABLE players (id TEXT PRIMARY KEY NOT NULL, oid TEXT NOT NULL UNIQUE, state TEXT NOT NULL);
+  CREATE TABLE table_index (id TEXT PRIMARY KEY NOT NULL, state TEXT NOT NULL);
+  CREATE TABLE jobs (position INTEGER PRIMARY KEY AUTOINCREMENT, job TEXT NOT NULL);
+  CREATE TABLE job_results (id TEXT PRIMARY KEY NOT NULL, result TEXT NOT NULL);
+",
+  "
+  CREATE TABLE hands (id TEXT PRIMARY KEY NOT NULL, table_id TEXT NOT NULL, state TEXT NOT NULL);
+  CREATE INDEX hands_by_table ON hands (table_id);
+  CREATE TABLE roll_archive (position INTEGER PRIMARY KEY AUTOINCREMENT, table_id TEXT NOT NULL, state TEXT NOT NULL);
+  CREATE INDEX roll_archive_by_table ON roll_archive (table_id, position);
+  CREATE TABLE transfers (id TEXT PRIMARY KEY NOT NULL, state TEXT NOT NULL);
+  CREATE TABLE sessions (token TEXT PRIMARY KEY NOT NULL, player TEXT NOT NULL);
+",
+];
+
+// The sqlite version of the mongo aggregation; builds an index entry from every table's seats and merges it into the
+// index, leaving entries for tables that no longer exist alone.
+const REINDEX: &str = "
+  INSERT INTO table_index (id, state)
+  SELECT tables.id, json_object(
+    'id', json_extract(tables.state, '$.id'),
+    'name', json_extract(tables.state, '$.name'),
+    'variant', json_extract(tables.state, '$.variant'),
+    'population', (
+      SELECT json_group_array(json_array(seat.key, json_extract(seat.value, '$.nickname')))
+      FROM json_each(tables.state, '$.seats') AS seat
+    )
+  )
+  FROM tables WHERE true
+  ON CONFLICT (id) DO UPDATE SET state = excluded.state
+";
+
+fn sqlite_error(error: rusqlite::Error) -> Error {
+  Error::new(ErrorKind::Other, format!("{}", error))
+}
+
+fn serde_error(error: serde_json::Error) -> Error {
+  Error::new(ErrorKind::InvalidData, format!("{}", error))
+}
+
+fn decode<T: serde::de::DeserializeOwned>(state: Option<String>) -> Result<Option<T>> {
+  state
+    .map(|state| serde_json::from_str(&state).map_err(serde_error))
+    .transpose()
+}
+
+// Decodes every row that can be, skipping (and logging) any that can't.
+fn decode_all<T: serde::de::DeserializeOwned>(states: Vec<String>, kind: &str) -> Vec<T> {
+  let mut decoded = Vec::with_capacity(states.len());
+
+  for state in states {
+    match serde_json::from_str(&state) {
+      Ok(item) => decoded.push(item),
+      Err(error) => log::warn!("unable to load next {} - {}", kind, error),
+    }
+  }
+
+  decoded
+}
+
+fn migrate(connection: &mut rusqlite::Connection) -> rusqlite::Result<()> {
+  let applied: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+  for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+    log::info!("applying sqlite migration {}", version + 1);
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(migration)?;
+    transaction.pragma_update(None, "user_version", version + 1)?;
+    transaction.commit()?;
+  }
+
+  Ok(())
+}
+
+// Everything stickbot keeps (tables, players, the table index, the archives, sessions and the job queue) in a single
+// sqlite database file, so that the web process and worker can share it. Records are stored as the same json documents
+// that would otherwise be sent to mongo.
+//
+// Statements block while they wait on the file (and on other processes writing to it), so they are run on the blocking
+// thread pool rather than the calling task. Clones share the same connection.
+#[derive(Clone)]
+pub struct SqliteStore {
+  connection: Arc<Mutex<rusqlite::Connection>>,
+  wait: Duration,
+}
+
+impl SqliteStore {
+  // Opens (creating if necessary) the database at `path`, bringing its schema up to date.
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let connection = rusqlite::Connection::open(path).map_err(sqlite_error)?;
+
+    // The web process and worker will both be writing; let readers continue while that happens, and let writers wait
+    // a moment on each other instead of failing immediately.
+    connection
+      .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+      .map_err(sqlite_error)?;
+    connection.busy_timeout(Duration::from_secs(5)).map_err(sqlite_error)?;
+
+    SqliteStore::prepare(connection, POP_WAIT)
+  }
+
+  // A private database that disappears with the store; meant for tests.
+  pub fn in_memory(wait: Duration) -> Result<Self> {
+    let connection = rusqlite::Connection::open_in_memory().map_err(sqlite_error)?;
+    SqliteStore::prepare(connection, wait)
+  }
+
+  fn prepare(mut connection: rusqlite::Connection, wait: Duration) -> Result<Self> {
+    migrate(&mut connection).map_err(sqlite_error)?;
+
+    Ok(SqliteStore {
+      connection: Arc::new(Mutex::new(connection)),
+      wait,
+    })
+  }
+
+  async fn with<F, T>(&self, operation: F) -> Result<T>
+  where
+    F: FnOnce(&mut rusqlite::Connection) -> rusqlite::Result<T> + Send + 'static,
+    T: Send + 'static,
+  {
+    let connection = self.connection.clone();
+
+    async_std::task::spawn_blocking(move || {
+      let mut connection = connection
+        .lock()
+        .map_err(|_| Error::new(ErrorKind::Other, "sqlite-connection-poisoned"))?;
+
+      operation(&mut connection).map_err(sqlite_error)
+    })
+    .await
+  }
+
+  // Loads, changes and saves a player inside a single write transaction, returning whatever the change did. Returns
+  // none if there is no such player.
+  async fn update_player<F, T>(&self, id: &str, change: F) -> Result<Option<T>>
+  where
+    F: FnOnce(&mut PlayerState) -> T + Send + 'static,
+    T: Send + 'static,
+  {
+    let id = id.to_string();
+
+    self
+      .with(move |connection| {
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let state: Option<String> = transaction
+          .query_row("SELECT state FROM players WHERE id = ?1", [&id], |row| row.get(0))
+          .optional()?;
+
+        let mut player = match state
+          .map(|state| serde_json::from_str::<PlayerState>(&state))
+          .transpose()
+        {
+          Ok(Some(player)) => player,
+          Ok(None) => return Ok(Ok(None)),
+          Err(error) => return Ok(Err(serde_error(error))),
+        };
+
+        let changed = change(&mut player);
+
+        let serialized = match serde_json::to_string(&player) {
+          Ok(serialized) => serialized,
+          Err(error) => return Ok(Err(serde_error(error))),
+        };
+
+        transaction.execute("UPDATE players SET state = ?2 WHERE id = ?1", params![id, serialized])?;
+        transaction.commit()?;
+
+        Ok(Ok(Some(changed)))
+      })
+      .await?
+  }
+
+  // Takes the oldest job off the queue, if there is one.
+  async fn take(&self) -> Result<Option<String>> {
+    self
+      .with(|connection| {
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let next = transaction
+          .query_row("SELECT position, job FROM jobs ORDER BY position LIMIT 1", [], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+          })
+          .optional()?;
+
+        if let Some((position, _)) = next.as_ref() {
+          transaction.execute("DELETE FROM jobs WHERE position = ?1", [position])?;
+        }
+
+        transaction.commit()?;
+        Ok(next.map(|(_, job)| job))
+      })
+      .await
+  }
+
+  // The `state` of every row the query returns.
+  async fn states<P>(&self, query: &'static str, parameters: P) -> Result<Vec<String>>
+  where
+    P: rusqlite::Params + Send + 'static,
+  {
+    self
+      .with(move |connection| {
+        let mut statement = connection.prepare(query)?;
+        let rows = statement.query_map(parameters, |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+      })
+      .await
+  }
+
+  async fn state<P>(&self, query: &'static str, parameters: P) -> Result<Option<String>>
+  where
+    P: rusqlite::Params + Send + 'static,
+  {
+    self
+      .with(move |connection| connection.query_row(query, parameters, |row| row.get(0)).optional())
+      .await
+  }
+
+  async fn execute<P>(&self, query: &'static str, parameters: P) -> Result<usize>
+  where
+    P: rusqlite::Params + Send + 'static,
+  {
+    self.with(move |connection| connection.execute(query, parameters)).await
+  }
+}
+
+#[async_trait]
+impl TableRepository for SqliteStore {
+  async fn find(&self, id: &str) -> Result<Option<TableState>> {
+    let state = self
+      .state("SELECT state FROM tables WHERE id = ?1", [id.to_string()])
+      .await?;
+
+    decode(state)
+  }
+
+  async fn save(&self, table: &TableState) -> Result<()> {
+    let serialized = serde_json::to_string(table).map_err(serde_error)?;
+
+    self
+      .execute(
+        "INSERT INTO tables (id, state) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET state = excluded.state",
+        [table.id.clone(), serialized],
+      )
+      .await?;
+
+    Ok(())
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self
+      .execute("DELETE FROM tables WHERE id = ?1", [id.to_string()])
+      .await?;
+    Ok(())
+  }
+
+  async fn seated(&self, player: &str) -> Result<Vec<TableState>> {
+    let states = self
+      .states(
+        "SELECT state FROM tables WHERE EXISTS (
+          SELECT 1 FROM json_each(tables.state, '$.seats') AS seat WHERE seat.key = ?1
+        )",
+        [player.to_string()],
+      )
+      .await?;
+
+    Ok(decode_all(states, "table"))
+  }
+
+  async fn clear(&self) -> Result<()> {
+    self.execute("DELETE FROM tables", []).await?;
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl PlayerRepository for SqliteStore {
+  async fn find(&self, id: &str) -> Result<Option<PlayerState>> {
+    let state = self
+      .state("SELECT state FROM players WHERE id = ?1", [id.to_string()])
+      .await?;
+
+    decode(state)
+  }
+
+  async fn find_or_create(&self, player: &PlayerState) -> Result<PlayerState> {
+    let serialized = serde_json::to_string(player).map_err(serde_error)?;
+    let (id, oid) = (player.id.clone(), player.oid.clone());
+
+    let state = self
+      .with(move |connection| {
+        connection.execute(
+          "INSERT OR IGNORE INTO players (id, oid, state) VALUES (?1, ?2, ?3)",
+          params![id, oid, serialized],
+        )?;
+
+        connection
+          .query_row("SELECT state FROM players WHERE oid = ?1", [&oid], |row| row.get(0))
+          .optional()
+      })
+      .await?;
+
+    decode(state)?.ok_or_else(|| Error::new(ErrorKind::NotFound, "missing-player"))
+  }
+
+  async fn set_seating(&self, id: &str, balance: u32, tables: &[String]) -> Result<bool> {
+    let tables = tables.to_vec();
+
+    self
+      .update_player(id, move |player| {
+        player.balance = balance;
+        player.tables = tables;
+      })
+      .await
+      .map(|updated| updated.is_some())
+  }
+
+  async fn set_balance(&self, id: &str, balance: u32) -> Result<()> {
+    self
+      .update_player(id, move |player| player.balance = balance)
+      .await
+      .map(|_| ())
+  }
+
+  async fn adjust(&self, id: &str, amount: i64) -> Result<bool> {
+    self
+      .update_player(id, move |player| match super::adjusted(player.balance, amount) {
+        Some(balance) => {
+          player.balance = balance;
+          true
+        }
+        None => false,
+      })
+      .await
+      .map(|adjusted| adjusted.unwrap_or(false))
+  }
+
+  async fn credit(&self, id: &str, amount: u32, entry: &HistoryState, left: Option<&str>) -> Result<()> {
+    let (entry, left) = (entry.clone(), left.map(String::from));
+
+    self
+      .update_player(id, move |player| {
+        if super::recorded(player, &entry) {
+          return;
+        }
+
+        player.balance += amount;
+        player.history.push(entry);
+        player.tables.retain(|table| Some(table) != left.as_ref());
+      })
+      .await
+      .map(|_| ())
+  }
+
+  async fn record(&self, id: &str, entry: &HistoryState) -> Result<()> {
+    let entry = entry.clone();
+
+    self
+      .update_player(id, move |player| {
+        if !super::recorded(player, &entry) {
+          player.history.push(entry);
+        }
+      })
+      .await
+      .map(|_| ())
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self
+      .execute("DELETE FROM players WHERE id = ?1", [id.to_string()])
+      .await?;
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl TableIndexRepository for SqliteStore {
+  async fn list(&self, limit: usize) -> Result<Vec<TableIndexState>> {
+    let states = self
+      .states("SELECT state FROM table_index LIMIT ?1", [limit as i64])
+      .await?;
+
+    Ok(
+      states
+        .iter()
+        .filter_map(|state| serde_json::from_str(state).ok())
+        .collect(),
+    )
+  }
+
+  async fn reindex(&self) -> Result<()> {
+    self.execute(REINDEX, []).await?;
+    Ok(())
+  }
+
+  async fn delete(&self, id: &str) -> Result<()> {
+    self
+      .execute("DELETE FROM table_index WHERE id = ?1", [id.to_string()])
+      .await?;
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl ArchiveRepository for SqliteStore {
+  async fn record_hands(&self, hands: &[HandState]) -> Result<()> {
+    let rows = hands
+      .iter()
+      .map(|hand| serde_json::to_string(hand).map(|state| (hand.id.clone(), hand.table.clone(), state)))
+      .collect::<serde_json::Result<Vec<(String, String, String)>>>()
+      .map_err(serde_error)?;
+
+    self
+      .with(move |connection| {
+        let transaction = connection.transaction()?;
+
+        for (id, table, state) in rows.iter() {
+          transaction.execute(
+            "INSERT OR IGNORE INTO hands (id, table_id, state) VALUES (?1, ?2, ?3)",
+            params![id, table, state],
+          )?;
+        }
+
+        transaction.commit()
+      })
+      .await
+  }
+
+  async fn hands(&self, table: Option<&str>, ranking: HandRanking, limit: usize) -> Result<Vec<HandState>> {
+    let query = match ranking {
+      HandRanking::Longest => {
+        "SELECT state FROM hands WHERE ?1 IS NULL OR table_id = ?1
+         ORDER BY json_extract(state, '$.rolls') DESC, json_extract(state, '$.points_made') DESC LIMIT ?2"
+      }
+      HandRanking::Best => {
+        "SELECT state FROM hands WHERE ?1 IS NULL OR table_id = ?1
+         ORDER BY json_extract(state, '$.net') DESC, json_extract(state, '$.rolls') DESC LIMIT ?2"
+      }
+    };
+
+    let table = table.map(String::from);
+    let states = self
+      .with(move |connection| {
+        let mut statement = connection.prepare(query)?;
+        let rows = statement.query_map(params![table, limit as i64], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+      })
+      .await?;
+
+    Ok(decode_all(states, "hand"))
+  }
+
+  async fn archive_roll(&self, roll: &RollArchiveState) -> Result<()> {
+    let serialized = serde_json::to_string(roll).map_err(serde_error)?;
+
+    self
+      .execute(
+        "INSERT INTO roll_archive (table_id, state) VALUES (?1, ?2)",
+        [roll.table.clone(), serialized],
+      )
+      .await?;
+
+    Ok(())
+  }
+
+  async fn rolls(&self, table: &str, limit: usize) -> Result<Vec<RollArchiveState>> {
+    let query = "SELECT state FROM (
+       SELECT state, position FROM roll_archive WHERE table_id = ?1 ORDER BY position DESC LIMIT ?2
+     ) ORDER BY position";
+
+    let table = table.to_string();
+    let states = self
+      .with(move |connection| {
+        let mut statement = connection.prepare(query)?;
+        let rows = statement.query_map(params![table, limit as i64], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+      })
+      .await?;
+
+    Ok(decode_all(states, "archived roll"))
+  }
+
+  async fn record_transfer(&self, transfer: &TransferState) -> Result<()> {
+    let serialized = serde_json::to_string(transfer).map_err(serde_error)?;
+
+    self
+      .execute(
+        "INSERT OR IGNORE INTO transfers (id, state) VALUES (?1, ?2)",
+        [transfer.id.clone(), serialized],
+      )
+      .await?;
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl SessionStore for SqliteStore {
+  async fn create(&self, token: &str, player: &str) -> Result<()> {
+    self
+      .execute(
+        "INSERT INTO sessions (token, player) VALUES (?1, ?2) ON CONFLICT (token) DO UPDATE SET player = excluded.player",
+        [token.to_string(), player.to_string()],
+      )
+      .await?;
+
+    Ok(())
+  }
+
+  async fn find(&self, token: &str) -> Result<Option<String>> {
+    self
+      .state("SELECT player FROM sessions WHERE token = ?1", [token.to_string()])
+      .await
+  }
+
+  async fn remove(&self, token: &str) -> Result<()> {
+    self
+      .execute("DELETE FROM sessions WHERE token = ?1", [token.to_string()])
+      .await?;
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl JobQueue for SqliteStore {
+  async fn push(&self, job: &TableJob) -> Result<String> {
+    let serialized = serde_json::to_string(job).map_err(serde_error)?;
+    self.execute("INSERT INTO jobs (job) VALUES (?1)", [serialized]).await?;
+    Ok(job.id())
+  }
+
+  // Other processes may be adding jobs to the same file, so there is nothing to wait on but the clock.
+  async fn pop(&self) -> Result<Option<TableJob>> {
+    let deadline = Instant::now() + self.wait;
+
+    loop {
+      if let Some(job) = self.take().await? {
+        return serde_json::from_str(&job).map(Some).map_err(serde_error);
+      }
+
+      if Instant::now() >= deadline {
+        return Ok(None);
+      }
+
+      async_std::task::sleep(POP_INTERVAL).await;
+    }
+  }
+
+  async fn store(&self, id: &str, result: &str) -> Result<()> {
+    self
+      .execute(
+        "INSERT INTO job_results (id, result) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET result = excluded.result",
+        [id.to_string(), result.to_string()],
+      )
+      .await?;
+
+    Ok(())
+  }
+
+  async fn fetch(&self, id: &str) -> Result<Option<String>> {
+    self
+      .state("SELECT result FROM job_results WHERE id = ?1", [id.to_string()])
+      .await
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::SqliteStore;
+  use crate::queue::JobQueue;
+  use crate::sessions::SessionStore;
+  use crate::storage::{ArchiveRepository, HandRanking, PlayerRepository, TableIndexRepository, TableRepository};
+  use bankah::jobs::TableJob;
+  use bankah::state::{HandState, HistoryState, PlayerState, RollArchiveState, TransferState};
+  use std::time::Duration;
+
+  fn player(id: &str) -> PlayerState {
+    PlayerState {
+      id: id.to_string(),
+      oid: format!("oid-{}", id),
+      emails: Vec::new(),
+      nickname: format!("nick-{}", id),
+      balance: 1000,
+      tables: Vec::new(),
+      history: Vec::new(),
+    }
+  }
+
+  fn hand(id: &str, table: &str, rolls: u32, net: i64) -> HandState {
+    HandState {
+      id: id.to_string(),
+      table: table.to_string(),
+      shooter: "a".to_string(),
+      nickname: "nick-a".to_string(),
+      rolls,
+      points_set: 0,
+      points_made: 0,
+      seven_out: true,
+      won: 0,
+      lost: 0,
+      net,
+      started_at: chrono::Utc::now(),
+      finished_at: None,
+    }
+  }
+
+  #[test]
+  fn test_migrations_once() {
+    let path = std::env::temp_dir().join(format!("stickbot-{}.db", uuid::Uuid::new_v4()));
+
+    let first = SqliteStore::open(&path).and_then(|_| SqliteStore::open(&path));
+    let _ = std::fs::remove_file(&path);
+
+    assert!(first.is_ok());
+  }
+
+  #[test]
+  fn test_players_and_seating() {
+    let store = SqliteStore::in_memory(Duration::from_millis(10)).unwrap();
+
+    let (created, again, seated, missing) = async_std::task::block_on(async {
+      let created = store.find_or_create(&player("a")).await.unwrap();
+      let again = store
+        .find_or_create(&PlayerState {
+          balance: 0,
+          ..player("a")
+        })
+        .await
+        .unwrap();
+      store.set_seating("a", 400, &["table".to_string()]).await.unwrap();
+      let seated = PlayerRepository::find(&store, "a").await.unwrap();
+      let missing = store.set_seating("b", 400, &[]).await.unwrap();
+      (created, again, seated, missing)
+    });
+
+    assert_eq!(created.balance, 1000);
+    assert_eq!(again.balance, 1000);
+    assert_eq!(
+      seated.map(|p| (p.balance, p.tables)),
+      Some((400, vec!["table".to_string()]))
+    );
+    assert!(!missing);
+  }
+
+  #[test]
+  fn test_record_history() {
+    let store = SqliteStore::in_memory(Duration::from_millis(10)).unwrap();
+    let entry = HistoryState::Transfer(TransferState {
+      id: "transfer".to_string(),
+      table: "t".to_string(),
+      from: "a".to_string(),
+      to: None,
+      amount: 25,
+      sent_at: chrono::Utc::now(),
+    });
+
+    let recorded = async_std::task::block_on(async {
+      store.find_or_create(&player("a")).await.unwrap();
+      store.record("a", &entry).await.unwrap();
+      store.record("a", &entry).await.unwrap();
+      PlayerRepository::find(&store, "a").await.unwrap()
+    });
+
+    assert_eq!(recorded.map(|p| (p.balance, p.history.len())), Some((1000, 1)));
+  }
+
+  #[test]
+  fn test_seated_and_reindex() {
+    let store = SqliteStore::in_memory(Duration::from_millis(10)).unwrap();
+    let seat = bankah::state::SeatState {
+      nickname: "nick-a".to_string(),
+      ..bankah::state::SeatState::default()
+    };
+    let table = bankah::state::TableState {
+      name: "craps".to_string(),
+      seats: vec![("a".to_string(), seat)].into_iter().collect(),
+      ..bankah::state::TableState::default()
+    };
+
+    let (seated, empty, index) = async_std::task::block_on(async {
+      store.save(&table).await.unwrap();
+      store.save(&table).await.unwrap();
+      let seated = store.seated("a").await.unwrap();
+      let empty = store.seated("b").await.unwrap();
+      store.reindex().await.unwrap();
+      (seated, empty, store.list(10).await.unwrap())
+    });
+
+    assert_eq!(seated.len(), 1);
+    assert!(empty.is_empty());
+    assert_eq!(index.len(), 1);
+    assert_eq!(index[0].name, "craps");
+    assert_eq!(index[0].population, vec![("a".to_string(), "nick-a".to_string())]);
+  }
+
+  #[test]
+  fn test_queue_and_results() {
+    let store = SqliteStore::in_memory(Duration::from_millis(10)).unwrap();
+    let (first, second) = (TableJob::reindex(), TableJob::stand("t".into(), "p".into()));
+
+    let (popped, result) = async_std::task::block_on(async {
+      store.push(&first).await.unwrap();
+      store.push(&second).await.unwrap();
+      let popped = (
+        store.pop().await.unwrap(),
+        store.pop().await.unwrap(),
+        store.pop().await.unwrap(),
+      );
+      store.store("job", "{}").await.unwrap();
+      (popped, store.fetch("job").await.unwrap())
+    });
+
+    assert_eq!(popped.0.map(|job| job.id()), Some(first.id()));
+    assert_eq!(popped.1.map(|job| job.id()), Some(second.id()));
+    assert!(popped.2.is_none());
+    assert_eq!(result, Some("{}".to_string()));
+  }
+
+  #[test]
+  fn test_archive() {
+    let store = SqliteStore::in_memory(Duration::from_millis(10)).unwrap();
+    let hands = vec![hand("1", "t", 3, 50), hand("2", "t", 9, -20), hand("3", "u", 5, 10)];
+    let roll = |dice| RollArchiveState {
+      table: "t".to_string(),
+      dice,
+      shooter: None,
+      button: None,
+      rolled_at: chrono::Utc::now(),
+    };
+
+    let (longest, best, rolls) = async_std::task::block_on(async {
+      store.record_hands(&hands).await.unwrap();
+      store.record_hands(&hands[..1]).await.unwrap();
+      store.archive_roll(&roll((6, 1))).await.unwrap();
+      store.archive_roll(&roll((2, 2))).await.unwrap();
+      store.archive_roll(&roll((3, 4))).await.unwrap();
+      (
+        store.hands(Some("t"), HandRanking::Longest, 10).await.unwrap(),
+        store.hands(None, HandRanking::Best, 2).await.unwrap(),
+        store.rolls("t", 2).await.unwrap(),
+      )
+    });
+
+    let ids = |hands: Vec<HandState>| hands.into_iter().map(|hand| hand.id).collect::<Vec<String>>();
+    assert_eq!(ids(longest), vec!["2", "1"]);
+    assert_eq!(ids(best), vec!["1", "3"]);
+    assert_eq!(
+      rolls.iter().map(|roll| roll.dice).collect::<Vec<(u8, u8)>>(),
+      vec![(2, 2), (3, 4)]
+    );
+  }
+
+  #[test]
+  fn test_sessions() {
+    let store = SqliteStore::in_memory(Duration::from_millis(10)).unwrap();
+
+    let (found, removed) = async_std::task::block_on(async {
+      store.create("token", "a").await.unwrap();
+      let found = SessionStore::find(&store, "token").await.unwrap();
+      store.remove("token").await.unwrap();
+      (found, SessionStore::find(&store, "token").await.unwrap())
+    });
+
+    assert_eq!(found, Some("a".to_string()));
+    assert!(removed.is_none());
+  }
+}
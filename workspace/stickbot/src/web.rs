@@ -2,6 +2,7 @@ use http_types;
 use tide;
 
 pub use http_types::{Cookie, Url};
+pub use tide::sse::Sender;
 pub use tide::{Body, Error, Redirect, Response, Result};
 pub type Request = tide::Request<crate::Services>;
 
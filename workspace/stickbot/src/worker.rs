@@ -0,0 +1,95 @@
+use std::io::{Error, ErrorKind, Result};
+use std::time::Duration;
+
+use bankah::events::TableEvent;
+use bankah::jobs::{TableAdminJob, TableJob};
+
+pub async fn work(services: &crate::Services) -> Result<()> {
+  let job = match services.pop().await? {
+    Some(job) => job,
+    None => {
+      log::info!("no available jobs, moving on.");
+      return Ok(());
+    }
+  };
+
+  log::debug!("deserialized job from queue - {:?}", job);
+
+  let id = job.id().to_string();
+  let result = match &job {
+    TableJob::Admin(inner) => match &inner.job {
+      TableAdminJob::ReindexPopulations => crate::processors::admin::reindex(&services, &inner.job).await,
+      TableAdminJob::CleanupPlayerData(id) => crate::processors::admin::cleanup(&services, &id).await,
+    },
+    TableJob::Bet(inner) => crate::processors::bet(&services, &inner.job).await,
+    TableJob::Roll(inner) => crate::processors::roll(&services, &inner.job).await,
+    TableJob::Sit(inner) => crate::processors::sit(&services, &inner.job).await,
+    TableJob::Create(inner) => crate::processors::create(&services, &inner.job).await,
+    TableJob::Stand(inner) => crate::processors::stand(&services, &inner.job).await,
+    TableJob::Rebuy(inner) => crate::processors::rebuy(&services, &inner.job).await,
+    TableJob::CashOut(inner) => crate::processors::cash_out(&services, &inner.job).await,
+    TableJob::Transfer(inner) => crate::processors::transfer(&services, &inner.job).await,
+  };
+
+  let completed = result.as_ref().ok().cloned();
+  let serialized = result
+    .map(|inner| bankah::jobs::JobResult::wrap(job.id(), inner))
+    .and_then(|out| {
+      serde_json::to_string(&out).map_err(|error| {
+        log::warn!("unable to serialze job output - {}", error);
+        bankah::jobs::JobError::Terminal(format!("unable to serialze job output - {}", error))
+      })
+    });
+
+  // Processors will return a Result<E, T>, where `E` can either represent a "fatal" error that is non-retryable or
+  // an error that is retryable. If the job is retryable, re-enqueue.
+  let output = match serialized {
+    Ok(output) => output,
+    Err(bankah::jobs::JobError::Retryable) => {
+      let retry = job.retry().ok_or(Error::new(ErrorKind::Other, "no-retryable"))?;
+      services.queue(&retry).await.map_err(|error| {
+        log::warn!("unable to persist retry into queue - {}", error);
+        error
+      })?;
+      log::debug!("job '{}' scheduled for retry", retry.id());
+      return Ok(());
+    }
+    Err(bankah::jobs::JobError::Terminal(error)) => return Err(Error::new(ErrorKind::Other, error)),
+  };
+
+  log::debug!("job '{}' processed - {}", id, output);
+
+  services.store_result(&id, &output).await?;
+
+  // Anyone watching the table can stop polling for this job once they see it complete.
+  if let (Some(table), Some(output)) = (job.table(), completed) {
+    let event = TableEvent::JobCompleted { job: id, output };
+
+    if let Err(error) = services.publish(table, &event).await {
+      log::warn!("unable to publish job completion - {}", error);
+    }
+  }
+
+  Ok(())
+}
+
+// Processes jobs from the queue forever, one at a time.
+pub async fn run(services: crate::Services) -> Result<()> {
+  let delay = std::env::var(crate::constants::BOXBOT_DELAY_ENV)
+    .ok()
+    .and_then(|content| content.parse::<u64>().ok())
+    .unwrap_or(0);
+
+  log::debug!("entering processing loop (w/ delay {:?})", delay);
+
+  loop {
+    if let Err(error) = work(&services).await {
+      log::warn!("unable to process - {}", error);
+    }
+
+    if delay > 0 {
+      log::debug!("sleeping worker for {} millis", delay);
+      async_std::task::sleep(Duration::from_millis(delay)).await;
+    }
+  }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use twowaiyo::{Bet, Player, Table};
+
+// The dice cycle through a fixed sequence with no sevens so that runs are repeatable between benchmark invocations.
+const FACES: [u8; 12] = [2, 2, 3, 3, 4, 4, 2, 3, 5, 5, 4, 5];
+
+#[derive(Clone, Default)]
+struct CycleDice(usize);
+
+impl Iterator for CycleDice {
+  type Item = u8;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let face = FACES[self.0 % FACES.len()];
+    self.0 += 1;
+    Some(face)
+  }
+}
+
+fn populated(seats: usize) -> Table<CycleDice> {
+  let mut players = (0..seats)
+    .map(|_| Player::with_balance(1_000_000))
+    .collect::<Vec<Player>>();
+  let table = players
+    .iter_mut()
+    .fold(Table::with_dice(CycleDice::default()), |table, player| {
+      table.sit(player)
+    });
+
+  let table = players.iter().fold(table, |table, player| {
+    table
+      .bet(player, &Bet::start_pass(10))
+      .expect("unable to place pass bet")
+  });
+
+  // The first roll establishes the button at four, after which the place bets can go up.
+  let table = table.roll().table;
+
+  players.iter().fold(table, |table, player| {
+    IntoIterator::into_iter([5, 6, 8, 9, 10]).fold(table, |table, target| {
+      table.bet(player, &Bet::Place(60, target)).expect("unable to place bet")
+    })
+  })
+}
+
+fn rolls(criterion: &mut Criterion) {
+  let mut group = criterion.benchmark_group("roll");
+  group.throughput(Throughput::Elements(1));
+
+  for seats in [1usize, 10, 100].iter() {
+    group.bench_with_input(BenchmarkId::from_parameter(seats), seats, |bencher, seats| {
+      // Every iteration starts from a freshly populated table so winning bets coming down don't thin out the work.
+      let table = populated(*seats);
+      let mut results = HashMap::with_capacity(*seats);
+      bencher.iter_batched(
+        || table.clone(),
+        |mut table| {
+          table.roll_into(&mut results);
+          table
+        },
+        BatchSize::SmallInput,
+      );
+    });
+  }
+
+  group.finish();
+}
+
+criterion_group!(benches, rolls);
+criterion_main!(benches);
@@ -1,4 +1,9 @@
+use std::convert::TryFrom;
+
+use super::errors::ConversionError;
 use super::roll::{Hardway, Roll};
+use super::rules::Rules;
+use super::variant::Variant;
 
 use bankah::state::{BetState, RaceType, TargetKind};
 
@@ -39,11 +44,33 @@ impl<T> BetResult<T> {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedRaceBet"))]
 pub struct RaceBet {
   amount: u32,
   target: Option<u8>,
 }
 
+// What a race bet deserializes as before its target is checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedRaceBet {
+  amount: u32,
+  target: Option<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedRaceBet> for RaceBet {
+  type Error = ConversionError;
+
+  fn try_from(race: UncheckedRaceBet) -> Result<RaceBet, Self::Error> {
+    Ok(RaceBet {
+      amount: race.amount,
+      target: race.target.map(point).transpose()?,
+    })
+  }
+}
+
 impl RaceBet {
   pub fn pull(self) -> (u32, Option<Self>) {
     match self.target {
@@ -59,6 +86,10 @@ impl RaceBet {
   }
 
   pub fn result(&self, roll: &Roll) -> BetResult<Self> {
+    self.settle(roll, Variant::Standard)
+  }
+
+  pub fn settle(&self, roll: &Roll, variant: Variant) -> BetResult<Self> {
     let total = roll.total();
 
     match (self.target, total) {
@@ -69,17 +100,20 @@ impl RaceBet {
         target: Some(goal),
       }),
 
-      (None, 7) | (None, 11) => BetResult::Win(self.amount + self.amount),
-      (None, 2) | (None, 3) | (None, 12) => BetResult::Loss(self.amount),
-      (None, value) => BetResult::Noop(RaceBet {
+      (None, 7) => BetResult::Win(self.amount + self.amount),
+      (None, value) if variant.is_point(value) => BetResult::Noop(RaceBet {
         amount: self.amount,
         target: Some(value),
       }),
+      (None, 11) => BetResult::Win(self.amount + self.amount),
+      (None, _) => BetResult::Loss(self.amount),
     }
   }
 }
 
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedBet"))]
 pub enum Bet {
   Pass(RaceBet),
   PassOdds(u32, u8),
@@ -94,29 +128,75 @@ pub enum Bet {
   Hardway(u32, Hardway),
 }
 
-impl From<&BetState> for Bet {
-  fn from(state: &BetState) -> Bet {
-    match state {
+// Without knowing which table a bet is headed for, the best that can be checked is that its target could be a point
+// under some variant of the game; tables check targets against their own variant when they are converted.
+fn point(target: u8) -> Result<u8, ConversionError> {
+  match Variant::Crapless.is_point(target) {
+    true => Ok(target),
+    false => Err(ConversionError::InvalidTarget(target)),
+  }
+}
+
+// What a bet deserializes as before its target is checked; race bets check their own.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+enum UncheckedBet {
+  Pass(RaceBet),
+  PassOdds(u32, u8),
+  Come(RaceBet),
+  ComeOdds(u32, u8),
+  Place(u32, u8),
+  Field(u32),
+  Hardway(u32, Hardway),
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedBet> for Bet {
+  type Error = ConversionError;
+
+  fn try_from(bet: UncheckedBet) -> Result<Bet, Self::Error> {
+    let bet = match bet {
+      UncheckedBet::Pass(race) => Bet::Pass(race),
+      UncheckedBet::PassOdds(amount, target) => Bet::PassOdds(amount, point(target)?),
+      UncheckedBet::Come(race) => Bet::Come(race),
+      UncheckedBet::ComeOdds(amount, target) => Bet::ComeOdds(amount, point(target)?),
+      UncheckedBet::Place(amount, target) => Bet::Place(amount, point(target)?),
+      UncheckedBet::Field(amount) => Bet::Field(amount),
+      UncheckedBet::Hardway(amount, way) => Bet::Hardway(amount, way),
+    };
+
+    Ok(bet)
+  }
+}
+
+impl TryFrom<&BetState> for Bet {
+  type Error = ConversionError;
+
+  fn try_from(state: &BetState) -> Result<Bet, Self::Error> {
+    let bet = match state {
       BetState::Target(kind, amount, target) => match kind {
-        TargetKind::ComeOdds => Bet::ComeOdds(*amount, *target),
-        TargetKind::PassOdds => Bet::PassOdds(*amount, *target),
-        TargetKind::Place => Bet::Place(*amount, *target),
-        TargetKind::Hardway => Bet::Hardway(*amount, target.into()),
+        TargetKind::ComeOdds => Bet::ComeOdds(*amount, point(*target)?),
+        TargetKind::PassOdds => Bet::PassOdds(*amount, point(*target)?),
+        TargetKind::Place => Bet::Place(*amount, point(*target)?),
+        TargetKind::Hardway => Bet::Hardway(*amount, Hardway::try_from(*target)?),
       },
 
-      BetState::Race(kind, amount, target) => match kind {
-        RaceType::Pass => Bet::Pass(RaceBet {
-          amount: *amount,
-          target: target.clone(),
-        }),
-        RaceType::Come => Bet::Come(RaceBet {
+      BetState::Race(kind, amount, target) => {
+        let race = RaceBet {
           amount: *amount,
-          target: target.clone(),
-        }),
-      },
+          target: target.map(point).transpose()?,
+        };
+
+        match kind {
+          RaceType::Pass => Bet::Pass(race),
+          RaceType::Come => Bet::Come(race),
+        }
+      }
 
       BetState::Field(amount) => Bet::Field(*amount),
-    }
+    };
+
+    Ok(bet)
   }
 }
 
@@ -163,6 +243,14 @@ fn odds_result(total: u8, target: u8, wager: u32, odds: Odds) -> BetResult<(u32,
   }
 
   let added = match target {
+    2 | 12 => match odds {
+      Odds::Place => Some((wager / 2) * 9),
+      Odds::Pass => Some(wager * 5),
+    },
+    3 | 11 => match odds {
+      Odds::Place => Some((wager / 4) * 7),
+      Odds::Pass => Some(wager * 2),
+    },
     4 | 10 => match odds {
       Odds::Place => Some((wager / 5) * 4),
       Odds::Pass => Some(wager),
@@ -192,6 +280,20 @@ impl Bet {
     Bet::Pass(RaceBet { amount, target: None })
   }
 
+  pub fn put_pass(amount: u32, target: u8) -> Self {
+    Bet::Pass(RaceBet {
+      amount,
+      target: Some(target),
+    })
+  }
+
+  pub fn put_come(amount: u32, target: u8) -> Self {
+    Bet::Come(RaceBet {
+      amount,
+      target: Some(target),
+    })
+  }
+
   pub fn come_target(&self) -> Option<u8> {
     match self {
       Bet::Come(race) => race.target,
@@ -206,6 +308,15 @@ impl Bet {
     }
   }
 
+  // The point a bet is riding on, if any; hardways and the field are never on a point.
+  pub(crate) fn point(&self) -> Option<u8> {
+    match self {
+      Bet::Pass(race) | Bet::Come(race) => race.target,
+      Bet::PassOdds(_, target) | Bet::ComeOdds(_, target) | Bet::Place(_, target) => Some(*target),
+      Bet::Field(_) | Bet::Hardway(_, _) => None,
+    }
+  }
+
   pub fn pull(self) -> (u32, Option<Self>) {
     let weight = self.weight();
 
@@ -227,11 +338,15 @@ impl Bet {
   }
 
   pub fn result(&self, roll: &Roll) -> BetResult<Self> {
+    self.settle(roll, &Rules::default())
+  }
+
+  pub fn settle(&self, roll: &Roll, rules: &Rules) -> BetResult<Self> {
     let total = roll.total();
 
     match self {
-      Bet::Pass(race) => race.result(roll).map(Bet::Pass),
-      Bet::Come(race) => race.result(roll).map(Bet::Come),
+      Bet::Pass(race) => race.settle(roll, rules.variant).map(Bet::Pass),
+      Bet::Come(race) => race.settle(roll, rules.variant).map(Bet::Come),
       Bet::PassOdds(amount, target) => {
         odds_result(total, *target, *amount, Odds::Pass).map(|(amount, target)| Bet::PassOdds(amount, target))
       }
@@ -241,10 +356,9 @@ impl Bet {
       Bet::Place(amount, target) => {
         odds_result(total, *target, *amount, Odds::Place).map(|(amount, target)| Bet::Place(amount, target))
       }
-      Bet::Field(amount) => match total {
-        2 | 12 => BetResult::Win((amount * 2) + amount),
-        3 | 4 | 9 | 10 | 11 => BetResult::Win(amount + amount),
-        _ => BetResult::Loss(amount + 0),
+      Bet::Field(amount) => match rules.field.multiplier(total) {
+        Some(multiplier) => BetResult::Win((amount * multiplier) + amount),
+        None => BetResult::Loss(amount + 0),
       },
       Bet::Hardway(amount, target) => {
         if roll.easyway().map(|e| e == *target).unwrap_or(false) || total == 7 {
@@ -283,7 +397,127 @@ impl Bet {
 #[cfg(test)]
 mod test {
   use super::{Bet, BetResult, RaceBet};
+  use crate::errors::ConversionError;
+  use crate::field::FieldPaytable;
   use crate::roll::{Hardway, Roll};
+  use crate::rules::Rules;
+  use crate::variant::Variant;
+  use bankah::state::{BetState, RaceType, TargetKind};
+  use std::convert::TryFrom;
+
+  #[test]
+  fn test_try_from_state() {
+    let state = BetState::Target(TargetKind::Hardway, 10, 6);
+    assert_eq!(Bet::try_from(&state), Ok(Bet::Hardway(10, Hardway::Six)));
+    let state = BetState::Race(RaceType::Come, 10, Some(11));
+    assert_eq!(Bet::try_from(&state), Ok(Bet::put_come(10, 11)));
+  }
+
+  #[test]
+  fn test_try_from_invalid_state() {
+    let state = BetState::Target(TargetKind::Hardway, 10, 5);
+    assert_eq!(Bet::try_from(&state), Err(ConversionError::InvalidHardway(5)));
+    let state = BetState::Target(TargetKind::Place, 10, 7);
+    assert_eq!(Bet::try_from(&state), Err(ConversionError::InvalidTarget(7)));
+    let state = BetState::Target(TargetKind::PassOdds, 10, 0);
+    assert_eq!(Bet::try_from(&state), Err(ConversionError::InvalidTarget(0)));
+    let state = BetState::Race(RaceType::Pass, 10, Some(13));
+    assert_eq!(Bet::try_from(&state), Err(ConversionError::InvalidTarget(13)));
+  }
+
+  #[test]
+  fn test_crapless_race_off() {
+    let bet = RaceBet {
+      amount: 10,
+      target: None,
+    };
+
+    for (left, right) in [(1u8, 1u8), (1, 2), (5, 6), (6, 6)] {
+      let roll = vec![left, right].into_iter().collect::<Roll>();
+      assert_eq!(
+        bet.settle(&roll, Variant::Crapless),
+        BetResult::Noop(RaceBet {
+          amount: 10,
+          target: Some(left + right)
+        })
+      );
+    }
+
+    let roll = vec![3u8, 4u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, Variant::Crapless), BetResult::Win(20));
+  }
+
+  fn crapless() -> Rules {
+    Rules {
+      variant: Variant::Crapless,
+      ..Rules::default()
+    }
+  }
+
+  #[test]
+  fn test_crapless_place_win_two() {
+    let bet = Bet::Place(100, 2);
+    let roll = vec![1u8, 1u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &crapless()), BetResult::Win(650));
+  }
+
+  #[test]
+  fn test_crapless_place_win_eleven() {
+    let bet = Bet::Place(100, 11);
+    let roll = vec![5u8, 6u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &crapless()), BetResult::Win(375));
+  }
+
+  #[test]
+  fn test_crapless_pass_odds_twelve() {
+    let bet = Bet::PassOdds(10, 12);
+    let roll = vec![6u8, 6u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &crapless()), BetResult::Win(70));
+  }
+
+  #[test]
+  fn test_crapless_pass_odds_three() {
+    let bet = Bet::PassOdds(10, 3);
+    let roll = vec![1u8, 2u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &crapless()), BetResult::Win(40));
+  }
+
+  #[test]
+  fn test_field_standard() {
+    let bet = Bet::Field(10);
+    let roll = vec![6u8, 6u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.result(&roll), BetResult::Win(30));
+    let roll = vec![1u8, 3u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.result(&roll), BetResult::Win(20));
+    let roll = vec![2u8, 3u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.result(&roll), BetResult::Loss(10));
+  }
+
+  #[test]
+  fn test_field_triple_twelve() {
+    let rules = Rules {
+      field: FieldPaytable::triple_twelve(),
+      ..Rules::default()
+    };
+    let bet = Bet::Field(10);
+    let roll = vec![6u8, 6u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &rules), BetResult::Win(40));
+    let roll = vec![1u8, 1u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &rules), BetResult::Win(30));
+  }
+
+  #[test]
+  fn test_field_five() {
+    let rules = Rules {
+      field: FieldPaytable::five_for_four(),
+      ..Rules::default()
+    };
+    let bet = Bet::Field(10);
+    let roll = vec![2u8, 3u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &rules), BetResult::Win(20));
+    let roll = vec![1u8, 3u8].into_iter().collect::<Roll>();
+    assert_eq!(bet.settle(&roll, &rules), BetResult::Loss(10));
+  }
 
   #[test]
   fn test_hit_hardway_four() {
@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+use twowaiyo::RandomRoller;
+
+// Either real random dice, or a fixed sequence of faces provided up front. Fixed dice simply run out when the
+// sequence has been used up.
+#[derive(Clone)]
+pub enum Dice {
+  Random(RandomRoller),
+  Fixed(VecDeque<u8>),
+}
+
+impl Dice {
+  pub fn parse(input: &str) -> Result<Self, String> {
+    let faces = input
+      .split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|face| !face.is_empty())
+      .map(|face| match face.parse::<u8>() {
+        Ok(value) if (1..=6).contains(&value) => Ok(value),
+        _ => Err(format!("invalid die face '{}'", face)),
+      })
+      .collect::<Result<VecDeque<u8>, String>>()?;
+
+    Ok(Dice::Fixed(faces))
+  }
+}
+
+// Anything a session can throw; fixed faces can run out, so a throw is only made once the source says it is ready.
+pub trait Ready {
+  // Whether or not there are enough faces left for another throw.
+  fn ready(&self) -> bool;
+}
+
+impl Ready for Dice {
+  fn ready(&self) -> bool {
+    match self {
+      Dice::Random(_) => true,
+      Dice::Fixed(faces) => faces.len() >= 2,
+    }
+  }
+}
+
+impl Default for Dice {
+  fn default() -> Self {
+    Dice::Random(RandomRoller::default())
+  }
+}
+
+impl Iterator for Dice {
+  type Item = u8;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self {
+      Dice::Random(roller) => roller.next(),
+      Dice::Fixed(faces) => faces.pop_front(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Dice, Ready};
+
+  #[test]
+  fn test_parse() {
+    let mut dice = Dice::parse("2,5 3, 3").expect("should parse");
+    assert!(dice.ready());
+    assert_eq!(dice.by_ref().collect::<Vec<u8>>(), vec![2, 5, 3, 3]);
+    assert!(!dice.ready());
+  }
+
+  #[test]
+  fn test_parse_invalid() {
+    assert!(Dice::parse("2,7").is_err());
+    assert!(Dice::parse("two").is_err());
+  }
+}
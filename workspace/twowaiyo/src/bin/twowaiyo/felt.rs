@@ -0,0 +1,177 @@
+use twowaiyo::io::{Action, TakeDown};
+use twowaiyo::{Bet, Hardway, Variant};
+
+pub const CHIPS: [u32; 5] = [1, 5, 25, 100, 500];
+
+// A spot is any area of the felt a bet can be made on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spot {
+  Pass,
+  PassOdds,
+  Come,
+  Field,
+  Box(u8),
+  Hard(Hardway),
+}
+
+// The selectable spots, row by row from the top of the layout. The proposition bets sit at the end of each row.
+pub fn rows(variant: Variant) -> Vec<Vec<Spot>> {
+  let boxes = (2..=12).filter(|total| variant.is_point(*total)).map(Spot::Box);
+
+  vec![
+    boxes.chain(Some(Spot::Hard(Hardway::Four))).collect(),
+    vec![Spot::Come, Spot::Hard(Hardway::Six)],
+    vec![Spot::Field, Spot::Hard(Hardway::Eight)],
+    vec![Spot::Pass, Spot::PassOdds, Spot::Hard(Hardway::Ten)],
+  ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+  Up,
+  Down,
+  Left,
+  Right,
+}
+
+// The cursor is a (row, column) pair into `rows`. Moving between rows keeps the column where possible, which means
+// moving down from the proposition column lands on the next hardway.
+pub fn step(rows: &[Vec<Spot>], cursor: (usize, usize), direction: Direction) -> (usize, usize) {
+  let (row, column) = cursor;
+  let last = |row: usize| rows[row].len() - 1;
+  let clamp = |row: usize, column: usize| (row, column.min(last(row)));
+
+  match direction {
+    Direction::Up if row > 0 => match column == last(row) {
+      true => (row - 1, last(row - 1)),
+      false => clamp(row - 1, column.min(last(row - 1).saturating_sub(1))),
+    },
+    Direction::Down if row + 1 < rows.len() => match column == last(row) {
+      true => (row + 1, last(row + 1)),
+      false => clamp(row + 1, column.min(last(row + 1).saturating_sub(1))),
+    },
+    Direction::Left if column > 0 => (row, column - 1),
+    Direction::Right if column < last(row) => (row, column + 1),
+    _ => cursor,
+  }
+}
+
+pub fn bet(spot: Spot, amount: u32) -> Action {
+  let bet = match spot {
+    Spot::Pass => Bet::start_pass(amount),
+    Spot::PassOdds => Bet::PassOdds(amount, 0),
+    Spot::Come => Bet::start_come(amount),
+    Spot::Field => Bet::Field(amount),
+    Spot::Box(target) => Bet::Place(amount, target),
+    Spot::Hard(way) => Bet::Hardway(amount, way),
+  };
+
+  Action::Bet(bet)
+}
+
+// Odds only make sense behind the line, or behind a come bet that has travelled to a box.
+pub fn odds(spot: Spot, amount: u32) -> Option<Action> {
+  match spot {
+    Spot::Pass | Spot::PassOdds => Some(Action::Bet(Bet::PassOdds(amount, 0))),
+    Spot::Box(target) => Some(Action::Bet(Bet::ComeOdds(amount, target))),
+    _ => None,
+  }
+}
+
+pub fn take_down(spot: Spot) -> Action {
+  let removal = match spot {
+    Spot::Pass => TakeDown::Pass,
+    Spot::PassOdds => TakeDown::PassOdds,
+    Spot::Come => TakeDown::Come,
+    Spot::Field => TakeDown::Field,
+    Spot::Box(target) => TakeDown::Place(target),
+    Spot::Hard(way) => TakeDown::Hardway(way),
+  };
+
+  Action::TakeDown(removal)
+}
+
+pub fn label(spot: Spot) -> String {
+  match spot {
+    Spot::Pass => "PASS LINE".into(),
+    Spot::PassOdds => "ODDS".into(),
+    Spot::Come => "COME".into(),
+    Spot::Field => "FIELD".into(),
+    Spot::Box(6) => "SIX".into(),
+    Spot::Box(9) => "NINE".into(),
+    Spot::Box(target) => format!("{}", target),
+    Spot::Hard(way) => format!("HARD {}", u8::from(&way)),
+  }
+}
+
+// The chips a player has on a spot, each marked with what kind of bet they belong to: `P` for place bets, `C` for
+// come bets that have travelled to a box, `O` for odds and `L` for a put line bet.
+pub fn chips(bets: &[Bet], spot: Spot) -> Vec<(char, u32)> {
+  bets
+    .iter()
+    .filter_map(|bet| match (spot, bet) {
+      (Spot::Pass, Bet::Pass(_)) => Some((' ', bet.weight())),
+      (Spot::PassOdds, Bet::PassOdds(amount, _)) => Some((' ', *amount)),
+      (Spot::Come, Bet::Come(_)) if bet.come_target().is_none() => Some((' ', bet.weight())),
+      (Spot::Field, Bet::Field(amount)) => Some((' ', *amount)),
+      (Spot::Hard(way), Bet::Hardway(amount, on)) if way == *on => Some((' ', *amount)),
+      (Spot::Box(target), Bet::Place(amount, on)) if target == *on => Some(('P', *amount)),
+      (Spot::Box(target), Bet::Come(_)) if bet.come_target() == Some(target) => Some(('C', bet.weight())),
+      (Spot::Box(target), Bet::ComeOdds(amount, on)) if target == *on => Some(('O', *amount)),
+      _ => None,
+    })
+    .collect()
+}
+
+pub fn stack(chips: &[(char, u32)]) -> String {
+  chips
+    .iter()
+    .map(|(kind, amount)| match kind {
+      ' ' => format!("${}", amount),
+      kind => format!("{}${}", kind, amount),
+    })
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{chips, rows, stack, step, Direction, Spot};
+  use twowaiyo::{Bet, Hardway, Variant};
+
+  #[test]
+  fn test_rows_follow_variant() {
+    assert_eq!(rows(Variant::Standard)[0].len(), 7);
+    assert_eq!(rows(Variant::Crapless)[0].len(), 11);
+    assert_eq!(rows(Variant::Crapless)[0][0], Spot::Box(2));
+  }
+
+  #[test]
+  fn test_step() {
+    let rows = rows(Variant::Standard);
+    assert_eq!(step(&rows, (0, 0), Direction::Up), (0, 0));
+    assert_eq!(step(&rows, (0, 3), Direction::Down), (1, 0));
+    assert_eq!(step(&rows, (0, 6), Direction::Down), (1, 1));
+    assert_eq!(step(&rows, (3, 2), Direction::Up), (2, 1));
+    assert_eq!(step(&rows, (2, 0), Direction::Down), (3, 0));
+    assert_eq!(step(&rows, (3, 1), Direction::Up), (2, 0));
+    assert_eq!(step(&rows, (3, 0), Direction::Right), (3, 1));
+    assert_eq!(step(&rows, (3, 2), Direction::Right), (3, 2));
+  }
+
+  #[test]
+  fn test_chips() {
+    let bets = vec![
+      Bet::Place(12, 6),
+      Bet::put_come(10, 6),
+      Bet::ComeOdds(20, 6),
+      Bet::Place(10, 8),
+      Bet::Hardway(5, Hardway::Six),
+    ];
+    let on_six = chips(&bets, Spot::Box(6));
+    assert_eq!(on_six, vec![('P', 12), ('C', 10), ('O', 20)]);
+    assert_eq!(stack(&on_six), "P$12 C$10 O$20");
+    assert_eq!(chips(&bets, Spot::Hard(Hardway::Six)), vec![(' ', 5)]);
+    assert_eq!(chips(&bets, Spot::Come), vec![]);
+  }
+}
@@ -0,0 +1,160 @@
+use std::io::{stdin, stdout, BufRead, BufReader, Result, Write};
+
+use twowaiyo::io::Action;
+
+use dice::Dice;
+
+mod dice;
+mod felt;
+mod render;
+mod repl;
+mod save;
+mod script;
+mod serve;
+mod tui;
+
+const USAGE: &'static str = "\
+usage: twowaiyo [--dice <faces>] [--script <path> | --tui]
+       twowaiyo serve [--bind <address>] [--dice <faces>]
+
+  --dice <faces>     roll a fixed sequence of die faces, e.g. '2,5,3,3'
+  --script <path>    run the commands in a file ('-' for stdin), printing a json lines transcript
+  --tui              play on a full screen craps layout instead of typing commands
+  --bind <address>   where 'serve' listens for players connecting with a line based client like 'nc', defaults
+                     to 0.0.0.0:7777";
+
+#[derive(Default)]
+struct Options {
+  dice: Dice,
+  script: Option<String>,
+  tui: bool,
+  serve: Option<String>,
+}
+
+fn options() -> std::result::Result<Options, String> {
+  let mut options = Options::default();
+  let mut args = std::env::args().skip(1).peekable();
+
+  if args.peek().map(|arg| arg.as_str()) == Some("serve") {
+    args.next();
+    options.serve = Some(serve::DEFAULT_ADDRESS.to_string());
+  }
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--dice" => {
+        let faces = args.next().ok_or_else(|| "missing dice for '--dice'".to_string())?;
+        options.dice = Dice::parse(&faces)?;
+      }
+      "--script" => {
+        let path = args.next().ok_or_else(|| "missing path for '--script'".to_string())?;
+        options.script = Some(path);
+      }
+      "--tui" => options.tui = true,
+      "--bind" if options.serve.is_some() => {
+        let address = args.next().ok_or_else(|| "missing address for '--bind'".to_string())?;
+        options.serve = Some(address);
+      }
+      "--help" | "-h" => return Err(USAGE.into()),
+      other => return Err(format!("unrecognized argument '{}'\n\n{}", other, USAGE)),
+    }
+  }
+
+  Ok(options)
+}
+
+fn interactive(mut session: repl::Session<Dice>) -> Result<()> {
+  let stdin = stdin();
+  let stdout = stdout();
+  let mut input = stdin.lock();
+  let mut out = stdout.lock();
+
+  writeln!(out, "welcome to the table, type 'help' for a list of commands")?;
+  session.status(&mut out)?;
+
+  loop {
+    write!(out, "> ")?;
+    out.flush()?;
+
+    let mut buffer = String::with_capacity(32);
+
+    if input.read_line(&mut buffer)? == 0 {
+      log::info!("end of input, leaving main game loop");
+      break;
+    }
+
+    let action = match Action::parse(buffer.trim()) {
+      Ok(action) => action,
+      Err(error) => {
+        writeln!(out, "  {}", buffer.trim())?;
+        writeln!(out, "  {:>width$}", "^", width = error.position + 1)?;
+        writeln!(out, "{}", error)?;
+        continue;
+      }
+    };
+
+    if session.apply(action, &mut out)? == repl::Flow::Exit {
+      log::info!("received exit, leaving main game loop");
+      break;
+    }
+  }
+
+  Ok(())
+}
+
+fn main() -> Result<()> {
+  let options = match options() {
+    Ok(options) => options,
+    Err(message) => {
+      eprintln!("{}", message);
+      std::process::exit(2);
+    }
+  };
+
+  // A `.env` file is only used to configure logging; the game is perfectly playable without one. Scripts stay quiet
+  // about it so their transcripts are all that's printed.
+  if let Err(error) = dotenv::dotenv() {
+    if options.script.is_none() {
+      eprintln!("no environment loaded ({}), continuing with defaults", error);
+    }
+  }
+
+  env_logger::init();
+  log::info!("logger initialized, preparing table");
+
+  let table = twowaiyo::Table::with_dice(options.dice);
+
+  if let Some(address) = options.serve {
+    let listener = std::net::TcpListener::bind(&address)?;
+    log::info!("listening on {}", address);
+    eprintln!("serving a table on {}", address);
+    return serve::serve(listener, serve::Lobby::new(table));
+  }
+
+  let session = repl::Session::new(table, "player", twowaiyo::Player::default());
+
+  let path = match (options.script, options.tui) {
+    (Some(_), true) => {
+      eprintln!("'--script' and '--tui' cannot be used together\n\n{}", USAGE);
+      std::process::exit(2);
+    }
+    (None, true) => return tui::run(&mut session.verbose(false)),
+    (Some(path), false) => path,
+    (None, false) => return interactive(session),
+  };
+
+  let mut session = session.verbose(false);
+  let stdout = stdout();
+  let mut out = stdout.lock();
+
+  let completed = match path.as_str() {
+    "-" => script::run(&mut session, stdin().lock(), &mut out)?,
+    path => script::run(&mut session, BufReader::new(std::fs::File::open(path)?), &mut out)?,
+  };
+
+  if !completed {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
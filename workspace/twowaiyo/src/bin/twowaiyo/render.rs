@@ -0,0 +1,220 @@
+use std::io::{Result, Write};
+
+use twowaiyo::{Bet, Player, Roll, Table};
+
+use super::repl::Local;
+
+pub const HELP: &'static str = "\
+commands:
+  roll (or an empty line)          throw the dice
+  bet <kind> [target] <amount>     make a bet; kinds are pass, come, field, place, hardway,
+                                   pass-odds, come-odds, put and put-come
+  take down <kind> [target]        pull a bet back off the table, or 'take down all'
+  balance                          show where your money is
+  bets                             list your bets
+  history                          list the most recent rolls
+  stand                            leave the table, taking down what you can
+  sit                              sit back down at the table
+  undo                             take back the last thing you did since the dice were thrown
+  join <name>                      add a player to the table and make them active
+  switch <name>                    make another player active
+  as <name> <command>              run a single command as another player
+  players                          list everyone at the table
+  save <path>                      write the table and players to a json file
+  load <path>                      pick a saved game back up
+  help                             show this message
+  exit                             leave the game
+
+numbers may be spelled out (six, yo, boxcars) and amounts may start with '$'.";
+
+pub fn money(amount: u32) -> String {
+  let digits = amount.to_string();
+  let mut out = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+  out.push('$');
+
+  for (index, digit) in digits.chars().enumerate() {
+    if index > 0 && (digits.len() - index) % 3 == 0 {
+      out.push(',');
+    }
+    out.push(digit);
+  }
+
+  out
+}
+
+pub fn describe(bet: &Bet) -> String {
+  match bet {
+    Bet::Pass(_) => match bet.pass_target() {
+      Some(target) => format!("pass line on {}", target),
+      None => "pass line".into(),
+    },
+    Bet::Come(_) => match bet.come_target() {
+      Some(target) => format!("come on {}", target),
+      None => "come".into(),
+    },
+    Bet::PassOdds(_, target) => format!("pass odds on {}", target),
+    Bet::ComeOdds(_, target) => format!("come odds on {}", target),
+    Bet::Place(_, target) => format!("place {}", target),
+    Bet::Field(_) => "field".into(),
+    Bet::Hardway(_, way) => format!("hard {}", u8::from(way)),
+  }
+}
+
+pub fn roll(roll: &Roll) -> String {
+  format!("{} + {} = {}", roll.left(), roll.right(), roll.total())
+}
+
+pub fn status<W, R>(out: &mut W, table: &Table<R>, name: &str, player: &Player) -> Result<()>
+where
+  W: Write,
+  R: Clone + Iterator<Item = u8>,
+{
+  let point = table
+    .button()
+    .map(|button| format!("point is {}", button))
+    .unwrap_or_else(|| "coming out".into());
+  let last = table.rolls().first().map(roll).unwrap_or_else(|| "-".into());
+
+  writeln!(out, "+------------------------------------------+")?;
+  writeln!(out, "| {:<18} last roll: {:<11} |", point, last)?;
+  let seat = table
+    .balance(&player.id)
+    .map(money)
+    .unwrap_or_else(|| "not seated".into());
+  writeln!(out, "| {:<18} seat: {:<16} |", name, seat)?;
+  writeln!(out, "+------------------------------------------+")?;
+
+  bets(out, table.bets(&player.id).unwrap_or(&[]))
+}
+
+pub fn bets<W>(out: &mut W, bets: &[Bet]) -> Result<()>
+where
+  W: Write,
+{
+  if bets.is_empty() {
+    return writeln!(out, "  no bets on the table");
+  }
+
+  for bet in bets {
+    writeln!(out, "  {:<24} {:>12}", describe(bet), money(bet.weight()))?;
+  }
+
+  Ok(())
+}
+
+pub fn balance<W, R>(out: &mut W, table: &Table<R>, player: &Player) -> Result<()>
+where
+  W: Write,
+  R: Clone + Iterator<Item = u8>,
+{
+  let seat = table.balance(&player.id).unwrap_or(0);
+  let working = table
+    .bets(&player.id)
+    .unwrap_or(&[])
+    .iter()
+    .fold(0, |total, bet| total + bet.weight());
+
+  writeln!(out, "  {:<24} {:>12}", "at the table", money(seat))?;
+  writeln!(out, "  {:<24} {:>12}", "in bets", money(working))?;
+  writeln!(out, "  {:<24} {:>12}", "off the table", money(player.balance))?;
+  writeln!(out, "  {:<24} {:>12}", "total", money(seat + working + player.balance))
+}
+
+pub fn history<W, R>(out: &mut W, table: &Table<R>) -> Result<()>
+where
+  W: Write,
+  R: Clone + Iterator<Item = u8>,
+{
+  if table.rolls().is_empty() {
+    return writeln!(out, "  the dice haven't been thrown yet");
+  }
+
+  for (index, item) in table.rolls().iter().enumerate() {
+    writeln!(out, "  {:>2}. {}", index + 1, roll(item))?;
+  }
+
+  Ok(())
+}
+
+pub fn players<W, R>(out: &mut W, table: &Table<R>, players: &[Local], active: usize) -> Result<()>
+where
+  W: Write,
+  R: Clone + Iterator<Item = u8>,
+{
+  for (index, local) in players.iter().enumerate() {
+    let marker = if index == active { "*" } else { " " };
+    let seat = table
+      .balance(&local.player.id)
+      .map(money)
+      .unwrap_or_else(|| "not seated".into());
+    let rolling = match table.roller() == Some(local.player.id.as_str()) {
+      true => "rolling",
+      false => "",
+    };
+
+    writeln!(out, "{} {:<16} {:>12} {}", marker, local.name, seat, rolling)?;
+  }
+
+  Ok(())
+}
+
+pub fn thrown<W>(out: &mut W, thrown: &Roll, button: Option<u8>) -> Result<()>
+where
+  W: Write,
+{
+  let point = button
+    .map(|button| format!("point is {}", button))
+    .unwrap_or_else(|| "coming out".into());
+
+  writeln!(out, "rolled {}, {}", roll(thrown), point)
+}
+
+pub fn runs<'a, W, I>(out: &mut W, name: &str, runs: I) -> Result<()>
+where
+  W: Write,
+  I: Iterator<Item = (&'a Bet, bool, u32)>,
+{
+  let mut runs = runs.peekable();
+
+  if runs.peek().is_none() {
+    return Ok(());
+  }
+
+  writeln!(out, "  {}", name)?;
+
+  // Winning amounts include the original wager coming back, only the profit is shown.
+  for (bet, won, amount) in runs {
+    match won {
+      true => writeln!(
+        out,
+        "    won {} on {}",
+        money(amount.saturating_sub(bet.weight())),
+        describe(bet)
+      )?,
+      false => writeln!(out, "    lost {} on {}", money(amount), describe(bet))?,
+    }
+  }
+
+  Ok(())
+}
+
+// Winnings from bets a player left working after standing up go straight back to them.
+pub fn payout<W>(out: &mut W, name: &str, amount: u32) -> Result<()>
+where
+  W: Write,
+{
+  writeln!(out, "  {} collected {} from bets left working", name, money(amount))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::money;
+
+  #[test]
+  fn test_money() {
+    assert_eq!(money(0), "$0");
+    assert_eq!(money(999), "$999");
+    assert_eq!(money(1000), "$1,000");
+    assert_eq!(money(1234567), "$1,234,567");
+  }
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::io::{Result, Write};
+
+use twowaiyo::io::Action;
+use twowaiyo::{Player, Table};
+
+use super::dice::Ready;
+use super::render;
+use super::save;
+
+#[derive(Debug, PartialEq)]
+pub enum Flow {
+  Continue,
+  Exit,
+  OutOfDice,
+}
+
+// Refuses a throw when the dice have run out, rather than letting the table roll whatever is left. Returns true if the
+// throw was refused.
+pub fn out_of_dice<R, W>(dice: &R, out: &mut W) -> Result<bool>
+where
+  R: Ready,
+  W: Write,
+{
+  if dice.ready() {
+    return Ok(false);
+  }
+
+  writeln!(out, "out of dice")?;
+  Ok(true)
+}
+
+// Everyone playing from the same keyboard; names are how they are addressed in commands, ids are how the table knows
+// them.
+#[derive(Clone)]
+pub struct Local {
+  pub name: String,
+  pub player: Player,
+}
+
+// A session is any number of local players at a single table, with one of them active at a time. Every change made
+// between throws of the dice is remembered so it can be undone; once the dice are thrown there is no going back.
+pub struct Session<R>
+where
+  R: Clone + Iterator<Item = u8> + Ready,
+{
+  table: Table<R>,
+  players: Vec<Local>,
+  active: usize,
+  undo: Vec<(Table<R>, Vec<Local>, usize)>,
+  results: HashMap<String, twowaiyo::SeatRuns>,
+  throws: u64,
+  verbose: bool,
+}
+
+impl<R> Session<R>
+where
+  R: Clone + Iterator<Item = u8> + Ready,
+{
+  pub fn new(table: Table<R>, name: &str, mut player: Player) -> Self {
+    let table = table.sit(&mut player);
+    let players = vec![Local {
+      name: name.to_string(),
+      player,
+    }];
+
+    Session {
+      table,
+      players,
+      active: 0,
+      undo: Vec::new(),
+      results: HashMap::with_capacity(1),
+      throws: 0,
+      verbose: true,
+    }
+  }
+
+  // When not verbose, the table status is no longer printed after every change.
+  pub fn verbose(self, verbose: bool) -> Self {
+    Session { verbose, ..self }
+  }
+
+  pub fn status<W>(&self, out: &mut W) -> Result<()>
+  where
+    W: Write,
+  {
+    let local = &self.players[self.active];
+    render::status(out, &self.table, &local.name, &local.player)
+  }
+
+  fn show<W>(&self, out: &mut W) -> Result<()>
+  where
+    W: Write,
+  {
+    match self.verbose {
+      true => self.status(out),
+      false => Ok(()),
+    }
+  }
+
+  // The number of times the dice have been thrown during this session.
+  pub fn throws(&self) -> u64 {
+    self.throws
+  }
+
+  pub fn table(&self) -> &Table<R> {
+    &self.table
+  }
+
+  pub fn active(&self) -> &Local {
+    &self.players[self.active]
+  }
+
+  pub fn players(&self) -> &[Local] {
+    &self.players
+  }
+
+  // Settlement results from the most recent roll, keyed by player id.
+  pub fn results(&self) -> &HashMap<String, twowaiyo::SeatRuns> {
+    &self.results
+  }
+
+  fn remember(&mut self) {
+    self.undo.push((self.table.clone(), self.players.clone(), self.active));
+  }
+
+  fn find(&self, name: &str) -> Option<usize> {
+    self.players.iter().position(|local| local.name == name)
+  }
+
+  fn roller(&self) -> Option<&Local> {
+    let roller = self.table.roller()?;
+    self.players.iter().find(|local| local.player.id == roller)
+  }
+
+  pub fn apply<W>(&mut self, action: Action, out: &mut W) -> Result<Flow>
+  where
+    W: Write,
+  {
+    log::debug!("applying action - {}", action);
+
+    let player = self.players[self.active].player.clone();
+    let seated = self.table.is_seated(&player.id);
+
+    match action {
+      Action::Exit => return Ok(Flow::Exit),
+      Action::Help => writeln!(out, "{}", render::HELP)?,
+      Action::Balance => render::balance(out, &self.table, &player)?,
+      Action::Bets => render::bets(out, self.table.bets(&player.id).unwrap_or(&[]))?,
+      Action::History => render::history(out, &self.table)?,
+      Action::Players => render::players(out, &self.table, &self.players, self.active)?,
+
+      Action::As(name, action) => match self.find(&name) {
+        Some(index) => {
+          let previous = std::mem::replace(&mut self.active, index);
+          let flow = self.apply(*action, out)?;
+
+          // Switching inside of an `as` command would be surprising; whatever the nested command did, the previously
+          // active player is active again afterwards.
+          self.active = previous;
+          return Ok(flow);
+        }
+        None => writeln!(out, "no player named '{}', try 'join {}'", name, name)?,
+      },
+
+      Action::Join(name) if self.find(&name).is_some() => writeln!(out, "'{}' is already playing", name)?,
+
+      Action::Join(name) => {
+        self.remember();
+        let mut player = Player::default();
+        self.table = self.table.clone().sit(&mut player);
+        self.players.push(Local { name, player });
+        self.active = self.players.len() - 1;
+        self.show(out)?;
+      }
+
+      Action::Switch(name) => match self.find(&name) {
+        Some(index) => {
+          self.active = index;
+          self.show(out)?;
+        }
+        None => writeln!(out, "no player named '{}', try 'join {}'", name, name)?,
+      },
+
+      Action::Roll => {
+        if let Some(roller) = self.roller().filter(|roller| roller.player.id != player.id) {
+          writeln!(out, "the dice are with {}, try 'as {} roll'", roller.name, roller.name)?;
+          return Ok(Flow::Continue);
+        }
+
+        if out_of_dice(self.table.dice(), out)? {
+          return Ok(Flow::OutOfDice);
+        }
+
+        self.table.roll_into(&mut self.results);
+        self.undo.clear();
+        self.throws += 1;
+
+        let thrown = self.table.rolls().first().expect("missing roll after throw");
+        render::thrown(out, thrown, self.table.button())?;
+
+        for local in &self.players {
+          if let Some(runs) = self.results.get(&local.player.id) {
+            render::runs(out, &local.name, runs.iter())?;
+          }
+        }
+
+        let payouts = self.table.take_payouts();
+
+        for local in self.players.iter_mut() {
+          if let Some(amount) = payouts.get(&local.player.id) {
+            local.player.balance += amount;
+            render::payout(out, &local.name, *amount)?;
+          }
+        }
+
+        self.show(out)?;
+      }
+
+      Action::Bet(_) | Action::TakeDown(_) | Action::Stand if !seated => {
+        writeln!(out, "you aren't seated at the table, try 'sit'")?;
+      }
+
+      Action::Bet(bet) => match self.table.clone().bet(&player, &bet) {
+        Ok(table) => {
+          self.remember();
+          self.table = table;
+          self.show(out)?;
+        }
+        Err(error) => writeln!(out, "unable to make that bet - {:?}", error)?,
+      },
+
+      Action::TakeDown(removal) => match self.table.clone().take_down(&player, |bet| removal.matches(bet)) {
+        Ok(table) => {
+          self.remember();
+          self.table = table;
+          self.show(out)?;
+        }
+        Err(error) => writeln!(out, "unable to take that down - {:?}", error)?,
+      },
+
+      Action::Stand => {
+        self.remember();
+        let mut player = player;
+        self.table = self.table.clone().stand(&mut player);
+
+        match self.table.is_seated(&player.id) {
+          true => writeln!(
+            out,
+            "your line bets stay working, you'll be off the table once they're settled"
+          )?,
+          false => writeln!(out, "you've left the table with {}", render::money(player.balance))?,
+        }
+
+        self.players[self.active].player = player;
+      }
+
+      Action::Sit if seated => writeln!(out, "you're already seated")?,
+
+      Action::Sit => {
+        self.remember();
+        let mut player = player;
+        self.table = self.table.clone().sit(&mut player);
+        self.players[self.active].player = player;
+        self.show(out)?;
+      }
+
+      Action::Save(path) => {
+        let game = save::SavedGame::capture(&self.table, &self.players, self.active);
+
+        match save::write(&path, &game) {
+          Ok(()) => writeln!(out, "saved to '{}'", path)?,
+          Err(error) => writeln!(out, "unable to save to '{}' - {}", path, error)?,
+        }
+      }
+
+      // Loading replaces the whole game, but it can still be undone until the next roll.
+      Action::Load(path) => match save::read(&path).and_then(|game| game.restore(self.table.dice().clone())) {
+        Ok((table, players, active)) => {
+          self.remember();
+          self.table = table;
+          self.players = players;
+          self.active = active;
+          writeln!(out, "loaded '{}'", path)?;
+          self.show(out)?;
+        }
+        Err(error) => writeln!(out, "unable to load '{}' - {}", path, error)?,
+      },
+
+      Action::Undo => match self.undo.pop() {
+        Some((table, players, active)) => {
+          self.table = table;
+          self.players = players;
+          self.active = active;
+          writeln!(out, "undone")?;
+          self.show(out)?;
+        }
+        None => writeln!(out, "nothing to undo since the last roll")?,
+      },
+    }
+
+    Ok(Flow::Continue)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Flow, Session};
+  use crate::dice::Ready;
+  use twowaiyo::io::Action;
+  use twowaiyo::{Player, Table};
+
+  #[derive(Clone)]
+  struct LoadedDice(Vec<u8>);
+
+  impl Iterator for LoadedDice {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      self.0.pop()
+    }
+  }
+
+  impl Ready for LoadedDice {
+    fn ready(&self) -> bool {
+      self.0.len() >= 2
+    }
+  }
+
+  fn run(dice: Vec<u8>, lines: &[&str]) -> (Session<LoadedDice>, String) {
+    let mut session = Session::new(Table::with_dice(LoadedDice(dice)), "alice", Player::with_balance(100));
+    let mut out = Vec::new();
+
+    for line in lines {
+      let action = Action::parse(line).expect("bad test input");
+      assert_eq!(
+        session.apply(action, &mut out).expect("unable to write"),
+        Flow::Continue
+      );
+    }
+
+    (session, String::from_utf8(out).expect("invalid output"))
+  }
+
+  fn balance(session: &Session<LoadedDice>, name: &str) -> Option<u32> {
+    let index = session.find(name)?;
+    session.table.balance(&session.players[index].player.id)
+  }
+
+  #[test]
+  fn test_undo_bet() {
+    let (session, _) = run(vec![], &["bet pass 10", "bet field 5", "undo"]);
+    assert_eq!(balance(&session, "alice"), Some(90));
+  }
+
+  #[test]
+  fn test_undo_cleared_by_roll() {
+    let (session, output) = run(vec![5, 2], &["bet pass 10", "roll", "undo"]);
+    assert_eq!(balance(&session, "alice"), Some(110));
+    assert!(output.contains("won $10 on pass line"));
+    assert!(output.contains("nothing to undo"));
+  }
+
+  #[test]
+  fn test_stand_and_sit() {
+    let (session, output) = run(vec![], &["stand", "bet pass 10", "sit"]);
+    assert!(output.contains("left the table with $100"));
+    assert!(output.contains("aren't seated"));
+    assert_eq!(balance(&session, "alice"), Some(100));
+  }
+
+  #[test]
+  fn test_take_down() {
+    let (session, _) = run(vec![], &["bet field 10", "take down field"]);
+    assert_eq!(balance(&session, "alice"), Some(100));
+  }
+
+  #[test]
+  fn test_join_and_bet_as() {
+    let (session, output) = run(
+      vec![5, 2],
+      &[
+        "join bob",
+        "bet field 10",
+        "as alice bet pass 10",
+        "roll",
+        "as alice roll",
+      ],
+    );
+    assert!(output.contains("the dice are with alice"));
+    assert!(output.contains("alice\n    won $10 on pass line"));
+    assert!(output.contains("bob\n    lost $10 on field"));
+    assert_eq!(balance(&session, "alice"), Some(110));
+    assert_eq!(balance(&session, "bob"), Some(9990));
+    assert_eq!(session.active, 1);
+  }
+
+  #[test]
+  fn test_roll_out_of_dice() {
+    let (mut session, _) = run(vec![5, 2], &["bet pass 10", "roll"]);
+    let mut out = Vec::new();
+    let flow = session.apply(Action::Roll, &mut out).expect("unable to write");
+    assert_eq!(flow, Flow::OutOfDice);
+    assert_eq!(String::from_utf8(out).expect("invalid output"), "out of dice\n");
+    assert_eq!(session.throws(), 1);
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let path = std::env::temp_dir().join(format!("twowaiyo-save-{}.json", std::process::id()));
+    let path = path.to_str().expect("bad temp path");
+    let save = format!("save {}", path);
+    let load = format!("load {}", path);
+    let (original, _) = run(
+      vec![1, 3, 2, 2],
+      &[
+        "join bob",
+        "as alice bet pass 10",
+        "as alice roll",
+        "as alice bet place 6 6",
+        &save,
+      ],
+    );
+    let (session, output) = run(vec![], &[&load]);
+    std::fs::remove_file(path).expect("unable to clean up");
+
+    assert!(output.contains("loaded"));
+    assert_eq!(session.table.button(), Some(4));
+    assert_eq!(session.table.rolls(), original.table.rolls());
+    assert_eq!(session.table.roller(), original.table.roller());
+    assert_eq!(session.players.len(), 2);
+    assert_eq!(session.players[session.active].name, "bob");
+
+    for local in &original.players {
+      let id = &local.player.id;
+      assert_eq!(session.table.bets(id), original.table.bets(id));
+      assert_eq!(session.table.balance(id), original.table.balance(id));
+    }
+  }
+
+  #[test]
+  fn test_load_missing() {
+    let (_, output) = run(vec![], &["load /definitely/not/here.json"]);
+    assert!(output.contains("unable to load"));
+  }
+
+  #[test]
+  fn test_switch() {
+    let (session, output) = run(vec![], &["join bob", "switch alice", "switch carol", "join alice"]);
+    assert!(output.contains("no player named 'carol'"));
+    assert!(output.contains("'alice' is already playing"));
+    assert_eq!(session.active, 0);
+  }
+
+  #[test]
+  fn test_undo_join() {
+    let (session, _) = run(vec![], &["join bob", "undo"]);
+    assert_eq!(session.players.len(), 1);
+    assert_eq!(session.active, 0);
+  }
+}
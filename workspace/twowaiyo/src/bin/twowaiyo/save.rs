@@ -0,0 +1,79 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+
+use serde::{Deserialize, Serialize};
+
+use bankah::state::{PlayerState, TableState};
+use twowaiyo::{Player, Table};
+
+use super::repl::Local;
+
+// Saved games reuse the same serialized table and player schema as stickbot; local player names are kept in the
+// player (and seat) nicknames.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedGame {
+  pub table: TableState,
+  pub players: Vec<PlayerState>,
+  pub active: usize,
+}
+
+impl SavedGame {
+  pub fn capture<R>(table: &Table<R>, players: &[Local], active: usize) -> Self
+  where
+    R: Clone + Iterator<Item = u8>,
+  {
+    let mut table = TableState::from(table);
+
+    let players = players
+      .iter()
+      .map(|local| {
+        if let Some(seat) = table.seats.get_mut(&local.player.id) {
+          seat.nickname = local.name.clone();
+        }
+
+        PlayerState {
+          nickname: local.name.clone(),
+          ..PlayerState::from(&local.player)
+        }
+      })
+      .collect();
+
+    SavedGame { table, players, active }
+  }
+
+  // Saved games are plain json that may have been edited by hand, so the table is validated on the way back in.
+  pub fn restore<R>(&self, dice: R) -> Result<(Table<R>, Vec<Local>, usize)>
+  where
+    R: Clone + Iterator<Item = u8>,
+  {
+    let table = Table::try_from(&self.table)
+      .map_err(|error| Error::new(ErrorKind::InvalidData, error))?
+      .replace_dice(dice);
+    let players = self
+      .players
+      .iter()
+      .map(|state| Local {
+        name: state.nickname.clone(),
+        player: Player::from(state),
+      })
+      .collect();
+
+    Ok((table, players, self.active))
+  }
+}
+
+pub fn write(path: &str, game: &SavedGame) -> Result<()> {
+  let serialized = serde_json::to_string_pretty(game).map_err(|error| Error::new(ErrorKind::Other, error))?;
+  std::fs::write(path, serialized)
+}
+
+pub fn read(path: &str) -> Result<SavedGame> {
+  let contents = std::fs::read_to_string(path)?;
+  let game: SavedGame = serde_json::from_str(&contents).map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+  if game.players.is_empty() || game.active >= game.players.len() {
+    return Err(Error::new(ErrorKind::InvalidData, "saved game has no active player"));
+  }
+
+  Ok(game)
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Result, Write};
+
+use serde_json::{json, Value};
+
+use twowaiyo::io::Action;
+
+use super::dice::Dice;
+use super::render;
+use super::repl::{Flow, Session};
+
+fn balances(session: &Session<Dice>) -> Value {
+  let table = session.table();
+
+  let balances = session
+    .players()
+    .iter()
+    .map(|local| {
+      let id = &local.player.id;
+      let bets = table
+        .bets(id)
+        .unwrap_or(&[])
+        .iter()
+        .fold(0, |total, bet| total + bet.weight());
+      let entry = json!({ "seat": table.balance(id), "bets": bets, "rail": local.player.balance });
+      (local.name.clone(), entry)
+    })
+    .collect::<BTreeMap<String, Value>>();
+
+  json!(balances)
+}
+
+fn thrown(session: &Session<Dice>) -> Value {
+  let table = session.table();
+  let roll = table
+    .rolls()
+    .first()
+    .map(|roll| (roll.left(), roll.right(), roll.total()));
+
+  let results = session
+    .players()
+    .iter()
+    .filter_map(|local| {
+      let runs = session.results().get(&local.player.id)?;
+      let entries = runs
+        .iter()
+        .map(|(bet, won, amount)| json!({ "bet": render::describe(bet), "won": won, "amount": amount }))
+        .collect::<Vec<Value>>();
+      Some((local.name.clone(), json!(entries)))
+    })
+    .collect::<BTreeMap<String, Value>>();
+
+  json!({
+    "dice": roll.map(|(left, right, _)| [left, right]),
+    "total": roll.map(|(_, _, total)| total),
+    "button": table.button(),
+    "results": results,
+  })
+}
+
+// Runs every line of the script through the session, writing one json object per command. Blank lines and lines
+// starting with '#' are skipped. Returns false if the script could not be completed because the dice ran out.
+pub fn run<I, W>(session: &mut Session<Dice>, input: I, out: &mut W) -> Result<bool>
+where
+  I: BufRead,
+  W: Write,
+{
+  for (index, line) in input.lines().enumerate() {
+    let line = line?;
+    let number = index + 1;
+    let trimmed = line.trim();
+
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+      continue;
+    }
+
+    let action = match Action::parse(trimmed) {
+      Ok(action) => action,
+      Err(error) => {
+        let entry = json!({
+          "line": number,
+          "input": trimmed,
+          "error": error.to_string(),
+          "position": error.position,
+        });
+        writeln!(out, "{}", entry)?;
+        continue;
+      }
+    };
+
+    let canonical = action.to_string();
+    let throws = session.throws();
+    let mut text = Vec::new();
+    let flow = session.apply(action, &mut text)?;
+
+    if flow == Flow::OutOfDice {
+      let entry = json!({ "line": number, "input": trimmed, "error": "out of dice" });
+      writeln!(out, "{}", entry)?;
+      return Ok(false);
+    }
+
+    let output = String::from_utf8_lossy(&text)
+      .lines()
+      .map(|line| line.to_string())
+      .collect::<Vec<String>>();
+
+    let mut entry = json!({
+      "line": number,
+      "input": trimmed,
+      "action": canonical,
+      "output": output,
+      "balances": balances(session),
+    });
+
+    if session.throws() != throws {
+      entry["roll"] = thrown(session);
+    }
+
+    writeln!(out, "{}", entry)?;
+
+    if flow == Flow::Exit {
+      break;
+    }
+  }
+
+  Ok(true)
+}
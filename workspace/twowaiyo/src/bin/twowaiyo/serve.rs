@@ -0,0 +1,643 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Result, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use twowaiyo::io::Action;
+use twowaiyo::{Player, SeatRuns, Table};
+
+use super::dice::Dice;
+use super::render;
+use super::repl::{self, Flow, Local};
+
+pub const DEFAULT_ADDRESS: &'static str = "0.0.0.0:7777";
+
+const HELP: &'static str = "\
+commands:
+  roll (or an empty line)          throw the dice, if they are with you
+  bet <kind> [target] <amount>     make a bet; kinds are pass, come, field, place, hardway,
+                                   pass-odds, come-odds, put and put-come
+  take down <kind> [target]        pull a bet back off the table, or 'take down all'
+  balance                          show where your money is
+  bets                             list your bets
+  history                          list the most recent rolls
+  stand                            leave the table, taking down what you can
+  sit                              sit back down at the table
+  players                          list everyone at the table
+  help                             show this message
+  exit                             disconnect; your seat is kept until you come back";
+
+// Slow or stalled clients should not be able to hold up whoever is writing to them for too long.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// What came of a single command; the private part goes back to whoever sent it while the public part is written to
+// every connected client, including the sender.
+#[derive(Default)]
+pub struct Reply {
+  pub private: Vec<u8>,
+  pub public: Vec<u8>,
+}
+
+// Everything shared between connections: the table, everyone who has ever sat down (by name), and the write half of
+// every live connection.
+pub struct Lobby {
+  table: Table<Dice>,
+  players: Vec<Local>,
+  clients: HashMap<String, TcpStream>,
+  results: HashMap<String, SeatRuns>,
+}
+
+fn valid(name: &str) -> bool {
+  !name.is_empty() && name.len() <= 24 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl Lobby {
+  pub fn new(table: Table<Dice>) -> Self {
+    Lobby {
+      table,
+      players: Vec::new(),
+      clients: HashMap::new(),
+      results: HashMap::new(),
+    }
+  }
+
+  fn find(&self, name: &str) -> Option<usize> {
+    self.players.iter().position(|local| local.name == name)
+  }
+
+  pub fn is_connected(&self, name: &str) -> bool {
+    self.clients.contains_key(name)
+  }
+
+  // Sits the player down (or welcomes them back) and starts writing to them, unless someone with the same name is
+  // already connected. Checking and connecting happen together so two connections can't both take the same name.
+  pub fn enter(&mut self, name: &str, stream: TcpStream) -> Option<Reply> {
+    if self.is_connected(name) {
+      return None;
+    }
+
+    let mut reply = self.arrive(name);
+    self.clients.insert(name.to_string(), stream);
+    self.hand_off(&mut reply.public);
+    Some(reply)
+  }
+
+  // Names are how players are known to each other; a name that has been used before picks up the same player, so a
+  // dropped connection can come back to the bets it left on the table.
+  pub fn arrive(&mut self, name: &str) -> Reply {
+    let mut reply = Reply::default();
+
+    let message = match self.find(name) {
+      Some(_) => format!("{} is back", name),
+      None => {
+        let mut player = Player::default();
+        self.table = self.table.clone().sit(&mut player);
+        self.players.push(Local {
+          name: name.to_string(),
+          player,
+        });
+        format!("{} sat down", name)
+      }
+    };
+
+    // Writing to a vector cannot fail.
+    writeln!(reply.public, "{}", message).expect("unable to write reply");
+    reply
+  }
+
+  // Stops writing to the player. Should they have been holding the dice, they are passed on; whatever is written to
+  // `public` should be broadcast to everyone still connected.
+  pub fn disconnect(&mut self, name: &str, public: &mut Vec<u8>) {
+    self.clients.remove(name);
+    self.hand_off(public);
+  }
+
+  // Nobody can roll while the dice are with someone who isn't connected, so they go to the next connected player that
+  // is sitting at the table, in the order everyone first sat down.
+  fn hand_off(&mut self, public: &mut Vec<u8>) {
+    let roller = self
+      .table
+      .roller()
+      .and_then(|id| self.players.iter().position(|local| local.player.id == id));
+
+    let start = match roller {
+      Some(index) if !self.clients.contains_key(&self.players[index].name) => index + 1,
+      _ => return,
+    };
+
+    let count = self.players.len();
+    let next = (start..start + count)
+      .map(|index| &self.players[index % count])
+      .filter(|local| self.clients.contains_key(&local.name))
+      .find_map(|local| {
+        self
+          .table
+          .clone()
+          .pass_dice(&local.player.id)
+          .ok()
+          .map(|table| (local, table))
+      });
+
+    if let Some((local, table)) = next {
+      // Writing to a vector cannot fail.
+      writeln!(public, "the dice pass to {}", local.name).expect("unable to write reply");
+      self.table = table;
+    }
+  }
+
+  // Copies of the write half of every connection, so messages can be written without holding the lobby lock.
+  pub fn recipients(&self) -> Vec<(String, TcpStream)> {
+    self
+      .clients
+      .iter()
+      .filter_map(|(name, stream)| stream.try_clone().ok().map(|stream| (name.clone(), stream)))
+      .collect()
+  }
+
+  pub fn apply(&mut self, name: &str, action: Action) -> Result<(Flow, Reply)> {
+    let mut reply = Reply::default();
+    let index = match self.find(name) {
+      Some(index) => index,
+      None => {
+        writeln!(reply.private, "no player named '{}'", name)?;
+        return Ok((Flow::Continue, reply));
+      }
+    };
+
+    let player = self.players[index].player.clone();
+    let seated = self.table.is_seated(&player.id);
+    let out = &mut reply.private;
+
+    log::debug!("applying action for '{}' - {}", name, action);
+
+    match action {
+      Action::Exit => {
+        writeln!(reply.public, "{} left, their seat is kept for them", name)?;
+        return Ok((Flow::Exit, reply));
+      }
+      Action::Help => writeln!(out, "{}", HELP)?,
+      Action::Balance => render::balance(out, &self.table, &player)?,
+      Action::Bets => render::bets(out, self.table.bets(&player.id).unwrap_or(&[]))?,
+      Action::History => render::history(out, &self.table)?,
+      Action::Players => render::players(out, &self.table, &self.players, index)?,
+
+      Action::Undo | Action::Join(_) | Action::Switch(_) | Action::As(_, _) | Action::Save(_) | Action::Load(_) => {
+        writeln!(out, "'{}' isn't available over the network", action)?
+      }
+
+      Action::Roll => {
+        let roller = self
+          .table
+          .roller()
+          .and_then(|id| self.players.iter().find(|local| local.player.id == id));
+
+        if let Some(roller) = roller.filter(|roller| roller.player.id != player.id) {
+          writeln!(out, "the dice are with {}", roller.name)?;
+          return Ok((Flow::Continue, reply));
+        }
+
+        if repl::out_of_dice(self.table.dice(), out)? {
+          return Ok((Flow::OutOfDice, reply));
+        }
+
+        self.table.roll_into(&mut self.results);
+
+        let public = &mut reply.public;
+        let thrown = self.table.rolls().first().expect("missing roll after throw");
+        write!(public, "{} ", name)?;
+        render::thrown(public, thrown, self.table.button())?;
+
+        for local in &self.players {
+          if let Some(runs) = self.results.get(&local.player.id) {
+            render::runs(public, &local.name, runs.iter())?;
+          }
+        }
+
+        let payouts = self.table.take_payouts();
+
+        for local in self.players.iter_mut() {
+          if let Some(amount) = payouts.get(&local.player.id) {
+            local.player.balance += amount;
+            render::payout(public, &local.name, *amount)?;
+          }
+        }
+      }
+
+      Action::Bet(_) | Action::TakeDown(_) | Action::Stand if !seated => {
+        writeln!(out, "you aren't seated at the table, try 'sit'")?;
+      }
+
+      Action::Bet(bet) => match self.table.clone().bet(&player, &bet) {
+        Ok(table) => {
+          self.table = table;
+          writeln!(
+            reply.public,
+            "{} bet {} on {}",
+            name,
+            render::money(bet.weight()),
+            render::describe(&bet)
+          )?;
+        }
+        Err(error) => writeln!(out, "unable to make that bet - {:?}", error)?,
+      },
+
+      Action::TakeDown(removal) => match self.table.clone().take_down(&player, |bet| removal.matches(bet)) {
+        Ok(table) => {
+          self.table = table;
+          writeln!(reply.public, "{} took down {}", name, removal)?;
+        }
+        Err(error) => writeln!(out, "unable to take that down - {:?}", error)?,
+      },
+
+      Action::Stand => {
+        let mut player = player;
+        self.table = self.table.clone().stand(&mut player);
+
+        match self.table.is_seated(&player.id) {
+          true => writeln!(reply.public, "{} is standing once their line bets are settled", name)?,
+          false => writeln!(
+            reply.public,
+            "{} left the table with {}",
+            name,
+            render::money(player.balance)
+          )?,
+        }
+
+        self.players[index].player = player;
+      }
+
+      Action::Sit if seated => writeln!(out, "you're already seated")?,
+
+      Action::Sit => {
+        let mut player = player;
+        self.table = self.table.clone().sit(&mut player);
+        self.players[index].player = player;
+        writeln!(reply.public, "{} sat back down", name)?;
+      }
+    }
+
+    Ok((Flow::Continue, reply))
+  }
+}
+
+// Writes to every recipient, outside of the lobby lock. A connection that can't be written to is shut down, which ends
+// its own thread and takes it out of the lobby.
+fn broadcast(recipients: Vec<(String, TcpStream)>, message: &[u8]) {
+  if message.is_empty() {
+    return;
+  }
+
+  for (name, mut stream) in recipients {
+    if let Err(error) = stream.write_all(message) {
+      log::warn!("dropping client '{}' after failed write - {}", name, error);
+      let _ = stream.shutdown(Shutdown::Both);
+    }
+  }
+}
+
+// Asks for a name until a valid one that isn't already connected is given, then puts the player in the lobby. Returns
+// none if the client goes away first.
+fn greet<R, W>(lobby: &Mutex<Lobby>, stream: &TcpStream, input: &mut R, out: &mut W) -> Result<Option<String>>
+where
+  R: BufRead,
+  W: Write,
+{
+  writeln!(out, "welcome to twowaiyo, who's playing?")?;
+
+  loop {
+    let mut buffer = String::with_capacity(32);
+
+    if input.read_line(&mut buffer)? == 0 {
+      return Ok(None);
+    }
+
+    let name = buffer.trim().to_lowercase();
+
+    if !valid(&name) {
+      writeln!(
+        out,
+        "names are a single word of letters, numbers, '-' or '_'; who's playing?"
+      )?;
+      continue;
+    }
+
+    let (reply, recipients) = {
+      let mut lobby = lobby.lock().expect("lobby lock poisoned");
+      let reply = lobby.enter(&name, stream.try_clone()?);
+      (reply, lobby.recipients())
+    };
+
+    match reply {
+      Some(reply) => {
+        broadcast(recipients, &reply.public);
+        return Ok(Some(name));
+      }
+      None => writeln!(out, "'{}' is already connected, pick another name", name)?,
+    }
+  }
+}
+
+// Takes a client out of the lobby however its connection ends, including errors part way through a command. Unless the
+// player said they were leaving, everyone else is told they dropped.
+struct Presence<'a> {
+  lobby: &'a Mutex<Lobby>,
+  name: &'a str,
+  announce: bool,
+}
+
+impl Drop for Presence<'_> {
+  fn drop(&mut self) {
+    let mut public = Vec::new();
+
+    if self.announce {
+      public.extend_from_slice(format!("{} dropped, their seat is kept for them\n", self.name).as_bytes());
+    }
+
+    let recipients = {
+      let mut lobby = self.lobby.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+      lobby.disconnect(self.name, &mut public);
+      lobby.recipients()
+    };
+
+    broadcast(recipients, &public);
+  }
+}
+
+fn client(lobby: Arc<Mutex<Lobby>>, stream: TcpStream) -> Result<()> {
+  stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+  let mut out = stream.try_clone()?;
+  let mut input = BufReader::new(stream.try_clone()?);
+
+  let name = match greet(&lobby, &stream, &mut input, &mut out)? {
+    Some(name) => name,
+    None => return Ok(()),
+  };
+
+  let mut presence = Presence {
+    lobby: &lobby,
+    name: &name,
+    announce: true,
+  };
+
+  writeln!(out, "type 'help' for a list of commands")?;
+
+  loop {
+    let mut buffer = String::with_capacity(32);
+
+    if input.read_line(&mut buffer)? == 0 {
+      log::info!("'{}' disconnected", name);
+      return Ok(());
+    }
+
+    let action = match Action::parse(buffer.trim()) {
+      Ok(action) => action,
+      Err(error) => {
+        writeln!(out, "  {}", buffer.trim())?;
+        writeln!(out, "  {:>width$}", "^", width = error.position + 1)?;
+        writeln!(out, "{}", error)?;
+        continue;
+      }
+    };
+
+    let (flow, reply, recipients) = {
+      let mut lobby = lobby.lock().expect("lobby lock poisoned");
+      let (flow, mut reply) = lobby.apply(&name, action)?;
+
+      if flow == Flow::Exit {
+        presence.announce = false;
+        lobby.disconnect(&name, &mut reply.public);
+      }
+
+      (flow, reply, lobby.recipients())
+    };
+
+    out.write_all(&reply.private)?;
+    broadcast(recipients, &reply.public);
+
+    if flow == Flow::Exit {
+      writeln!(out, "goodbye")?;
+      return Ok(());
+    }
+  }
+}
+
+// Accepts connections forever, handling each of them on its own thread.
+pub fn serve(listener: TcpListener, lobby: Lobby) -> Result<()> {
+  let lobby = Arc::new(Mutex::new(lobby));
+
+  for stream in listener.incoming() {
+    let stream = match stream {
+      Ok(stream) => stream,
+      Err(error) => {
+        log::warn!("unable to accept connection - {}", error);
+        continue;
+      }
+    };
+
+    let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+    log::info!("accepted connection from {}", peer);
+
+    let lobby = lobby.clone();
+    std::thread::spawn(move || {
+      if let Err(error) = client(lobby, stream) {
+        log::warn!("connection from {} closed - {}", peer, error);
+      }
+    });
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{BufRead, BufReader, Write};
+  use std::net::{TcpListener, TcpStream};
+  use std::time::Duration;
+
+  use super::{serve, Lobby};
+  use crate::dice::Dice;
+  use crate::repl::Flow;
+  use twowaiyo::io::Action;
+  use twowaiyo::Table;
+
+  fn lobby(faces: &str) -> Lobby {
+    Lobby::new(Table::with_dice(Dice::parse(faces).expect("bad dice")))
+  }
+
+  fn apply(lobby: &mut Lobby, name: &str, line: &str) -> (Flow, String, String) {
+    let action = Action::parse(line).expect("bad test input");
+    let (flow, reply) = lobby.apply(name, action).expect("unable to apply");
+    let private = String::from_utf8(reply.private).expect("invalid output");
+    let public = String::from_utf8(reply.public).expect("invalid output");
+    (flow, private, public)
+  }
+
+  #[test]
+  fn test_roll_is_public() {
+    let mut lobby = lobby("5,2");
+    lobby.arrive("alice");
+    lobby.arrive("bob");
+
+    let (_, _, public) = apply(&mut lobby, "alice", "bet pass 10");
+    assert_eq!(public, "alice bet $10 on pass line\n");
+
+    let (_, _, public) = apply(&mut lobby, "bob", "bet field 10");
+    assert_eq!(public, "bob bet $10 on field\n");
+
+    let (_, private, public) = apply(&mut lobby, "bob", "roll");
+    assert_eq!(private, "the dice are with alice\n");
+    assert!(public.is_empty());
+
+    let (_, private, public) = apply(&mut lobby, "alice", "roll");
+    assert!(private.is_empty());
+    assert!(public.starts_with("alice rolled 5 + 2 = 7"));
+    assert!(public.contains("alice\n    won $10 on pass line"));
+    assert!(public.contains("bob\n    lost $10 on field"));
+
+    let (_, private, _) = apply(&mut lobby, "alice", "roll");
+    assert_eq!(private, "out of dice\n");
+  }
+
+  #[test]
+  fn test_local_commands_refused() {
+    let mut lobby = lobby("");
+    lobby.arrive("alice");
+
+    let (flow, private, _) = apply(&mut lobby, "alice", "as bob roll");
+    assert_eq!(flow, Flow::Continue);
+    assert!(private.contains("isn't available over the network"));
+
+    let (flow, _, public) = apply(&mut lobby, "alice", "exit");
+    assert_eq!(flow, Flow::Exit);
+    assert!(public.contains("alice left"));
+  }
+
+  #[test]
+  fn test_arrive_again() {
+    let mut lobby = lobby("");
+    lobby.arrive("alice");
+    apply(&mut lobby, "alice", "bet pass 10");
+
+    let reply = lobby.arrive("alice");
+    assert_eq!(
+      String::from_utf8(reply.public).expect("invalid output"),
+      "alice is back\n"
+    );
+    let id = &lobby.players[0].player.id;
+    assert_eq!(lobby.table.bets(id).map(|bets| bets.len()), Some(1));
+  }
+
+  #[test]
+  fn test_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("unable to bind");
+    let address = listener.local_addr().expect("no local address");
+    std::thread::spawn(move || serve(listener, lobby("3,3")));
+
+    let connect = |name: &str| {
+      let mut stream = TcpStream::connect(address).expect("unable to connect");
+      let mut reader = BufReader::new(stream.try_clone().expect("unable to clone"));
+      let mut line = String::new();
+      reader.read_line(&mut line).expect("unable to read");
+      assert!(line.contains("who's playing"));
+      writeln!(stream, "{}", name).expect("unable to write");
+      (stream, reader)
+    };
+
+    let read = |reader: &mut BufReader<TcpStream>, expected: &str| loop {
+      let mut line = String::new();
+      reader.read_line(&mut line).expect("unable to read");
+      assert!(!line.is_empty(), "connection closed before '{}'", expected);
+      if line.contains(expected) {
+        break;
+      }
+    };
+
+    let (mut alice, mut alice_reader) = connect("alice");
+    read(&mut alice_reader, "alice sat down");
+    let (_bob, mut bob_reader) = connect("Bob");
+    read(&mut bob_reader, "bob sat down");
+    read(&mut alice_reader, "bob sat down");
+
+    writeln!(alice, "roll").expect("unable to write");
+    read(&mut bob_reader, "alice rolled 3 + 3 = 6");
+    read(&mut alice_reader, "alice rolled 3 + 3 = 6");
+  }
+
+  #[test]
+  fn test_roller_disconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("unable to bind");
+    let address = listener.local_addr().expect("no local address");
+    std::thread::spawn(move || serve(listener, lobby("4,2")));
+
+    let connect = |name: &str| {
+      let mut stream = TcpStream::connect(address).expect("unable to connect");
+      stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .expect("unable to set timeout");
+      let reader = BufReader::new(stream.try_clone().expect("unable to clone"));
+      writeln!(stream, "{}", name).expect("unable to write");
+      (stream, reader)
+    };
+
+    let read = |reader: &mut BufReader<TcpStream>, expected: &str| loop {
+      let mut line = String::new();
+      reader.read_line(&mut line).expect("unable to read");
+      assert!(!line.is_empty(), "connection closed before '{}'", expected);
+      if line.contains(expected) {
+        break;
+      }
+    };
+
+    let (alice, mut alice_reader) = connect("alice");
+    read(&mut alice_reader, "alice sat down");
+    let (mut bob, mut bob_reader) = connect("bob");
+    read(&mut bob_reader, "bob sat down");
+
+    drop(alice_reader);
+    drop(alice);
+    read(&mut bob_reader, "alice dropped");
+    read(&mut bob_reader, "the dice pass to bob");
+
+    writeln!(bob, "roll").expect("unable to write");
+    read(&mut bob_reader, "bob rolled 4 + 2 = 6");
+  }
+
+  #[test]
+  fn test_invalid_input_disconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("unable to bind");
+    let address = listener.local_addr().expect("no local address");
+    std::thread::spawn(move || serve(listener, lobby("")));
+
+    let connect = |name: &str| {
+      let mut stream = TcpStream::connect(address).expect("unable to connect");
+      stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .expect("unable to set timeout");
+      let reader = BufReader::new(stream.try_clone().expect("unable to clone"));
+      writeln!(stream, "{}", name).expect("unable to write");
+      (stream, reader)
+    };
+
+    let read = |reader: &mut BufReader<TcpStream>, expected: &str| loop {
+      let mut line = String::new();
+      reader.read_line(&mut line).expect("unable to read");
+      assert!(!line.is_empty(), "connection closed before '{}'", expected);
+      if line.contains(expected) {
+        break;
+      }
+    };
+
+    let (mut alice, mut alice_reader) = connect("alice");
+    read(&mut alice_reader, "alice sat down");
+    let (_bob, mut bob_reader) = connect("bob");
+    read(&mut bob_reader, "bob sat down");
+
+    alice.write_all(&[0xff, 0xfe, b'\n']).expect("unable to write");
+    read(&mut bob_reader, "alice dropped");
+
+    let (_alice, mut alice_reader) = connect("alice");
+    read(&mut alice_reader, "alice is back");
+  }
+}
@@ -0,0 +1,294 @@
+use std::io::{stdout, Result, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+
+use twowaiyo::io::Action;
+
+use super::dice::Dice;
+use super::felt::{self, Direction, Spot, CHIPS};
+use super::render;
+use super::repl::{Flow, Session};
+
+const KEYS: &'static str =
+  "arrows move  +/- chip  enter bet  o odds  del take down  r roll  u undo  tab next player  q quit";
+const MESSAGES: usize = 6;
+const ROLLS: usize = 10;
+
+const CELL: u16 = 9;
+const PROPS: u16 = 14;
+const LEFT: u16 = 2;
+
+// Raw mode and the alternate screen are only held for as long as the guard is alive, so the terminal is put back the
+// way it was found even if the game loop bails out early with an error.
+struct Screen;
+
+impl Screen {
+  fn enter() -> Result<Self> {
+    terminal::enable_raw_mode()?;
+    execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+    Ok(Screen)
+  }
+}
+
+impl Drop for Screen {
+  fn drop(&mut self) {
+    if let Err(error) = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen) {
+      log::warn!("unable to leave alternate screen - {}", error);
+    }
+
+    if let Err(error) = terminal::disable_raw_mode() {
+      log::warn!("unable to disable raw mode - {}", error);
+    }
+  }
+}
+
+struct Felt {
+  rows: Vec<Vec<Spot>>,
+  cursor: (usize, usize),
+  chip: usize,
+  messages: Vec<String>,
+}
+
+impl Felt {
+  fn selected(&self) -> Spot {
+    self.rows[self.cursor.0][self.cursor.1]
+  }
+
+  fn amount(&self) -> u32 {
+    CHIPS[self.chip]
+  }
+
+  fn say<S>(&mut self, message: S)
+  where
+    S: Into<String>,
+  {
+    self.messages.push(message.into());
+    let overflow = self.messages.len().saturating_sub(MESSAGES);
+    self.messages.drain(..overflow);
+  }
+
+  // Where a spot is drawn, as the column, row and width of its label; chips are drawn on the line below.
+  fn area(&self, spot: Spot, row: usize, column: usize) -> (u16, u16, u16) {
+    let boxes = self.rows[0].len() as u16 - 1;
+    let main = boxes * CELL;
+    let top = 2 + row as u16 * 3;
+
+    match spot {
+      Spot::Hard(_) => (LEFT + main + 2, top, PROPS),
+      Spot::Box(_) => (LEFT + column as u16 * CELL, top, CELL - 1),
+      Spot::Pass => (LEFT, top, main - CELL * 2),
+      Spot::PassOdds => (LEFT + main - CELL * 2, top, CELL * 2 - 1),
+      Spot::Come | Spot::Field => (LEFT, top, main - 1),
+    }
+  }
+}
+
+fn fit(text: &str, width: u16) -> String {
+  let width = width as usize;
+  let clipped = text.chars().take(width).collect::<String>();
+  format!("{:<width$}", clipped, width = width)
+}
+
+fn draw<W>(out: &mut W, session: &Session<Dice>, felt: &Felt) -> Result<()>
+where
+  W: Write,
+{
+  let table = session.table();
+  let active = session.active();
+  let bets = table.bets(&active.player.id).unwrap_or(&[]);
+
+  queue!(out, terminal::Clear(terminal::ClearType::All))?;
+
+  let title = format!(
+    "twowaiyo - {:?} - playing as {} - chip {}",
+    table.rules().variant,
+    active.name,
+    render::money(felt.amount())
+  );
+  queue!(out, cursor::MoveTo(LEFT, 0), Print(title))?;
+
+  let mut bottom = 0;
+
+  for (row, spots) in felt.rows.iter().enumerate() {
+    for (column, spot) in spots.iter().enumerate() {
+      let (x, y, width) = felt.area(*spot, row, column);
+      let label = match (spot, table.button()) {
+        (Spot::Box(target), Some(button)) if *target == button => format!("{} ON", felt::label(*spot)),
+        (Spot::Pass, None) => format!("{}  OFF", felt::label(*spot)),
+        _ => felt::label(*spot),
+      };
+
+      let chips = felt::stack(&felt::chips(bets, *spot));
+      let selected = felt.cursor == (row, column);
+
+      if selected {
+        queue!(out, SetAttribute(Attribute::Reverse))?;
+      }
+
+      queue!(out, cursor::MoveTo(x, y), Print(fit(&format!("[{}]", label), width)))?;
+
+      if selected {
+        queue!(out, SetAttribute(Attribute::Reset))?;
+      }
+
+      queue!(out, cursor::MoveTo(x, y + 1), Print(fit(&chips, width)))?;
+      bottom = bottom.max(y + 2);
+    }
+  }
+
+  let panel = LEFT + (felt.rows[0].len() as u16 - 1) * CELL + 2 + PROPS + 2;
+
+  queue!(out, cursor::MoveTo(panel, 2), Print("seats"))?;
+
+  for (index, local) in session.players().iter().enumerate() {
+    let marker = match local.name == active.name {
+      true => '>',
+      false => ' ',
+    };
+    let dice = match table.roller() == Some(local.player.id.as_str()) {
+      true => " (dice)",
+      false => "",
+    };
+    let seat = match table.balance(&local.player.id) {
+      Some(balance) => render::money(balance),
+      None => "standing".into(),
+    };
+    let line = format!(
+      "{} {}{} seat {} rail {}",
+      marker,
+      local.name,
+      dice,
+      seat,
+      render::money(local.player.balance)
+    );
+    queue!(out, cursor::MoveTo(panel, 3 + index as u16), Print(line))?;
+  }
+
+  let rolls = table
+    .rolls()
+    .iter()
+    .take(ROLLS)
+    .map(|roll| format!("{} ({}+{})", roll.total(), roll.left(), roll.right()))
+    .collect::<Vec<String>>()
+    .join("  ");
+  queue!(
+    out,
+    cursor::MoveTo(LEFT, bottom + 1),
+    Print(format!("rolls: {}", rolls))
+  )?;
+
+  for (index, message) in felt.messages.iter().enumerate() {
+    queue!(out, cursor::MoveTo(LEFT, bottom + 3 + index as u16), Print(message))?;
+  }
+
+  queue!(out, cursor::MoveTo(LEFT, bottom + 4 + MESSAGES as u16), Print(KEYS))?;
+  out.flush()
+}
+
+fn action(session: &Session<Dice>, felt: &mut Felt, key: KeyEvent) -> Option<Action> {
+  let spot = felt.selected();
+  let direction = match key.code {
+    KeyCode::Up => Some(Direction::Up),
+    KeyCode::Down => Some(Direction::Down),
+    KeyCode::Left => Some(Direction::Left),
+    KeyCode::Right => Some(Direction::Right),
+    _ => None,
+  };
+
+  if let Some(direction) = direction {
+    felt.cursor = felt::step(&felt.rows, felt.cursor, direction);
+    return None;
+  }
+
+  match key.code {
+    KeyCode::Char('q') | KeyCode::Esc => Some(Action::Exit),
+    KeyCode::Char('+') | KeyCode::Char('=') => {
+      felt.chip = (felt.chip + 1).min(CHIPS.len() - 1);
+      None
+    }
+    KeyCode::Char('-') => {
+      felt.chip = felt.chip.saturating_sub(1);
+      None
+    }
+    KeyCode::Enter => Some(felt::bet(spot, felt.amount())),
+    KeyCode::Char('o') => {
+      let odds = felt::odds(spot, felt.amount());
+
+      if odds.is_none() {
+        felt.say(format!("no odds can be taken on {}", felt::label(spot).to_lowercase()));
+      }
+
+      odds
+    }
+    KeyCode::Backspace | KeyCode::Delete => Some(felt::take_down(spot)),
+    KeyCode::Char('u') => Some(Action::Undo),
+    KeyCode::Tab => {
+      let players = session.players();
+      let current = players.iter().position(|local| local.name == session.active().name)?;
+      let next = &players[(current + 1) % players.len()];
+      Some(Action::Switch(next.name.clone()))
+    }
+
+    // Whoever holds the dice throws them, regardless of who is active.
+    KeyCode::Char('r') | KeyCode::Char(' ') => {
+      let roller = session
+        .table()
+        .roller()
+        .and_then(|id| session.players().iter().find(|local| local.player.id == id));
+
+      match roller {
+        Some(local) => Some(Action::As(local.name.clone(), Box::new(Action::Roll))),
+        None => Some(Action::Roll),
+      }
+    }
+    _ => None,
+  }
+}
+
+// Runs the session full screen until the player quits. The session should not be verbose; anything it writes is
+// shown in the message area below the felt.
+pub fn run(session: &mut Session<Dice>) -> Result<()> {
+  let _screen = Screen::enter()?;
+  let stdout = stdout();
+  let mut out = stdout.lock();
+
+  let mut felt = Felt {
+    rows: felt::rows(session.table().rules().variant),
+    cursor: (0, 0),
+    chip: 1,
+    messages: vec!["welcome to the table".into()],
+  };
+
+  loop {
+    draw(&mut out, session, &felt)?;
+
+    let key = match event::read()? {
+      Event::Key(key) if key.kind == KeyEventKind::Press => key,
+      _ => continue,
+    };
+
+    let action = match action(session, &mut felt, key) {
+      Some(action) => action,
+      None => continue,
+    };
+
+    let mut text = Vec::new();
+    let flow = session.apply(action, &mut text)?;
+
+    for line in String::from_utf8_lossy(&text)
+      .lines()
+      .filter(|line| !line.trim().is_empty())
+    {
+      felt.say(line.trim_end());
+    }
+
+    if flow == Flow::Exit {
+      log::info!("received exit, leaving terminal ui");
+      break;
+    }
+  }
+
+  Ok(())
+}
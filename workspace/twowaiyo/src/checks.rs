@@ -4,3 +4,7 @@ pub fn is_place(amount: u8) -> bool {
     _ => false,
   }
 }
+
+pub fn is_crapless_place(amount: u8) -> bool {
+  is_place(amount) || matches!(amount, 2 | 3 | 11 | 12)
+}
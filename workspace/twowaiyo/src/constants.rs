@@ -1,7 +1,26 @@
 pub const PASS_ON_ERROR: &'static str = "invalid pass line bet with button established";
 pub const PASS_ODDS_OFF_ERROR: &'static str = "invalid pass line odds bet without button established";
-pub const COME_OFF_ERROR: &'static str = "invalid come bet with button established";
+pub const COME_OFF_ERROR: &'static str = "invalid come bet without button established";
 pub const PLACE_OFF_ERROR: &'static str = "invalid place bet without button established";
 pub const HARDWAY_OFF_ERROR: &'static str = "invalid hardway bet without button established";
+pub const PUT_OFF_ERROR: &'static str = "invalid put bet without button established";
+pub const PUT_TARGET_ERROR: &'static str = "invalid put bet target for established button";
+pub const MISSING_COME_FOR_ODDS_ERROR: &'static str = "invalid come odds bet without a come bet on the number";
+pub const MISSING_PASS_FOR_ODDS_ERROR: &'static str = "invalid pass line odds bet without a pass line bet";
+pub const INSUFFICIENT_FUNDS_ERROR: &'static str = "insufficient balance for bet";
+pub const ODDS_LIMIT_ERROR: &'static str = "odds bet exceeds the table maximum";
+pub const INVALID_RECIPIENT_ERROR: &'static str = "transfer recipient is not another player seated at table";
+pub const INVALID_SEAT_ERROR: &'static str = "player is not seated at table";
+pub const INVALID_TARGET_ERROR: &'static str = "invalid bet target for table";
+pub const INVALID_HARDWAY_ERROR: &'static str = "invalid hardway target";
+pub const INVALID_DIE_ERROR: &'static str = "invalid die face";
+pub const INVALID_BUTTON_ERROR: &'static str = "invalid button for table";
+pub const UNKNOWN_ROLLER_ERROR: &'static str = "roller is not seated at table";
+pub const UNKNOWN_TABLE_ERROR: &'static str = "no table on the floor with id";
+pub const UNKNOWN_PLAYER_ERROR: &'static str = "no player on the floor with id";
+pub const TABLE_LIMIT_ERROR: &'static str = "player is already seated at the maximum number of tables";
+pub const ALREADY_SEATED_ERROR: &'static str = "player is already seated at table";
+pub const NOT_SEATED_ERROR: &'static str = "player is not seated at table";
 
-pub const MAX_ROLL_HISTORY: usize = 10;
+pub const DEFAULT_ROLL_HISTORY: usize = 10;
+pub const DEFAULT_MAX_TABLES_PER_PLAYER: usize = 2;
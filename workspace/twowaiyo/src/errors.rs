@@ -6,6 +6,7 @@ pub enum PassLineNotEstablishedViolation {
   PlaceBet,
   HardwayBet,
   ComeBet,
+  PutBet,
 }
 
 impl std::fmt::Display for PassLineNotEstablishedViolation {
@@ -15,6 +16,19 @@ impl std::fmt::Display for PassLineNotEstablishedViolation {
       PassLineNotEstablishedViolation::PlaceBet => write!(formatter, "{}", constants::PLACE_OFF_ERROR),
       PassLineNotEstablishedViolation::HardwayBet => write!(formatter, "{}", constants::HARDWAY_OFF_ERROR),
       PassLineNotEstablishedViolation::ComeBet => write!(formatter, "{}", constants::COME_OFF_ERROR),
+      PassLineNotEstablishedViolation::PutBet => write!(formatter, "{}", constants::PUT_OFF_ERROR),
+    }
+  }
+}
+
+impl PassLineNotEstablishedViolation {
+  pub fn code(&self) -> &'static str {
+    match self {
+      PassLineNotEstablishedViolation::PassLineOddsBet => "pass_odds_without_point",
+      PassLineNotEstablishedViolation::PlaceBet => "place_without_point",
+      PassLineNotEstablishedViolation::HardwayBet => "hardway_without_point",
+      PassLineNotEstablishedViolation::ComeBet => "come_without_point",
+      PassLineNotEstablishedViolation::PutBet => "put_without_point",
     }
   }
 }
@@ -22,12 +36,23 @@ impl std::fmt::Display for PassLineNotEstablishedViolation {
 #[derive(Debug, PartialEq)]
 pub enum PassLineEstablishedViolation {
   PassLineBet,
+  PutTarget,
 }
 
 impl std::fmt::Display for PassLineEstablishedViolation {
   fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
     match self {
       PassLineEstablishedViolation::PassLineBet => write!(formatter, "{}", constants::PASS_ON_ERROR),
+      PassLineEstablishedViolation::PutTarget => write!(formatter, "{}", constants::PUT_TARGET_ERROR),
+    }
+  }
+}
+
+impl PassLineEstablishedViolation {
+  pub fn code(&self) -> &'static str {
+    match self {
+      PassLineEstablishedViolation::PassLineBet => "pass_with_point",
+      PassLineEstablishedViolation::PutTarget => "put_on_point",
     }
   }
 }
@@ -37,11 +62,28 @@ pub enum PlayerBetViolation {
   MissingComeForOdds,
   MissingPassForOdds,
   InsufficientFunds,
+  OddsLimitExceeded,
 }
 
 impl std::fmt::Display for PlayerBetViolation {
   fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-    write!(formatter, "{:?}", self)
+    match self {
+      PlayerBetViolation::MissingComeForOdds => write!(formatter, "{}", constants::MISSING_COME_FOR_ODDS_ERROR),
+      PlayerBetViolation::MissingPassForOdds => write!(formatter, "{}", constants::MISSING_PASS_FOR_ODDS_ERROR),
+      PlayerBetViolation::InsufficientFunds => write!(formatter, "{}", constants::INSUFFICIENT_FUNDS_ERROR),
+      PlayerBetViolation::OddsLimitExceeded => write!(formatter, "{}", constants::ODDS_LIMIT_ERROR),
+    }
+  }
+}
+
+impl PlayerBetViolation {
+  pub fn code(&self) -> &'static str {
+    match self {
+      PlayerBetViolation::MissingComeForOdds => "missing_come_for_odds",
+      PlayerBetViolation::MissingPassForOdds => "missing_pass_for_odds",
+      PlayerBetViolation::InsufficientFunds => "insufficient_funds",
+      PlayerBetViolation::OddsLimitExceeded => "odds_limit_exceeded",
+    }
   }
 }
 
@@ -50,7 +92,9 @@ pub enum RuleViolation {
   PassLineNotEstablished(PassLineNotEstablishedViolation),
   PassLineEstablished(PassLineEstablishedViolation),
   PlayerBetViolation(PlayerBetViolation),
+  InvalidTarget(u8),
   InvalidSeat,
+  InvalidRecipient,
 }
 
 pub const PASS_LINE_ALREADY_ON: RuleViolation =
@@ -63,6 +107,8 @@ pub const HARDWAY_OFF_ERROR: RuleViolation =
   RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::HardwayBet);
 pub const PASS_ODDS_OFF_ERROR: RuleViolation =
   RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::PassLineOddsBet);
+pub const PUT_OFF_ERROR: RuleViolation = RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::PutBet);
+pub const PUT_TARGET_ERROR: RuleViolation = RuleViolation::PassLineEstablished(PassLineEstablishedViolation::PutTarget);
 
 impl std::fmt::Display for RuleViolation {
   fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -70,11 +116,85 @@ impl std::fmt::Display for RuleViolation {
       RuleViolation::PassLineNotEstablished(violation) => write!(formatter, "{}", violation),
       RuleViolation::PassLineEstablished(violation) => write!(formatter, "{}", violation),
       RuleViolation::PlayerBetViolation(violation) => write!(formatter, "{}", violation),
-      RuleViolation::InvalidSeat => write!(formatter, "missing-seat"),
+      RuleViolation::InvalidTarget(target) => write!(formatter, "{} ({})", constants::INVALID_TARGET_ERROR, target),
+      RuleViolation::InvalidSeat => write!(formatter, "{}", constants::INVALID_SEAT_ERROR),
+      RuleViolation::InvalidRecipient => write!(formatter, "{}", constants::INVALID_RECIPIENT_ERROR),
+    }
+  }
+}
+
+impl std::error::Error for RuleViolation {}
+
+impl RuleViolation {
+  // A stable, machine readable identifier for the violation; unlike the message, this is safe for clients to match on.
+  pub fn code(&self) -> &'static str {
+    match self {
+      RuleViolation::PassLineNotEstablished(violation) => violation.code(),
+      RuleViolation::PassLineEstablished(violation) => violation.code(),
+      RuleViolation::PlayerBetViolation(violation) => violation.code(),
+      RuleViolation::InvalidTarget(_) => "invalid_target",
+      RuleViolation::InvalidSeat => "not_seated",
+      RuleViolation::InvalidRecipient => "invalid_recipient",
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FloorViolation {
+  UnknownTable(String),
+  UnknownPlayer(String),
+  TableLimitReached(usize),
+  AlreadySeated,
+  NotSeated,
+  Rule(RuleViolation),
+}
+
+impl std::fmt::Display for FloorViolation {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      FloorViolation::UnknownTable(id) => write!(formatter, "{} ({})", constants::UNKNOWN_TABLE_ERROR, id),
+      FloorViolation::UnknownPlayer(id) => write!(formatter, "{} ({})", constants::UNKNOWN_PLAYER_ERROR, id),
+      FloorViolation::TableLimitReached(limit) => write!(formatter, "{} ({})", constants::TABLE_LIMIT_ERROR, limit),
+      FloorViolation::AlreadySeated => write!(formatter, "{}", constants::ALREADY_SEATED_ERROR),
+      FloorViolation::NotSeated => write!(formatter, "{}", constants::NOT_SEATED_ERROR),
+      FloorViolation::Rule(violation) => write!(formatter, "{}", violation),
+    }
+  }
+}
+
+impl std::error::Error for FloorViolation {}
+
+impl From<RuleViolation> for FloorViolation {
+  fn from(violation: RuleViolation) -> Self {
+    FloorViolation::Rule(violation)
+  }
+}
+
+// Problems found while building engine types from their serialized `bankah` state. Anything that arrives from storage
+// or over the wire is validated on the way in, instead of producing bets that can never settle.
+#[derive(Debug, PartialEq)]
+pub enum ConversionError {
+  InvalidHardway(u8),
+  InvalidTarget(u8),
+  InvalidDie(u8),
+  InvalidButton(u8),
+  UnknownRoller(String),
+}
+
+impl std::fmt::Display for ConversionError {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ConversionError::InvalidHardway(target) => write!(formatter, "{} ({})", constants::INVALID_HARDWAY_ERROR, target),
+      ConversionError::InvalidTarget(target) => write!(formatter, "{} ({})", constants::INVALID_TARGET_ERROR, target),
+      ConversionError::InvalidDie(face) => write!(formatter, "{} ({})", constants::INVALID_DIE_ERROR, face),
+      ConversionError::InvalidButton(button) => write!(formatter, "{} ({})", constants::INVALID_BUTTON_ERROR, button),
+      ConversionError::UnknownRoller(id) => write!(formatter, "{} ({})", constants::UNKNOWN_ROLLER_ERROR, id),
     }
   }
 }
 
+impl std::error::Error for ConversionError {}
+
 pub struct CarryError<T> {
   kind: T,
   pub error: RuleViolation,
@@ -103,6 +223,10 @@ impl<T> CarryError<T> {
   pub fn consume(self) -> T {
     self.kind
   }
+
+  pub fn into_parts(self) -> (T, RuleViolation) {
+    (self.kind, self.error)
+  }
 }
 
 impl<T> std::fmt::Debug for CarryError<T> {
@@ -110,3 +234,35 @@ impl<T> std::fmt::Debug for CarryError<T> {
     write!(formatter, "{}", self.error)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{PassLineEstablishedViolation, PassLineNotEstablishedViolation, PlayerBetViolation, RuleViolation};
+
+  #[test]
+  fn test_rule_violation_codes_unique() {
+    let violations = vec![
+      RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::PassLineOddsBet),
+      RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::PlaceBet),
+      RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::HardwayBet),
+      RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::ComeBet),
+      RuleViolation::PassLineNotEstablished(PassLineNotEstablishedViolation::PutBet),
+      RuleViolation::PassLineEstablished(PassLineEstablishedViolation::PassLineBet),
+      RuleViolation::PassLineEstablished(PassLineEstablishedViolation::PutTarget),
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::MissingComeForOdds),
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::MissingPassForOdds),
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::InsufficientFunds),
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::OddsLimitExceeded),
+      RuleViolation::InvalidTarget(11),
+      RuleViolation::InvalidSeat,
+      RuleViolation::InvalidRecipient,
+    ];
+    let mut codes = violations
+      .iter()
+      .map(|violation| violation.code())
+      .collect::<Vec<&str>>();
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), violations.len());
+  }
+}
@@ -0,0 +1,152 @@
+use bankah::state::{FieldPaytableState, FieldPreset};
+
+// Ways to roll each total between 2 and 12 with two dice; used when computing the house edge of a paytable.
+const WAYS: [u8; 11] = [1, 2, 3, 4, 5, 6, 5, 4, 3, 2, 1];
+
+// The field paytable holds the multiplier paid for each total in the field, indexed from 2. A zero multiplier means
+// the total is not in the field and the bet loses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldPaytable {
+  pays: [u8; 11],
+}
+
+impl Default for FieldPaytable {
+  fn default() -> Self {
+    FieldPaytable::standard()
+  }
+}
+
+impl FieldPaytable {
+  pub fn standard() -> Self {
+    FieldPaytable {
+      pays: [2, 1, 1, 0, 0, 0, 0, 1, 1, 1, 2],
+    }
+  }
+
+  pub fn triple_twelve() -> Self {
+    FieldPaytable::standard().with(12, 3)
+  }
+
+  pub fn triple_two() -> Self {
+    FieldPaytable::standard().with(2, 3)
+  }
+
+  // Five takes the place of four in the field, and 2 and 12 are paid even money. Putting 5 in the field on top of the
+  // standard table would give the player the edge; this trade keeps the house edge of the standard table (5.56%).
+  pub fn five_for_four() -> Self {
+    FieldPaytable::standard().without(4).with(5, 1).with(2, 1).with(12, 1)
+  }
+
+  pub fn with(mut self, total: u8, multiplier: u8) -> Self {
+    if let Some(pay) = total.checked_sub(2).and_then(|index| self.pays.get_mut(index as usize)) {
+      *pay = multiplier;
+    }
+
+    self
+  }
+
+  pub fn without(self, total: u8) -> Self {
+    self.with(total, 0)
+  }
+
+  pub fn multiplier(&self, total: u8) -> Option<u32> {
+    total
+      .checked_sub(2)
+      .and_then(|index| self.pays.get(index as usize))
+      .and_then(|pay| if *pay > 0 { Some(*pay as u32) } else { None })
+  }
+
+  // The expected loss of a one unit field bet, as a fraction of the amount wagered.
+  pub fn house_edge(&self) -> f64 {
+    let expected = self
+      .pays
+      .iter()
+      .zip(WAYS.iter())
+      .fold(0i32, |acc, (pay, ways)| match pay {
+        0 => acc - *ways as i32,
+        pay => acc + (*pay as i32 * *ways as i32),
+      });
+
+    -(expected as f64) / 36.0
+  }
+}
+
+impl From<&FieldPreset> for FieldPaytable {
+  fn from(preset: &FieldPreset) -> FieldPaytable {
+    match preset {
+      FieldPreset::Standard => FieldPaytable::standard(),
+      FieldPreset::TripleTwelve => FieldPaytable::triple_twelve(),
+      FieldPreset::TripleTwo => FieldPaytable::triple_two(),
+      FieldPreset::FiveInField => FieldPaytable::five_for_four(),
+    }
+  }
+}
+
+impl From<&FieldPaytableState> for FieldPaytable {
+  fn from(state: &FieldPaytableState) -> FieldPaytable {
+    let blank = FieldPaytable { pays: [0; 11] };
+    state
+      .pays
+      .iter()
+      .fold(blank, |table, (total, multiplier)| table.with(*total, *multiplier))
+  }
+}
+
+impl From<&FieldPaytable> for FieldPaytableState {
+  fn from(table: &FieldPaytable) -> FieldPaytableState {
+    let pays = (2..=12)
+      .filter_map(|total| table.multiplier(total).map(|pay| (total, pay as u8)))
+      .collect();
+
+    FieldPaytableState { pays }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::FieldPaytable;
+  use bankah::state::FieldPaytableState;
+
+  fn edge(table: FieldPaytable) -> f64 {
+    (table.house_edge() * 10000.0).round() / 100.0
+  }
+
+  #[test]
+  fn standard_house_edge() {
+    assert_eq!(edge(FieldPaytable::standard()), 5.56);
+  }
+
+  #[test]
+  fn triple_house_edge() {
+    assert_eq!(edge(FieldPaytable::triple_twelve()), 2.78);
+    assert_eq!(edge(FieldPaytable::triple_two()), 2.78);
+  }
+
+  #[test]
+  fn five_for_four_multipliers() {
+    let table = FieldPaytable::five_for_four();
+    assert_eq!(table.multiplier(4), None);
+    assert_eq!(table.multiplier(5), Some(1));
+    assert_eq!(table.multiplier(12), Some(1));
+  }
+
+  #[test]
+  fn five_for_four_house_edge() {
+    assert_eq!(edge(FieldPaytable::five_for_four()), edge(FieldPaytable::standard()));
+    assert_eq!(edge(FieldPaytable::standard().with(5, 1)), -16.67);
+  }
+
+  #[test]
+  fn state_round_trip() {
+    let table = FieldPaytable::triple_twelve();
+    let state = FieldPaytableState::from(&table);
+    assert_eq!(FieldPaytable::from(&state), table);
+  }
+
+  #[test]
+  fn default_state_is_standard() {
+    let state = FieldPaytableState::default();
+    assert_eq!(FieldPaytable::from(&state), FieldPaytable::standard());
+  }
+}
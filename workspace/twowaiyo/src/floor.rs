@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use super::bets::Bet;
+use super::errors::FloorViolation;
+use super::player::Player;
+use super::rollers::RandomRoller;
+use super::seat::SeatRuns;
+use super::table::Table;
+
+// The floor owns every table and player known to a single process, keeping track of where each player is seated so
+// that callers only ever need to deal in identifiers.
+pub struct Floor<R>
+where
+  R: Clone + Iterator<Item = u8>,
+{
+  tables: HashMap<String, Table<R>>,
+  players: HashMap<String, Player>,
+  seating: HashMap<String, Vec<String>>,
+  limit: usize,
+}
+
+impl<R> Default for Floor<R>
+where
+  R: Clone + Iterator<Item = u8>,
+{
+  fn default() -> Self {
+    Floor::with_limit(crate::constants::DEFAULT_MAX_TABLES_PER_PLAYER)
+  }
+}
+
+impl Floor<RandomRoller> {
+  pub fn open(&mut self) -> String {
+    self.add_table(Table::default())
+  }
+}
+
+impl<R> Floor<R>
+where
+  R: Clone + Iterator<Item = u8>,
+{
+  pub fn with_limit(limit: usize) -> Self {
+    Floor {
+      tables: HashMap::new(),
+      players: HashMap::new(),
+      seating: HashMap::new(),
+      limit,
+    }
+  }
+
+  pub fn add_table(&mut self, table: Table<R>) -> String {
+    let id = table.identifier();
+    self.tables.insert(id.clone(), table);
+    id
+  }
+
+  pub fn add_player(&mut self, player: Player) -> String {
+    let id = player.id.clone();
+    self.players.insert(id.clone(), player);
+    id
+  }
+
+  pub fn table(&self, id: &str) -> Option<&Table<R>> {
+    self.tables.get(id)
+  }
+
+  pub fn player(&self, id: &str) -> Option<&Player> {
+    self.players.get(id)
+  }
+
+  // Returns the ids of every table the player currently holds a seat at, in the order they were joined.
+  pub fn seating(&self, player: &str) -> &[String] {
+    self.seating.get(player).map(|tables| tables.as_slice()).unwrap_or(&[])
+  }
+
+  // A player who left with bets still working can join again, sitting back down at the seat they left behind.
+  pub fn join(&mut self, player: &str, table: &str) -> Result<(), FloorViolation> {
+    let seated = self.seating(player);
+    let rejoining = self.tables.get(table).map(|t| t.is_orphaned(player)).unwrap_or(false);
+
+    if seated.iter().any(|id| id == table) && !rejoining {
+      return Err(FloorViolation::AlreadySeated);
+    }
+
+    if seated.len() >= self.limit && !rejoining {
+      return Err(FloorViolation::TableLimitReached(self.limit));
+    }
+
+    let current = self
+      .tables
+      .remove(table)
+      .ok_or_else(|| FloorViolation::UnknownTable(table.to_string()))?;
+
+    let patron = match self.players.get_mut(player) {
+      Some(patron) => patron,
+      None => {
+        self.tables.insert(table.to_string(), current);
+        return Err(FloorViolation::UnknownPlayer(player.to_string()));
+      }
+    };
+
+    self.tables.insert(table.to_string(), current.sit(patron));
+
+    if !rejoining {
+      self
+        .seating
+        .entry(player.to_string())
+        .or_default()
+        .push(table.to_string());
+    }
+
+    Ok(())
+  }
+
+  // Standing returns whatever balance the seat held back to the player. Much like the table itself, a player with
+  // bets still working remains seated until those bets are resolved.
+  pub fn leave(&mut self, player: &str, table: &str) -> Result<(), FloorViolation> {
+    if !self.seating(player).iter().any(|id| id == table) {
+      return Err(FloorViolation::NotSeated);
+    }
+
+    let current = self
+      .tables
+      .remove(table)
+      .ok_or_else(|| FloorViolation::UnknownTable(table.to_string()))?;
+
+    let patron = match self.players.get_mut(player) {
+      Some(patron) => patron,
+      None => {
+        self.tables.insert(table.to_string(), current);
+        return Err(FloorViolation::UnknownPlayer(player.to_string()));
+      }
+    };
+
+    let next = current.stand(patron);
+
+    if !next.is_seated(player) {
+      if let Some(tables) = self.seating.get_mut(player) {
+        tables.retain(|id| id != table);
+      }
+    }
+
+    self.tables.insert(table.to_string(), next);
+    Ok(())
+  }
+
+  pub fn bet(&mut self, player: &str, table: &str, bet: &Bet) -> Result<(), FloorViolation> {
+    let patron = self
+      .players
+      .get(player)
+      .ok_or_else(|| FloorViolation::UnknownPlayer(player.to_string()))?;
+
+    if !self.seating(player).iter().any(|id| id == table) {
+      return Err(FloorViolation::NotSeated);
+    }
+
+    let current = self
+      .tables
+      .remove(table)
+      .ok_or_else(|| FloorViolation::UnknownTable(table.to_string()))?;
+
+    match current.bet(patron, bet) {
+      Ok(next) => {
+        self.tables.insert(table.to_string(), next);
+        Ok(())
+      }
+      Err(carry) => {
+        let (previous, violation) = carry.into_parts();
+        self.tables.insert(table.to_string(), previous);
+        Err(violation.into())
+      }
+    }
+  }
+
+  pub fn roll(&mut self, table: &str) -> Result<HashMap<String, SeatRuns>, FloorViolation> {
+    let current = self
+      .tables
+      .get_mut(table)
+      .ok_or_else(|| FloorViolation::UnknownTable(table.to_string()))?;
+
+    let mut results = HashMap::with_capacity(current.population());
+    current.roll_into(&mut results);
+
+    // Players who stood up with bets working are paid out here, and are no longer seated once their last bet is gone.
+    for (id, amount) in current.take_payouts() {
+      if let Some(player) = self.players.get_mut(&id) {
+        player.balance += amount;
+      }
+    }
+
+    for (id, tables) in self.seating.iter_mut() {
+      if !current.is_seated(id) {
+        tables.retain(|seated| seated != table);
+      }
+    }
+
+    Ok(results)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Floor;
+  use crate::errors::{FloorViolation, RuleViolation};
+  use crate::{Bet, Player, Table};
+
+  #[derive(Debug, Default, Clone)]
+  struct TestDice(Vec<u8>);
+
+  impl Iterator for TestDice {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      self.0.pop()
+    }
+  }
+
+  fn floor_with(dice: Vec<u8>) -> (Floor<TestDice>, String, String) {
+    let mut floor = Floor::default();
+    let table = floor.add_table(Table::with_dice(TestDice(dice)));
+    let player = floor.add_player(Player::default());
+    (floor, table, player)
+  }
+
+  #[test]
+  fn test_join_and_leave() {
+    let (mut floor, table, player) = floor_with(vec![]);
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.seating(&player), &[table.clone()]);
+    assert_eq!(floor.player(&player).map(|p| p.balance), Some(0));
+    assert_eq!(floor.leave(&player, &table), Ok(()));
+    assert_eq!(floor.seating(&player).len(), 0);
+    assert_eq!(floor.player(&player).map(|p| p.balance), Some(10000));
+  }
+
+  #[test]
+  fn test_join_twice() {
+    let (mut floor, table, player) = floor_with(vec![]);
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.join(&player, &table), Err(FloorViolation::AlreadySeated));
+  }
+
+  #[test]
+  fn test_join_unknown() {
+    let (mut floor, table, player) = floor_with(vec![]);
+    let missing = FloorViolation::UnknownTable("nope".into());
+    assert_eq!(floor.join(&player, "nope"), Err(missing));
+    let missing = FloorViolation::UnknownPlayer("nope".into());
+    assert_eq!(floor.join("nope", &table), Err(missing));
+    assert_eq!(floor.table(&table).map(|t| t.population()), Some(0));
+  }
+
+  #[test]
+  fn test_table_limit() {
+    let mut floor = Floor::<TestDice>::with_limit(1);
+    let first = floor.add_table(Table::with_dice(TestDice::default()));
+    let second = floor.add_table(Table::with_dice(TestDice::default()));
+    let player = floor.add_player(Player::default());
+    assert_eq!(floor.join(&player, &first), Ok(()));
+    assert_eq!(floor.join(&player, &second), Err(FloorViolation::TableLimitReached(1)));
+    assert_eq!(floor.leave(&player, &first), Ok(()));
+    assert_eq!(floor.join(&player, &second), Ok(()));
+  }
+
+  #[test]
+  fn test_leave_not_seated() {
+    let (mut floor, table, player) = floor_with(vec![]);
+    assert_eq!(floor.leave(&player, &table), Err(FloorViolation::NotSeated));
+  }
+
+  #[test]
+  fn test_bet_and_roll() {
+    let (mut floor, table, player) = floor_with(vec![5, 2]);
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.bet(&player, &table, &Bet::start_pass(100)), Ok(()));
+    let results = floor.roll(&table).expect("unable to roll");
+    assert_eq!(results.get(&player).map(|r| r.winnings()), Some(200));
+  }
+
+  #[test]
+  fn test_bet_violation() {
+    let (mut floor, table, player) = floor_with(vec![]);
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    let result = floor.bet(&player, &table, &Bet::Place(60, 6));
+    assert!(matches!(
+      result,
+      Err(FloorViolation::Rule(RuleViolation::PassLineNotEstablished(_)))
+    ));
+    assert_eq!(floor.table(&table).map(|t| t.population()), Some(1));
+  }
+
+  #[test]
+  fn test_bet_not_seated() {
+    let (mut floor, table, player) = floor_with(vec![]);
+    let result = floor.bet(&player, &table, &Bet::start_pass(100));
+    assert_eq!(result, Err(FloorViolation::NotSeated));
+  }
+
+  #[test]
+  fn test_leave_with_bets() {
+    let (mut floor, table, player) = floor_with(vec![2, 2]);
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.bet(&player, &table, &Bet::start_pass(100)), Ok(()));
+    assert!(floor.roll(&table).is_ok());
+    assert_eq!(floor.leave(&player, &table), Ok(()));
+    assert_eq!(floor.seating(&player), &[table.clone()]);
+    assert_eq!(floor.player(&player).map(|p| p.balance), Some(9900));
+  }
+
+  #[test]
+  fn test_rejoin_after_leaving_with_bets() {
+    let mut floor = Floor::<TestDice>::with_limit(1);
+    let table = floor.add_table(Table::with_dice(TestDice(vec![2, 2])));
+    let player = floor.add_player(Player::default());
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.bet(&player, &table, &Bet::start_pass(100)), Ok(()));
+    assert!(floor.roll(&table).is_ok());
+    assert_eq!(floor.leave(&player, &table), Ok(()));
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.join(&player, &table), Err(FloorViolation::AlreadySeated));
+    assert_eq!(floor.seating(&player), &[table.clone()]);
+    assert_eq!(floor.player(&player).map(|p| p.balance), Some(0));
+
+    let seated = floor.table(&table).expect("missing table");
+    assert!(!seated.is_orphaned(&player));
+    assert_eq!(seated.balance(&player), Some(9900));
+    assert_eq!(seated.bets(&player).map(|bets| bets.len()), Some(1));
+  }
+
+  #[test]
+  fn test_leave_with_bets_paid_out() {
+    let (mut floor, table, player) = floor_with(vec![1, 3, 2, 2]);
+    assert_eq!(floor.join(&player, &table), Ok(()));
+    assert_eq!(floor.bet(&player, &table, &Bet::start_pass(100)), Ok(()));
+    assert!(floor.roll(&table).is_ok());
+    assert_eq!(floor.leave(&player, &table), Ok(()));
+    assert!(floor.roll(&table).is_ok());
+    assert_eq!(floor.seating(&player).len(), 0);
+    assert_eq!(floor.player(&player).map(|p| p.balance), Some(10100));
+    assert_eq!(floor.table(&table).map(|t| t.population()), Some(0));
+  }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use super::roll::Roll;
+use super::seat::SeatRuns;
+
+use bankah::state::HandState;
+
+// A shooter's hand runs from the first roll they throw until they seven out (or give up the dice by leaving the
+// table). Amounts won and lost are totals across every seat at the table; wins only count the profit on a bet, not the
+// original wager coming back.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hand {
+  shooter: String,
+  rolls: u32,
+  points_set: u32,
+  points_made: u32,
+  seven_out: bool,
+  won: u32,
+  lost: u32,
+  started_at: SystemTime,
+  finished_at: Option<SystemTime>,
+}
+
+impl Hand {
+  pub fn new<S>(shooter: S) -> Self
+  where
+    S: Into<String>,
+  {
+    Hand {
+      shooter: shooter.into(),
+      rolls: 0,
+      points_set: 0,
+      points_made: 0,
+      seven_out: false,
+      won: 0,
+      lost: 0,
+      started_at: SystemTime::now(),
+      finished_at: None,
+    }
+  }
+
+  pub fn shooter(&self) -> &str {
+    &self.shooter
+  }
+
+  pub fn rolls(&self) -> u32 {
+    self.rolls
+  }
+
+  pub fn points_set(&self) -> u32 {
+    self.points_set
+  }
+
+  pub fn points_made(&self) -> u32 {
+    self.points_made
+  }
+
+  pub fn seven_out(&self) -> bool {
+    self.seven_out
+  }
+
+  pub fn won(&self) -> u32 {
+    self.won
+  }
+
+  pub fn lost(&self) -> u32 {
+    self.lost
+  }
+
+  pub fn started_at(&self) -> SystemTime {
+    self.started_at
+  }
+
+  pub fn finished_at(&self) -> Option<SystemTime> {
+    self.finished_at
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.finished_at.is_some()
+  }
+
+  // How long the hand lasted, or has lasted so far if it is still going.
+  pub fn duration(&self) -> Duration {
+    let end = self.finished_at.unwrap_or_else(SystemTime::now);
+    end.duration_since(self.started_at).unwrap_or_default()
+  }
+
+  // Records a roll that was thrown during this hand, given the button before and after it was settled.
+  pub(crate) fn record(
+    &mut self,
+    roll: &Roll,
+    before: Option<u8>,
+    after: Option<u8>,
+    results: &HashMap<String, SeatRuns>,
+  ) {
+    self.rolls += 1;
+
+    match (before, after) {
+      (None, Some(_)) => self.points_set += 1,
+      (Some(button), None) if roll.total() == button => self.points_made += 1,
+      (Some(_), None) => self.seven_out = true,
+      _ => (),
+    }
+
+    for runs in results.values() {
+      for (bet, won, amount) in runs.iter() {
+        match won {
+          true => self.won += amount.saturating_sub(bet.weight()),
+          false => self.lost += amount,
+        }
+      }
+    }
+  }
+
+  pub(crate) fn finish(self) -> Self {
+    Hand {
+      finished_at: Some(SystemTime::now()),
+      ..self
+    }
+  }
+}
+
+impl From<&Hand> for HandState {
+  fn from(hand: &Hand) -> HandState {
+    HandState {
+      shooter: hand.shooter.clone(),
+      rolls: hand.rolls,
+      points_set: hand.points_set,
+      points_made: hand.points_made,
+      seven_out: hand.seven_out,
+      won: hand.won,
+      lost: hand.lost,
+      net: hand.won as i64 - hand.lost as i64,
+      started_at: hand.started_at.into(),
+      finished_at: hand.finished_at.map(|time| time.into()),
+      ..HandState::default()
+    }
+  }
+}
+
+impl From<&HandState> for Hand {
+  fn from(state: &HandState) -> Hand {
+    Hand {
+      shooter: state.shooter.clone(),
+      rolls: state.rolls,
+      points_set: state.points_set,
+      points_made: state.points_made,
+      seven_out: state.seven_out,
+      won: state.won,
+      lost: state.lost,
+      started_at: state.started_at.into(),
+      finished_at: state.finished_at.map(|time| time.into()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Hand;
+  use crate::Roll;
+  use bankah::state::HandState;
+  use std::collections::HashMap;
+
+  fn roll(left: u8, right: u8) -> Roll {
+    vec![left, right].into_iter().collect()
+  }
+
+  #[test]
+  fn test_record_points() {
+    let results = HashMap::new();
+    let mut hand = Hand::new("shooter");
+    hand.record(&roll(2, 2), None, Some(4), &results);
+    hand.record(&roll(3, 1), Some(4), None, &results);
+    hand.record(&roll(5, 5), None, Some(10), &results);
+    hand.record(&roll(5, 2), Some(10), None, &results);
+    assert_eq!(hand.rolls(), 4);
+    assert_eq!(hand.points_set(), 2);
+    assert_eq!(hand.points_made(), 1);
+    assert!(hand.seven_out());
+  }
+
+  #[test]
+  fn test_state_round_trip() {
+    let mut hand = Hand::new("shooter");
+    hand.record(&roll(2, 2), None, Some(4), &HashMap::new());
+    let hand = hand.finish();
+    let state = HandState::from(&hand);
+    assert_eq!(Hand::from(&state), hand);
+  }
+}
@@ -1,142 +1,586 @@
+use std::convert::TryFrom;
 use std::str::FromStr;
 
 use super::{bets::Bet, checks, roll::Hardway};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Action {
   Exit,
   Roll,
   Bet(Bet),
+  TakeDown(TakeDown),
+  Help,
+  Balance,
+  Bets,
+  History,
+  Stand,
+  Sit,
+  Undo,
+  Join(String),
+  Switch(String),
+  Players,
+  As(String, Box<Action>),
+  Save(String),
+  Load(String),
 }
 
-fn log_pass<E>(error: E) -> E
-where
-  E: std::fmt::Display,
-{
-  log::warn!("unable to parse input - {}", error);
-  error
+// Identifies which of a player's bets should be pulled from the table; any line bet that already has a point will
+// stay up regardless.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TakeDown {
+  All,
+  Pass,
+  Come,
+  Field,
+  PassOdds,
+  Place(u8),
+  ComeOdds(u8),
+  Hardway(Hardway),
 }
 
-fn parse_bet_line(parts: &Vec<&str>) -> Option<Action> {
-  log::debug!("parsing bet string - '{:?}'", parts);
+impl TakeDown {
+  pub fn matches(&self, bet: &Bet) -> bool {
+    match (self, bet) {
+      (TakeDown::All, _) => true,
+      (TakeDown::Pass, Bet::Pass(_)) => true,
+      (TakeDown::Come, Bet::Come(_)) => true,
+      (TakeDown::Field, Bet::Field(_)) => true,
+      (TakeDown::PassOdds, Bet::PassOdds(_, _)) => true,
+      (TakeDown::Place(target), Bet::Place(_, on)) => target == on,
+      (TakeDown::ComeOdds(target), Bet::ComeOdds(_, on)) => target == on,
+      (TakeDown::Hardway(way), Bet::Hardway(_, on)) => way == on,
+      _ => false,
+    }
+  }
+}
 
-  match parts[..] {
-    ["bet", "field", value] => {
-      log::debug!("parsing field bet - {}", value);
+impl std::fmt::Display for TakeDown {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      TakeDown::All => write!(formatter, "all"),
+      TakeDown::Pass => write!(formatter, "pass"),
+      TakeDown::Come => write!(formatter, "come"),
+      TakeDown::Field => write!(formatter, "field"),
+      TakeDown::PassOdds => write!(formatter, "pass-odds"),
+      TakeDown::Place(target) => write!(formatter, "place {}", target),
+      TakeDown::ComeOdds(target) => write!(formatter, "come-odds {}", target),
+      TakeDown::Hardway(way) => write!(formatter, "hardway {}", u8::from(way)),
+    }
+  }
+}
 
-      u32::from_str(value)
-        .map_err(log_pass)
-        .ok()
-        .map(Bet::Field)
-        .map(Action::Bet)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+  Command,
+  Keyword(&'static str),
+  BetKind,
+  Amount,
+  Target,
+  Hardway,
+  TakeDown,
+  Name,
+  Path,
+  End,
+}
+
+impl std::fmt::Display for Expected {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Expected::Command => write!(formatter, "a command (try 'help')"),
+      Expected::Keyword(keyword) => write!(formatter, "'{}'", keyword),
+      Expected::BetKind => write!(
+        formatter,
+        "a bet (pass, come, field, place, hardway, pass-odds, come-odds, put, put-come)"
+      ),
+      Expected::Amount => write!(formatter, "an amount"),
+      Expected::Target => write!(formatter, "a point (2, 3, 4, 5, 6, 8, 9, 10, 11, 12)"),
+      Expected::Hardway => write!(formatter, "a hardway (4, 6, 8, 10)"),
+      Expected::TakeDown => write!(
+        formatter,
+        "a bet to take down (all, pass, come, field, place, hardway, pass-odds, come-odds)"
+      ),
+      Expected::Name => write!(formatter, "a player name (letters, numbers, '-' or '_')"),
+      Expected::Path => write!(formatter, "a file path"),
+      Expected::End => write!(formatter, "end of input"),
     }
+  }
+}
 
-    ["bet", "place", target, value] => {
-      log::debug!("parsing come line bet - {}", value);
-      let parsed_target =
-        u8::from_str(target)
-          .map_err(log_pass)
-          .ok()
-          .and_then(|value| if checks::is_place(value) { Some(value) } else { None });
-      let parsed_value = u32::from_str(value).map_err(log_pass).ok();
+// Positions are byte offsets into the original input, pointing at the start of the offending token (or the end of the
+// input when it ran out early).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+  pub expected: Expected,
+  pub position: usize,
+  pub found: Option<String>,
+}
 
-      parsed_target
-        .zip(parsed_value)
-        .map(|(target, value)| Bet::Place(value, target))
-        .map(Action::Bet)
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match &self.found {
+      Some(found) => write!(
+        formatter,
+        "expected {} at position {}, found '{}'",
+        self.expected, self.position, found
+      ),
+      None => write!(
+        formatter,
+        "expected {} at position {}, found end of input",
+        self.expected, self.position
+      ),
     }
+  }
+}
 
-    ["bet", "hardway", "four", value] => u32::from_str(value)
-      .map_err(log_pass)
-      .ok()
-      .map(|amount| Bet::Hardway(amount, Hardway::Four))
-      .map(Action::Bet),
+impl std::error::Error for ParseError {}
 
-    ["bet", "hardway", "six", value] => u32::from_str(value)
-      .map_err(log_pass)
-      .ok()
-      .map(|amount| Bet::Hardway(amount, Hardway::Six))
-      .map(Action::Bet),
+enum Kind {
+  Pass,
+  Come,
+  Field,
+  Place,
+  Hardway,
+  PassOdds,
+  ComeOdds,
+  Put,
+  PutCome,
+}
 
-    ["bet", "hardway", "eight", value] => u32::from_str(value)
-      .map_err(log_pass)
-      .ok()
-      .map(|amount| Bet::Hardway(amount, Hardway::Eight))
-      .map(Action::Bet),
+struct Tokens<'a> {
+  input: &'a str,
+  offset: usize,
+}
 
-    ["bet", "hardway", "ten", value] => u32::from_str(value)
-      .map_err(log_pass)
-      .ok()
-      .map(|amount| Bet::Hardway(amount, Hardway::Ten))
-      .map(Action::Bet),
+impl<'a> Tokens<'a> {
+  fn new(input: &'a str) -> Self {
+    Tokens { input, offset: 0 }
+  }
 
-    ["bet", "come", value] => {
-      log::debug!("parsing come line bet - {}", value);
+  fn next(&mut self) -> Option<(usize, &'a str)> {
+    let rest = &self.input[self.offset..];
+    let trimmed = rest.trim_start();
 
-      u32::from_str(value)
-        .map_err(log_pass)
-        .ok()
-        .map(|amount| Bet::start_come(amount))
-        .map(Action::Bet)
+    if trimmed.is_empty() {
+      self.offset = self.input.len();
+      return None;
     }
 
-    ["bet", "come-odds", target, value] => {
-      log::debug!("parsing come odds - {} on {}", value, target);
+    let start = self.offset + (rest.len() - trimmed.len());
+    let length = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    self.offset = start + length;
+    Some((start, &trimmed[..length]))
+  }
 
-      let parsed_amount = u32::from_str(value).map_err(log_pass).ok();
-      let parsed_target = u8::from_str(target).map_err(log_pass).ok();
+  fn expect<T, F>(&mut self, expected: Expected, parse: F) -> Result<T, ParseError>
+  where
+    F: FnOnce(&str) -> Option<T>,
+  {
+    match self.next() {
+      Some((position, token)) => parse(token).ok_or_else(|| ParseError {
+        expected,
+        position,
+        found: Some(token.to_string()),
+      }),
+      None => Err(ParseError {
+        expected,
+        position: self.input.len(),
+        found: None,
+      }),
+    }
+  }
 
-      parsed_amount
-        .zip(parsed_target)
-        .map(|(amount, target)| Bet::ComeOdds(amount, target))
-        .map(Action::Bet)
+  fn finish(&mut self) -> Result<(), ParseError> {
+    match self.next() {
+      None => Ok(()),
+      Some((position, token)) => Err(ParseError {
+        expected: Expected::End,
+        position,
+        found: Some(token.to_string()),
+      }),
     }
+  }
+}
+
+fn kind(token: &str) -> Option<Kind> {
+  match token.to_ascii_lowercase().as_str() {
+    "pass" | "p" | "line" => Some(Kind::Pass),
+    "come" | "c" => Some(Kind::Come),
+    "field" | "f" => Some(Kind::Field),
+    "place" | "pl" => Some(Kind::Place),
+    "hardway" | "hard" | "hw" => Some(Kind::Hardway),
+    "pass-odds" | "po" | "odds" => Some(Kind::PassOdds),
+    "come-odds" | "co" => Some(Kind::ComeOdds),
+    "put" => Some(Kind::Put),
+    "put-come" | "pc" => Some(Kind::PutCome),
+    _ => None,
+  }
+}
+
+fn number(token: &str) -> Option<u8> {
+  u8::from_str(token).ok().or(match token.to_ascii_lowercase().as_str() {
+    "two" => Some(2),
+    "three" => Some(3),
+    "four" => Some(4),
+    "five" => Some(5),
+    "six" => Some(6),
+    "eight" => Some(8),
+    "nine" => Some(9),
+    "ten" => Some(10),
+    "eleven" | "yo" => Some(11),
+    "twelve" | "boxcars" => Some(12),
+    _ => None,
+  })
+}
 
-    ["bet", "pass-odds", value] => {
-      log::debug!("parsing pass odds - {}", value);
+fn amount(token: &str) -> Option<u32> {
+  u32::from_str(token.strip_prefix('$').unwrap_or(token)).ok()
+}
+
+fn target(token: &str) -> Option<u8> {
+  number(token).filter(|value| checks::is_crapless_place(*value))
+}
+
+fn hardway(token: &str) -> Option<Hardway> {
+  number(token).and_then(|value| Hardway::try_from(value).ok())
+}
 
-      u32::from_str(value)
-        .map_err(log_pass)
-        .ok()
-        .map(|amount| Bet::PassOdds(amount, 0))
-        .map(Action::Bet)
+fn parse_bet(tokens: &mut Tokens) -> Result<Bet, ParseError> {
+  let bet = match tokens.expect(Expected::BetKind, kind)? {
+    Kind::Pass => Bet::start_pass(tokens.expect(Expected::Amount, amount)?),
+    Kind::Come => Bet::start_come(tokens.expect(Expected::Amount, amount)?),
+    Kind::Field => Bet::Field(tokens.expect(Expected::Amount, amount)?),
+    Kind::PassOdds => Bet::PassOdds(tokens.expect(Expected::Amount, amount)?, 0),
+    Kind::Place => {
+      let target = tokens.expect(Expected::Target, target)?;
+      Bet::Place(tokens.expect(Expected::Amount, amount)?, target)
     }
+    Kind::ComeOdds => {
+      let target = tokens.expect(Expected::Target, target)?;
+      Bet::ComeOdds(tokens.expect(Expected::Amount, amount)?, target)
+    }
+    Kind::Put => {
+      let target = tokens.expect(Expected::Target, target)?;
+      Bet::put_pass(tokens.expect(Expected::Amount, amount)?, target)
+    }
+    Kind::PutCome => {
+      let target = tokens.expect(Expected::Target, target)?;
+      Bet::put_come(tokens.expect(Expected::Amount, amount)?, target)
+    }
+    Kind::Hardway => {
+      let way = tokens.expect(Expected::Hardway, hardway)?;
+      Bet::Hardway(tokens.expect(Expected::Amount, amount)?, way)
+    }
+  };
+
+  log::debug!("parsed bet - {:?}", bet);
+  Ok(bet)
+}
 
-    ["bet", "pass", value] => {
-      log::debug!("parsing pass line bet - {}", value);
+fn parse_take_down(tokens: &mut Tokens) -> Result<TakeDown, ParseError> {
+  tokens.expect(Expected::Keyword("down"), |token| {
+    Some(()).filter(|_| token.eq_ignore_ascii_case("down"))
+  })?;
 
-      u32::from_str(value)
-        .map_err(log_pass)
-        .ok()
-        .map(|amount| Bet::start_pass(amount))
-        .map(Action::Bet)
+  let (position, token) = match tokens.next() {
+    Some(next) => next,
+    None => {
+      return Err(ParseError {
+        expected: Expected::TakeDown,
+        position: tokens.input.len(),
+        found: None,
+      })
     }
+  };
 
+  let removal = match (token.eq_ignore_ascii_case("all"), kind(token)) {
+    (true, _) => TakeDown::All,
+    (_, Some(Kind::Pass)) => TakeDown::Pass,
+    (_, Some(Kind::Come)) => TakeDown::Come,
+    (_, Some(Kind::Field)) => TakeDown::Field,
+    (_, Some(Kind::PassOdds)) => TakeDown::PassOdds,
+    (_, Some(Kind::Place)) => TakeDown::Place(tokens.expect(Expected::Target, target)?),
+    (_, Some(Kind::ComeOdds)) => TakeDown::ComeOdds(tokens.expect(Expected::Target, target)?),
+    (_, Some(Kind::Hardway)) => TakeDown::Hardway(tokens.expect(Expected::Hardway, hardway)?),
     _ => {
-      log::debug!("unrecognized bet - {:?}", parts);
-      None
+      return Err(ParseError {
+        expected: Expected::TakeDown,
+        position,
+        found: Some(token.to_string()),
+      })
     }
-  }
+  };
+
+  Ok(removal)
+}
+
+fn name(token: &str) -> Option<String> {
+  let valid = token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  Some(token.to_ascii_lowercase()).filter(|_| valid)
+}
+
+fn path(token: &str) -> Option<String> {
+  Some(token.to_string())
+}
+
+fn parse_action(tokens: &mut Tokens, nested: bool) -> Result<Action, ParseError> {
+  let (position, token) = match tokens.next() {
+    Some(next) => next,
+    None if !nested => return Ok(Action::Roll),
+    None => {
+      return Err(ParseError {
+        expected: Expected::Command,
+        position: tokens.input.len(),
+        found: None,
+      })
+    }
+  };
+
+  let action = match token.to_ascii_lowercase().as_str() {
+    "roll" | "r" => Action::Roll,
+    "exit" | "quit" | "q" => Action::Exit,
+    "bet" | "b" => Action::Bet(parse_bet(tokens)?),
+    "take" => Action::TakeDown(parse_take_down(tokens)?),
+    "help" | "h" | "?" => Action::Help,
+    "balance" | "bal" => Action::Balance,
+    "bets" => Action::Bets,
+    "history" | "hist" => Action::History,
+    "stand" => Action::Stand,
+    "sit" => Action::Sit,
+    "undo" | "u" => Action::Undo,
+    "join" => Action::Join(tokens.expect(Expected::Name, name)?),
+    "switch" | "sw" => Action::Switch(tokens.expect(Expected::Name, name)?),
+    "players" => Action::Players,
+    "save" => Action::Save(tokens.expect(Expected::Path, path)?),
+    "load" => Action::Load(tokens.expect(Expected::Path, path)?),
+    "as" if !nested => {
+      let player = tokens.expect(Expected::Name, name)?;
+      Action::As(player, Box::new(parse_action(tokens, true)?))
+    }
+    _ => {
+      return Err(ParseError {
+        expected: Expected::Command,
+        position,
+        found: Some(token.to_string()),
+      })
+    }
+  };
+
+  Ok(action)
 }
 
 impl Action {
-  pub fn parse<T>(input: T) -> Option<Self>
+  pub fn parse<T>(input: T) -> Result<Self, ParseError>
   where
     T: std::fmt::Display,
   {
     let nice = format!("{}", input);
+    let mut tokens = Tokens::new(&nice);
+    let action = parse_action(&mut tokens, false)?;
+    tokens.finish()?;
+    Ok(action)
+  }
+}
+
+impl FromStr for Action {
+  type Err = ParseError;
+
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    Action::parse(input)
+  }
+}
+
+// The canonical text of an action is always something `Action::parse` will accept. Pass line odds are printed without
+// their target, which is resolved against the table's button when the bet is made.
+impl std::fmt::Display for Action {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let bet = match self {
+      Action::Exit => return write!(formatter, "exit"),
+      Action::Roll => return write!(formatter, "roll"),
+      Action::TakeDown(removal) => return write!(formatter, "take down {}", removal),
+      Action::Help => return write!(formatter, "help"),
+      Action::Balance => return write!(formatter, "balance"),
+      Action::Bets => return write!(formatter, "bets"),
+      Action::History => return write!(formatter, "history"),
+      Action::Stand => return write!(formatter, "stand"),
+      Action::Sit => return write!(formatter, "sit"),
+      Action::Undo => return write!(formatter, "undo"),
+      Action::Join(player) => return write!(formatter, "join {}", player),
+      Action::Switch(player) => return write!(formatter, "switch {}", player),
+      Action::Players => return write!(formatter, "players"),
+      Action::Save(path) => return write!(formatter, "save {}", path),
+      Action::Load(path) => return write!(formatter, "load {}", path),
+      Action::As(player, action) => return write!(formatter, "as {} {}", player, action),
+      Action::Bet(bet) => bet,
+    };
+
+    match bet {
+      Bet::Pass(_) => match bet.pass_target() {
+        Some(target) => write!(formatter, "bet put {} {}", target, bet.weight()),
+        None => write!(formatter, "bet pass {}", bet.weight()),
+      },
+      Bet::Come(_) => match bet.come_target() {
+        Some(target) => write!(formatter, "bet put-come {} {}", target, bet.weight()),
+        None => write!(formatter, "bet come {}", bet.weight()),
+      },
+      Bet::PassOdds(amount, _) => write!(formatter, "bet pass-odds {}", amount),
+      Bet::ComeOdds(amount, target) => write!(formatter, "bet come-odds {} {}", target, amount),
+      Bet::Place(amount, target) => write!(formatter, "bet place {} {}", target, amount),
+      Bet::Field(amount) => write!(formatter, "bet field {}", amount),
+      Bet::Hardway(amount, way) => write!(formatter, "bet hardway {} {}", u8::from(way), amount),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Action, Expected, ParseError, TakeDown};
+  use crate::bets::Bet;
+  use crate::roll::Hardway;
 
-    match nice.as_str() {
-      "" => Some(Action::Roll),
-      "exit" => Some(Action::Exit),
-      "roll" => Some(Action::Roll),
+  #[test]
+  fn test_commands() {
+    assert_eq!(Action::parse(""), Ok(Action::Roll));
+    assert_eq!(Action::parse("  r "), Ok(Action::Roll));
+    assert_eq!(Action::parse("QUIT"), Ok(Action::Exit));
+    assert_eq!("exit".parse::<Action>(), Ok(Action::Exit));
+  }
+
+  #[test]
+  fn test_aliases() {
+    assert_eq!(Action::parse("b p $10"), Ok(Action::Bet(Bet::start_pass(10))));
+    assert_eq!(
+      Action::parse("bet hard six $5"),
+      Ok(Action::Bet(Bet::Hardway(5, Hardway::Six)))
+    );
+    assert_eq!(
+      Action::parse("bet hw 10 5"),
+      Ok(Action::Bet(Bet::Hardway(5, Hardway::Ten)))
+    );
+    assert_eq!(Action::parse("bet pl yo 5"), Ok(Action::Bet(Bet::Place(5, 11))));
+    assert_eq!(Action::parse("bet co nine 20"), Ok(Action::Bet(Bet::ComeOdds(20, 9))));
+    assert_eq!(Action::parse("bet pc 4 10"), Ok(Action::Bet(Bet::put_come(10, 4))));
+  }
+
+  #[test]
+  fn test_come_odds_target() {
+    let expected = ParseError {
+      expected: Expected::Target,
+      position: 14,
+      found: Some("7".into()),
+    };
+    assert_eq!(Action::parse("bet come-odds 7 10"), Err(expected));
+  }
+
+  #[test]
+  fn test_missing_amount() {
+    let expected = ParseError {
+      expected: Expected::Amount,
+      position: 8,
+      found: None,
+    };
+    assert_eq!(Action::parse("bet pass"), Err(expected));
+  }
+
+  #[test]
+  fn test_bad_amount() {
+    let error = Action::parse("bet field ten").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Amount);
+    assert_eq!(error.position, 10);
+    assert_eq!(format!("{}", error), "expected an amount at position 10, found 'ten'");
+  }
+
+  #[test]
+  fn test_unknown_command() {
+    let error = Action::parse("hello").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Command);
+    assert_eq!(error.position, 0);
+  }
+
+  #[test]
+  fn test_trailing_input() {
+    let error = Action::parse("bet pass 10 please").expect_err("should fail");
+    assert_eq!(error.expected, Expected::End);
+    assert_eq!(error.position, 12);
+  }
+
+  #[test]
+  fn test_hardway_target() {
+    let error = Action::parse("bet hardway five 10").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Hardway);
+  }
+
+  #[test]
+  fn test_take_down() {
+    assert_eq!(
+      Action::parse("take down place six"),
+      Ok(Action::TakeDown(TakeDown::Place(6)))
+    );
+    let error = Action::parse("take it").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Keyword("down"));
+    assert_eq!(error.position, 5);
+    let error = Action::parse("take down put").expect_err("should fail");
+    assert_eq!(error.expected, Expected::TakeDown);
+  }
+
+  #[test]
+  fn test_as_player() {
+    let expected = Action::As("bob".into(), Box::new(Action::Bet(Bet::start_pass(10))));
+    assert_eq!(Action::parse("as Bob bet pass 10"), Ok(expected));
+    let error = Action::parse("as bob").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Command);
+    let error = Action::parse("as bob as alice roll").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Command);
+    assert_eq!(error.position, 7);
+    let error = Action::parse("join b@b").expect_err("should fail");
+    assert_eq!(error.expected, Expected::Name);
+  }
+
+  #[test]
+  fn test_take_down_matches() {
+    assert!(TakeDown::Place(6).matches(&Bet::Place(10, 6)));
+    assert!(!TakeDown::Place(6).matches(&Bet::Place(10, 8)));
+    assert!(TakeDown::All.matches(&Bet::Field(10)));
+  }
 
-      bet if bet.starts_with("bet") => {
-        let parts = bet.split(" ").collect::<Vec<&str>>();
-        parse_bet_line(&parts)
-      }
+  #[test]
+  fn test_display_round_trip() {
+    let actions = vec![
+      Action::Exit,
+      Action::Roll,
+      Action::Bet(Bet::start_pass(10)),
+      Action::Bet(Bet::start_come(10)),
+      Action::Bet(Bet::put_pass(10, 6)),
+      Action::Bet(Bet::put_come(10, 12)),
+      Action::Bet(Bet::PassOdds(20, 0)),
+      Action::Bet(Bet::ComeOdds(20, 5)),
+      Action::Bet(Bet::Place(12, 8)),
+      Action::Bet(Bet::Field(5)),
+      Action::Bet(Bet::Hardway(5, Hardway::Four)),
+      Action::TakeDown(TakeDown::All),
+      Action::TakeDown(TakeDown::Pass),
+      Action::TakeDown(TakeDown::Come),
+      Action::TakeDown(TakeDown::Field),
+      Action::TakeDown(TakeDown::PassOdds),
+      Action::TakeDown(TakeDown::Place(6)),
+      Action::TakeDown(TakeDown::ComeOdds(9)),
+      Action::TakeDown(TakeDown::Hardway(Hardway::Ten)),
+      Action::Help,
+      Action::Balance,
+      Action::Bets,
+      Action::History,
+      Action::Stand,
+      Action::Sit,
+      Action::Undo,
+      Action::Join("alice".into()),
+      Action::Switch("bob".into()),
+      Action::Players,
+      Action::Save("games/Tricky.json".into()),
+      Action::Load("games/Tricky.json".into()),
+      Action::As("carol".into(), Box::new(Action::Bet(Bet::Field(5)))),
+    ];
 
-      _ => None,
+    for action in actions {
+      assert_eq!(Action::parse(format!("{}", action)), Ok(action));
     }
   }
 }
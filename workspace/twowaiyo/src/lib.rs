@@ -1,16 +1,30 @@
 mod bets;
 mod checks;
 mod constants;
+mod field;
+mod floor;
+mod hand;
 mod player;
 mod roll;
 mod rollers;
+mod rules;
 mod seat;
+mod stats;
 mod table;
+mod variant;
 
 pub mod errors;
 pub mod io;
 
 pub use bets::Bet;
+pub use field::FieldPaytable;
+pub use floor::Floor;
+pub use hand::Hand;
 pub use player::Player;
 pub use roll::{Hardway, Roll};
+pub use rollers::RandomRoller;
+pub use rules::Rules;
+pub use seat::SeatRuns;
+pub use stats::DiceStats;
 pub use table::Table;
+pub use variant::Variant;
@@ -3,9 +3,11 @@ use uuid;
 use bankah::state::PlayerState;
 
 #[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
   pub id: String,
   pub balance: u32,
+  pub nickname: String,
 }
 
 impl From<&Player> for PlayerState {
@@ -15,7 +17,8 @@ impl From<&Player> for PlayerState {
       balance: state.balance,
       emails: vec![],
       tables: vec![],
-      nickname: String::default(),
+      history: vec![],
+      nickname: state.nickname.clone(),
       oid: String::default(),
     }
   }
@@ -26,6 +29,7 @@ impl From<&PlayerState> for Player {
     Player {
       id: state.id.clone(),
       balance: state.balance,
+      nickname: state.nickname.clone(),
     }
   }
 }
@@ -50,6 +54,7 @@ impl Default for Player {
     Player {
       id: uuid::Uuid::new_v4().to_string(),
       balance: 10000,
+      nickname: String::default(),
     }
   }
 }
@@ -1,9 +1,11 @@
 use std::convert::TryFrom;
 use std::iter::FromIterator;
 
-use super::checks::is_place;
+use super::errors::ConversionError;
+use super::variant::Variant;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Hardway {
   Four,
   Six,
@@ -12,7 +14,7 @@ pub enum Hardway {
 }
 
 impl TryFrom<u8> for Hardway {
-  type Error = std::io::Error;
+  type Error = ConversionError;
 
   fn try_from(target: u8) -> Result<Hardway, Self::Error> {
     match target {
@@ -20,22 +22,7 @@ impl TryFrom<u8> for Hardway {
       6 => Ok(Hardway::Six),
       8 => Ok(Hardway::Eight),
       10 => Ok(Hardway::Ten),
-      other => Err(std::io::Error::new(
-        std::io::ErrorKind::Other,
-        format!("'{}' is not a hardway", other),
-      )),
-    }
-  }
-}
-
-impl From<&u8> for Hardway {
-  fn from(way: &u8) -> Hardway {
-    match way {
-      4 => Hardway::Four,
-      6 => Hardway::Six,
-      8 => Hardway::Eight,
-      10 => Hardway::Ten,
-      _ => unreachable!(),
+      other => Err(ConversionError::InvalidHardway(other)),
     }
   }
 }
@@ -52,8 +39,21 @@ impl From<&Hardway> for u8 {
 }
 
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "(u8, u8)"))]
 pub struct Roll(u8, u8);
 
+impl TryFrom<(u8, u8)> for Roll {
+  type Error = ConversionError;
+
+  fn try_from(faces: (u8, u8)) -> Result<Roll, Self::Error> {
+    match faces {
+      (1..=6, 1..=6) => Ok(Roll(faces.0, faces.1)),
+      (1..=6, face) | (face, _) => Err(ConversionError::InvalidDie(face)),
+    }
+  }
+}
+
 impl<U> FromIterator<U> for Roll
 where
   U: Into<u8>,
@@ -114,9 +114,9 @@ impl Roll {
     }
   }
 
-  pub fn result(&self, button: &Option<u8>) -> RollResult {
+  pub fn result(&self, button: &Option<u8>, variant: Variant) -> RollResult {
     match (button, self.total()) {
-      (None, target) if is_place(target) => RollResult::Button(self.total()),
+      (None, target) if variant.is_point(target) => RollResult::Button(self.total()),
       (Some(target), value) if value == *target => RollResult::Hit,
       (Some(_), 7) => RollResult::Craps,
       (None, 2) | (None, 12) | (None, 3) => RollResult::Craps,
@@ -150,7 +150,25 @@ impl RollResult {
 
 #[cfg(test)]
 mod test {
-  use super::{Hardway, Roll};
+  use super::{Hardway, Roll, RollResult};
+  use crate::variant::Variant;
+
+  #[test]
+  fn crapless_come_out_points() {
+    for (left, right) in [(1u8, 1u8), (1, 2), (5, 6), (6, 6)] {
+      let roll = vec![left, right].into_iter().collect::<Roll>();
+      let result = roll.result(&None, Variant::Crapless);
+      assert_eq!(result.button(None), Some(left + right));
+    }
+  }
+
+  #[test]
+  fn standard_come_out_craps() {
+    let roll = vec![1u8, 1u8].into_iter().collect::<Roll>();
+    assert!(matches!(roll.result(&None, Variant::Standard), RollResult::Craps));
+    let roll = vec![5u8, 6u8].into_iter().collect::<Roll>();
+    assert_eq!(roll.result(&None, Variant::Standard).button(None), None);
+  }
 
   #[test]
   fn easyway_four() {
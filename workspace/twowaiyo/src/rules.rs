@@ -0,0 +1,12 @@
+use super::field::FieldPaytable;
+use super::variant::Variant;
+
+// The rules of a table are passed into the settlement of every bet on every roll. When set, `max_odds` limits the odds
+// taken behind a line bet to that multiple of the flat amount.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rules {
+  pub variant: Variant,
+  pub field: FieldPaytable,
+  pub max_odds: Option<u32>,
+}
@@ -1,7 +1,11 @@
+use std::convert::TryFrom;
+use std::time::SystemTime;
+
 use super::{
   bets::{Bet, BetResult},
-  errors::{CarryError, PlayerBetViolation, RuleViolation},
+  errors::{CarryError, ConversionError, PlayerBetViolation, RuleViolation},
   roll::Roll,
+  rules::Rules,
 };
 
 use bankah::state::SeatState;
@@ -25,6 +29,16 @@ impl Iterator for &mut SeatRuns {
 }
 
 impl SeatRuns {
+  pub fn clear(&mut self) {
+    self.wins.clear();
+    self.losses.clear();
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Bet, bool, u32)> {
+    let wins = self.wins.iter().map(|(bet, amount)| (bet, true, *amount));
+    wins.chain(self.losses.iter().map(|(bet, amount)| (bet, false, *amount)))
+  }
+
   pub fn losses(&self) -> u32 {
     self.losses.iter().fold(0, |acc, item| acc + item.1)
   }
@@ -34,31 +48,83 @@ impl SeatRuns {
   }
 }
 
-#[derive(Clone, Default, PartialEq)]
+// Along with the bets and balance used to play, a seat keeps who is sitting in it, when they sat down, and the bets it
+// has already settled; the engine doesn't use these itself but carries them so a seat survives a round trip.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Seat {
   bets: Vec<Bet>,
   balance: u32,
+  nickname: String,
+  history: Vec<(Bet, bool, u32)>,
+  seated_at: SystemTime,
+  orphaned: bool,
 }
 
-impl From<&SeatState> for Seat {
-  fn from(seat: &SeatState) -> Seat {
-    let bets = seat.bets.iter().map(|b| b.into()).collect();
-
+impl Default for Seat {
+  fn default() -> Self {
     Seat {
+      bets: Vec::new(),
+      balance: 0,
+      nickname: String::default(),
+      history: Vec::new(),
+      seated_at: SystemTime::now(),
+      orphaned: false,
+    }
+  }
+}
+
+// Two seats are the same when they are in the same state of play, regardless of when each was sat in.
+impl PartialEq for Seat {
+  fn eq(&self, other: &Self) -> bool {
+    self.bets == other.bets
+      && self.balance == other.balance
+      && self.nickname == other.nickname
+      && self.history == other.history
+      && self.orphaned == other.orphaned
+  }
+}
+
+impl TryFrom<&SeatState> for Seat {
+  type Error = ConversionError;
+
+  fn try_from(seat: &SeatState) -> Result<Seat, Self::Error> {
+    let bets = seat
+      .bets
+      .iter()
+      .map(Bet::try_from)
+      .collect::<Result<Vec<Bet>, ConversionError>>()?;
+
+    let history = seat
+      .history
+      .iter()
+      .map(|(bet, won, amount)| Bet::try_from(bet).map(|bet| (bet, *won, *amount)))
+      .collect::<Result<Vec<(Bet, bool, u32)>, ConversionError>>()?;
+
+    Ok(Seat {
       bets,
       balance: seat.balance,
-    }
+      nickname: seat.nickname.clone(),
+      history,
+      seated_at: seat.seated_at.into(),
+      orphaned: seat.orphaned,
+    })
   }
 }
 
 impl From<&Seat> for SeatState {
   fn from(seat: &Seat) -> SeatState {
-    let def = SeatState::default();
-
     SeatState {
       balance: seat.balance,
+      nickname: seat.nickname.clone(),
+      history: seat
+        .history
+        .iter()
+        .map(|(bet, won, amount)| (bet.into(), *won, *amount))
+        .collect(),
+      seated_at: seat.seated_at.into(),
+      orphaned: seat.orphaned,
       bets: seat.bets.iter().map(|b| b.into()).collect(),
-      ..def
     }
   }
 }
@@ -84,9 +150,95 @@ impl Seat {
     }
   }
 
-  pub fn stand(self) -> (u32, Option<Self>) {
-    let Seat { bets, balance } = self;
-    let start = (balance, Vec::with_capacity(bets.len()));
+  pub fn with_nickname<S>(self, nickname: S) -> Self
+  where
+    S: Into<String>,
+  {
+    Seat {
+      nickname: nickname.into(),
+      ..self
+    }
+  }
+
+  // Chips bought at the table go straight into the seat's balance. Buying back into a seat that was left behind
+  // makes it the player's own again.
+  pub fn rebuy(self, amount: u32) -> Self {
+    Seat {
+      balance: self.balance + amount,
+      orphaned: false,
+      ..self
+    }
+  }
+
+  // Colors up part of the seat's balance; whatever is riding on bets stays on the table.
+  pub fn cash_out(self, amount: u32) -> Result<Self, CarryError<Self>> {
+    if amount > self.balance {
+      let violation = RuleViolation::PlayerBetViolation(PlayerBetViolation::InsufficientFunds);
+      return Err(CarryError::new(self, violation));
+    }
+
+    Ok(Seat {
+      balance: self.balance - amount,
+      ..self
+    })
+  }
+
+  pub fn balance(&self) -> u32 {
+    self.balance
+  }
+
+  pub fn nickname(&self) -> &str {
+    &self.nickname
+  }
+
+  pub fn history(&self) -> &[(Bet, bool, u32)] {
+    &self.history
+  }
+
+  pub fn seated_at(&self) -> SystemTime {
+    self.seated_at
+  }
+
+  // A seat is orphaned when its player stands up while bets that can't be taken down are still working.
+  pub fn is_orphaned(&self) -> bool {
+    self.orphaned
+  }
+
+  // Takes the whole balance out of the seat.
+  pub(crate) fn collect(&mut self) -> u32 {
+    std::mem::take(&mut self.balance)
+  }
+
+  pub fn bets(&self) -> &[Bet] {
+    &self.bets
+  }
+
+  // Pulls every bet matching the filter back into the seat's balance. Bets that can't be taken down (line bets with a
+  // point established) are left working.
+  pub fn take_down<F>(mut self, filter: F) -> Self
+  where
+    F: Fn(&Bet) -> bool,
+  {
+    let mut balance = self.balance;
+    let bets = std::mem::take(&mut self.bets)
+      .into_iter()
+      .filter_map(|bet| {
+        if !filter(&bet) {
+          return Some(bet);
+        }
+
+        let (amount, remaining) = bet.pull();
+        balance += amount;
+        remaining
+      })
+      .collect();
+
+    Seat { bets, balance, ..self }
+  }
+
+  pub fn stand(mut self) -> (u32, Option<Self>) {
+    let bets = std::mem::take(&mut self.bets);
+    let start = (self.balance, Vec::with_capacity(bets.len()));
     let (balance, bets) = bets.into_iter().fold(start, |(balance, bets), bet| {
       let (amt, rem) = bet.pull();
       let bets = bets.into_iter().chain(rem).collect();
@@ -97,54 +249,128 @@ impl Seat {
       return (balance, None);
     }
 
-    (balance, Some(Seat { bets, balance: 0 }))
-  }
-
-  pub fn roll(self, roll: &Roll) -> (Self, SeatRuns) {
-    let Seat { bets, balance } = self;
-    let start: (Vec<Bet>, _) = (vec![], SeatRuns::default());
-
-    let (stays, runs) = bets.into_iter().fold(start, |(stays, runs), item| {
-      let result = item.result(&roll);
-      log::info!("{:<25} -> {:<25}", format!("{:?}", item), format!("{:?}", result));
-      let runs = match &result {
-        BetResult::Win(amount) => SeatRuns {
-          losses: runs.losses,
-          wins: runs.wins.into_iter().chain(Some((item, amount + 0))).collect(),
-        },
-        BetResult::Loss(amount) => SeatRuns {
-          wins: runs.wins,
-          losses: runs.losses.into_iter().chain(Some((item, amount + 0))).collect(),
-        },
-        BetResult::Noop(_) => runs,
-      };
-
-      (stays.into_iter().chain(result.remaining()).collect(), runs)
-    });
+    (
+      balance,
+      Some(Seat {
+        bets,
+        balance: 0,
+        orphaned: true,
+        ..self
+      }),
+    )
+  }
 
-    let next = Seat {
-      balance: balance + runs.winnings(),
-      bets: stays,
-    };
+  pub fn roll(mut self, roll: &Roll, rules: &Rules) -> (Self, SeatRuns) {
+    let mut runs = SeatRuns::default();
+    self.settle(roll, rules, &mut runs);
+    (self, runs)
+  }
 
-    return (next, runs);
+  // Settles every bet against the roll in place, compacting the bets that remain on the table to the front of the
+  // existing buffer. The runs are cleared before use so callers can hold onto them between rolls.
+  pub fn settle(&mut self, roll: &Roll, rules: &Rules, runs: &mut SeatRuns) {
+    runs.clear();
+    let mut kept = 0;
+
+    for index in 0..self.bets.len() {
+      let result = self.bets[index].settle(roll, rules);
+      log::info!(
+        "{:<25} -> {:<25}",
+        format!("{:?}", self.bets[index]),
+        format!("{:?}", result)
+      );
+
+      match result {
+        BetResult::Win(amount) => {
+          self.balance += amount;
+          runs.wins.push((self.bets[index].clone(), amount));
+        }
+        BetResult::Loss(amount) => runs.losses.push((self.bets[index].clone(), amount)),
+        BetResult::Noop(remaining) => {
+          self.bets[kept] = remaining;
+          kept += 1;
+        }
+      }
+    }
+
+    self.bets.truncate(kept);
   }
 
-  pub fn bet(self, bet: &Bet) -> Result<Self, CarryError<Self>> {
-    self
-      .normalize_bet(bet)
-      .map(|bet| {
-        let balance = self.balance - bet.weight();
-        let bets = self.bets.iter().chain(Some(&bet)).map(|b| b.clone()).collect();
-        Seat { balance, bets }
-      })
-      .map_err(|error| {
-        let seat = Seat { ..self };
-        CarryError::new(seat, RuleViolation::PlayerBetViolation(error))
+  pub fn bet(mut self, bet: &Bet, rules: &Rules) -> Result<Self, CarryError<Self>> {
+    match self.normalize_bet(bet, rules) {
+      Ok(bet) => {
+        self.balance -= bet.weight();
+        self.bets.push(bet);
+        Ok(self)
+      }
+      Err(error) => Err(CarryError::new(self, RuleViolation::PlayerBetViolation(error))),
+    }
+  }
+
+  // Put bets are made directly on a point, optionally with odds behind them. If the odds can't be taken, the put bet
+  // itself is taken back down so the seat is left as it was found.
+  pub fn put(self, bet: &Bet, odds: u32, rules: &Rules) -> Result<Self, CarryError<Self>> {
+    let behind = match bet {
+      Bet::Pass(_) => bet.pass_target().map(|target| Bet::PassOdds(odds, target)),
+      Bet::Come(_) => bet.come_target().map(|target| Bet::ComeOdds(odds, target)),
+      _ => None,
+    };
+
+    let behind = match (behind, bet) {
+      (Some(behind), _) => behind,
+      (None, Bet::Pass(_)) => {
+        return Err(CarryError::new(
+          self,
+          RuleViolation::PlayerBetViolation(PlayerBetViolation::MissingPassForOdds),
+        ))
+      }
+      (None, _) => {
+        return Err(CarryError::new(
+          self,
+          RuleViolation::PlayerBetViolation(PlayerBetViolation::MissingComeForOdds),
+        ))
+      }
+    };
+
+    let seat = self.bet(bet, rules)?;
+
+    seat.bet(&behind, rules).map_err(|error| {
+      error.map(|mut seat| {
+        if let Some(put) = seat.bets.pop() {
+          seat.balance += put.weight();
+        }
+        seat
       })
+    })
+  }
+
+  // The amount of odds a player may take behind a line bet is limited to a multiple of the flat amount on that same
+  // number, when the table has a limit.
+  fn check_odds(&self, target: u8, amount: u32, rules: &Rules, come: bool) -> Result<(), PlayerBetViolation> {
+    let limit = match rules.max_odds {
+      Some(limit) => limit,
+      None => return Ok(()),
+    };
+
+    let (flat, taken) = self
+      .bets
+      .iter()
+      .fold((0u32, 0u32), |(flat, taken), bet| match (bet, come) {
+        (Bet::Pass(_), false) if bet.pass_target() == Some(target) => (flat + bet.weight(), taken),
+        (Bet::PassOdds(odds, _), false) => (flat, taken + odds),
+        (Bet::Come(_), true) if bet.come_target() == Some(target) => (flat + bet.weight(), taken),
+        (Bet::ComeOdds(odds, on), true) if *on == target => (flat, taken + odds),
+        _ => (flat, taken),
+      });
+
+    if taken + amount > flat * limit {
+      return Err(PlayerBetViolation::OddsLimitExceeded);
+    }
+
+    Ok(())
   }
 
-  fn normalize_bet(&self, bet: &Bet) -> Result<Bet, PlayerBetViolation> {
+  fn normalize_bet(&self, bet: &Bet, rules: &Rules) -> Result<Bet, PlayerBetViolation> {
     let weight = bet.weight();
 
     if weight > self.balance {
@@ -154,26 +380,30 @@ impl Seat {
     match bet {
       Bet::PassOdds(amount, _) => {
         log::debug!("pass odds received, checking match");
-        self
+        let target = self
           .bets
           .iter()
           .find_map(|b| b.pass_target())
-          .map(|target| Bet::PassOdds(*amount, target))
-          .ok_or(PlayerBetViolation::MissingPassForOdds)
+          .ok_or(PlayerBetViolation::MissingPassForOdds)?;
+
+        self.check_odds(target, *amount, rules, false)?;
+        Ok(Bet::PassOdds(*amount, target))
       }
 
       Bet::ComeOdds(amount, target) => {
         log::debug!("pass odds received, checking match");
 
-        self
+        let target = self
           .bets
           .iter()
           .find_map(|b| {
             b.come_target()
               .and_then(|inner| if inner == *target { Some(target) } else { None })
           })
-          .map(|target| Bet::ComeOdds(*amount, *target))
-          .ok_or(PlayerBetViolation::MissingComeForOdds)
+          .ok_or(PlayerBetViolation::MissingComeForOdds)?;
+
+        self.check_odds(*target, *amount, rules, true)?;
+        Ok(Bet::ComeOdds(*amount, *target))
       }
 
       _ => Ok(bet.clone()),
@@ -185,57 +415,150 @@ impl Seat {
 mod test {
   use super::{Seat, SeatRuns};
   use crate::bets::Bet;
+  use crate::errors::{PlayerBetViolation, RuleViolation};
+  use crate::rules::Rules;
+  use bankah::state::{BetState, SeatState};
+  use std::convert::TryFrom;
+
+  #[test]
+  fn rebuy_and_cash_out() {
+    let seat = Seat::with_balance(100).rebuy(50);
+    assert_eq!(seat.balance(), 150);
+    let seat = seat.cash_out(120).expect("cash out");
+    assert_eq!(seat.balance(), 30);
+    let error = seat.cash_out(31).unwrap_err();
+    assert_eq!(
+      error.error,
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::InsufficientFunds)
+    );
+    assert_eq!(error.consume().balance(), 30);
+  }
+
+  #[test]
+  fn state_round_trip() {
+    let state = SeatState {
+      balance: 90,
+      nickname: "shooter".into(),
+      history: vec![(BetState::Field(10), true, 20)],
+      bets: vec![BetState::Field(10)],
+      ..SeatState::default()
+    };
+    let seat = Seat::try_from(&state).expect("valid seat");
+    assert_eq!(seat.nickname(), "shooter");
+    assert_eq!(seat.history(), &[(Bet::Field(10), true, 20)][..]);
+
+    let after = SeatState::from(&seat);
+    assert_eq!(after.nickname, state.nickname);
+    assert_eq!(after.history.len(), 1);
+    assert_eq!(after.seated_at, state.seated_at);
+  }
 
   #[test]
   fn run_with_winners() {
     let seat = Seat::with_balance(100);
-    let seat = seat.bet(&Bet::start_pass(10)).expect("");
+    let seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
     let roll = vec![2u8, 5u8].into_iter().collect();
     let expected = SeatRuns {
       wins: vec![(Bet::start_pass(10), 20)],
       losses: vec![],
     };
-    assert_eq!(seat.roll(&roll), (Seat::with_balance(110), expected));
+    assert_eq!(seat.roll(&roll, &Rules::default()), (Seat::with_balance(110), expected));
+  }
+
+  #[test]
+  fn settle_reuses_runs() {
+    let mut seat = Seat::with_balance(100);
+    seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
+    seat = seat.bet(&Bet::start_come(10), &Rules::default()).expect("");
+    let mut runs = SeatRuns {
+      wins: vec![(Bet::start_pass(50), 100)],
+      losses: vec![],
+    };
+    let roll = vec![2u8, 5u8].into_iter().collect();
+    seat.settle(&roll, &Rules::default(), &mut runs);
+    let expected = SeatRuns {
+      wins: vec![(Bet::start_pass(10), 20), (Bet::start_come(10), 20)],
+      losses: vec![],
+    };
+    assert_eq!(runs, expected);
+    assert_eq!(seat, Seat::with_balance(120));
+  }
+
+  #[test]
+  fn settle_keeps_remaining_bets_in_order() {
+    let mut seat = Seat::with_balance(100);
+    seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
+    seat = seat.bet(&Bet::start_come(10), &Rules::default()).expect("");
+    let mut runs = SeatRuns::default();
+    let roll = vec![2u8, 2u8].into_iter().collect();
+    seat.settle(&roll, &Rules::default(), &mut runs);
+    assert_eq!(runs, SeatRuns::default());
+    assert_eq!(seat.bets, vec![Bet::put_pass(10, 4), Bet::put_come(10, 4)]);
+  }
+
+  #[test]
+  fn take_down_matching() {
+    let seat = Seat::with_balance(100);
+    let seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
+    let seat = seat.bet(&Bet::Field(10), &Rules::default()).expect("");
+    let seat = seat.take_down(|bet| matches!(bet, Bet::Field(_)));
+    assert_eq!(seat.balance(), 90);
+    assert_eq!(seat.bets(), &[Bet::start_pass(10)]);
+  }
+
+  #[test]
+  fn take_down_contract_bet() {
+    let seat = Seat::with_balance(100);
+    let seat = seat.bet(&Bet::put_pass(10, 6), &Rules::default()).expect("");
+    let seat = seat.take_down(|_| true);
+    assert_eq!(seat.balance(), 90);
+    assert_eq!(seat.bets(), &[Bet::put_pass(10, 6)]);
   }
 
   #[test]
   fn run_with_losers() {
     let seat = Seat::with_balance(100);
-    let seat = seat.bet(&Bet::start_pass(10)).expect("");
+    let seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
     let roll = vec![2u8, 1u8].into_iter().collect();
     let expected = SeatRuns {
       wins: vec![],
       losses: vec![(Bet::start_pass(10), 10)],
     };
-    assert_eq!(seat.roll(&roll), (Seat::with_balance(90), expected));
+    assert_eq!(seat.roll(&roll, &Rules::default()), (Seat::with_balance(90), expected));
   }
 
   #[test]
   fn run_with_losers_after_pass() {
     let seat = Seat::with_balance(100);
-    let seat = seat.bet(&Bet::start_pass(10)).expect("");
+    let seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
     let roll = vec![2u8, 4u8].into_iter().collect();
-    let passed = seat.roll(&roll).0;
+    let passed = seat.roll(&roll, &Rules::default()).0;
     let crapped = vec![2u8, 5u8].into_iter().collect();
     let expected = SeatRuns {
       wins: vec![],
       losses: vec![(Bet::start_pass(10).result(&roll).remaining().unwrap(), 10)],
     };
-    assert_eq!(passed.roll(&crapped), (Seat::with_balance(90), expected));
+    assert_eq!(
+      passed.roll(&crapped, &Rules::default()),
+      (Seat::with_balance(90), expected)
+    );
   }
 
   #[test]
   fn run_with_winners_after_pass() {
     let seat = Seat::with_balance(100);
-    let seat = seat.bet(&Bet::start_pass(10)).expect("");
+    let seat = seat.bet(&Bet::start_pass(10), &Rules::default()).expect("");
     let roll = vec![2u8, 4u8].into_iter().collect();
-    let passed = seat.roll(&roll).0;
+    let passed = seat.roll(&roll, &Rules::default()).0;
     let hit = vec![2u8, 4u8].into_iter().collect();
     let expected = SeatRuns {
       losses: vec![],
       wins: vec![(Bet::start_pass(10).result(&roll).remaining().unwrap(), 20)],
     };
-    assert_eq!(passed.roll(&hit), (Seat::with_balance(110), expected));
+    assert_eq!(
+      passed.roll(&hit, &Rules::default()),
+      (Seat::with_balance(110), expected)
+    );
   }
 
   #[test]
@@ -247,19 +570,82 @@ mod test {
   #[test]
   fn stand_with_pass_off() {
     let seat = Seat::with_balance(100);
-    let seat = seat.bet(&Bet::start_pass(50)).expect("");
+    let seat = seat.bet(&Bet::start_pass(50), &Rules::default()).expect("");
     assert_eq!(seat.stand(), (100u32, None));
   }
 
   #[test]
   fn stand_with_pass_on() {
     let seat = Seat::with_balance(100);
-    let seat = seat.bet(&Bet::start_pass(50)).expect("");
+    let seat = seat.bet(&Bet::start_pass(50), &Rules::default()).expect("");
     let roll = vec![2u8, 4u8].into_iter().collect();
-    let seat = seat.roll(&roll).0;
+    let seat = seat.roll(&roll, &Rules::default()).0;
     let expected = Seat::with_balance(50)
-      .bet(&Bet::start_pass(50).result(&roll).remaining().unwrap())
+      .bet(
+        &Bet::start_pass(50).result(&roll).remaining().unwrap(),
+        &Rules::default(),
+      )
       .unwrap();
+    let expected = Seat {
+      orphaned: true,
+      ..expected
+    };
     assert_eq!(seat.stand(), (50u32, Some(expected)));
   }
+
+  fn limited(max_odds: u32) -> Rules {
+    Rules {
+      max_odds: Some(max_odds),
+      ..Rules::default()
+    }
+  }
+
+  #[test]
+  fn put_with_odds() {
+    let rules = limited(2);
+    let seat = Seat::with_balance(100)
+      .put(&Bet::put_pass(10, 6), 20, &rules)
+      .expect("");
+    let expected = Seat::with_balance(100)
+      .bet(&Bet::put_pass(10, 6), &rules)
+      .and_then(|seat| seat.bet(&Bet::PassOdds(20, 6), &rules))
+      .expect("");
+    assert_eq!(seat, expected);
+  }
+
+  #[test]
+  fn put_with_too_much_odds() {
+    let rules = limited(2);
+    let result = Seat::with_balance(100).put(&Bet::put_come(10, 8), 30, &rules);
+    let error = result.expect_err("");
+    assert_eq!(
+      error.error,
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::OddsLimitExceeded)
+    );
+    assert_eq!(error.consume(), Seat::with_balance(100));
+  }
+
+  #[test]
+  fn pass_odds_within_limit() {
+    let rules = limited(3);
+    let seat = Seat::with_balance(100)
+      .bet(&Bet::put_pass(10, 4), &rules)
+      .and_then(|seat| seat.bet(&Bet::PassOdds(20, 0), &rules))
+      .and_then(|seat| seat.bet(&Bet::PassOdds(10, 0), &rules))
+      .expect("");
+    let error = seat.bet(&Bet::PassOdds(10, 0), &rules).expect_err("");
+    assert_eq!(
+      error.error,
+      RuleViolation::PlayerBetViolation(PlayerBetViolation::OddsLimitExceeded)
+    );
+  }
+
+  #[test]
+  fn odds_without_limit() {
+    let rules = Rules::default();
+    let seat = Seat::with_balance(1000)
+      .bet(&Bet::put_pass(10, 4), &rules)
+      .and_then(|seat| seat.bet(&Bet::PassOdds(500, 0), &rules));
+    assert!(seat.is_ok());
+  }
 }
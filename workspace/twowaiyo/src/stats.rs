@@ -0,0 +1,174 @@
+use super::roll::Roll;
+
+use bankah::state::DiceStatsState;
+
+// The number of ways each total can be made with two dice, indexed by total.
+const WAYS: [u32; 13] = [0, 0, 1, 2, 3, 4, 5, 6, 5, 4, 3, 2, 1];
+
+// How many numbers are reported as hot, and as cold.
+const STREAK: usize = 3;
+
+// Counts of every total and every face thrown, built up one roll at a time in the order the rolls were thrown.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiceStats {
+  rolls: u32,
+  totals: [u32; 13],
+  faces: [u32; 7],
+  since_seven: u32,
+}
+
+impl DiceStats {
+  // Builds stats from rolls ordered newest first, the way a table keeps them.
+  pub fn from_recent<'a, I>(rolls: I) -> Self
+  where
+    I: IntoIterator<Item = &'a Roll>,
+    I::IntoIter: DoubleEndedIterator,
+  {
+    let mut stats = DiceStats::default();
+
+    for roll in rolls.into_iter().rev() {
+      stats.record(roll);
+    }
+
+    stats
+  }
+
+  // Counts a single roll. Rolls with faces that aren't on a die (e.g. read back from a damaged archive) are skipped
+  // rather than counted; returns whether or not the roll was counted.
+  pub fn record(&mut self, roll: &Roll) -> bool {
+    let faces = 1..=6;
+
+    if !faces.contains(&roll.left()) || !faces.contains(&roll.right()) {
+      return false;
+    }
+
+    self.rolls += 1;
+    self.totals[roll.total() as usize] += 1;
+    self.faces[roll.left() as usize] += 1;
+    self.faces[roll.right() as usize] += 1;
+
+    self.since_seven = match roll.total() {
+      7 => 0,
+      _ => self.since_seven + 1,
+    };
+
+    true
+  }
+
+  pub fn rolls(&self) -> u32 {
+    self.rolls
+  }
+
+  pub fn total(&self, total: u8) -> u32 {
+    self.totals.get(total as usize).copied().unwrap_or(0)
+  }
+
+  pub fn face(&self, face: u8) -> u32 {
+    self.faces.get(face as usize).copied().unwrap_or(0)
+  }
+
+  // The number of rolls thrown since the last seven, or since the first roll if there hasn't been one.
+  pub fn since_seven(&self) -> u32 {
+    self.since_seven
+  }
+
+  // Totals ordered by how often they have been thrown compared to how often they should have been, most often first.
+  // Comparing `count / ways` is done as `count * other_ways` to stay in integers.
+  fn ranked(&self) -> Vec<u8> {
+    let mut totals = (2..=12u8).collect::<Vec<u8>>();
+
+    totals.sort_by(|a, b| {
+      let (a, b) = (*a as usize, *b as usize);
+      (self.totals[b] * WAYS[a]).cmp(&(self.totals[a] * WAYS[b]))
+    });
+
+    totals
+  }
+
+  pub fn hot(&self) -> Vec<u8> {
+    match self.rolls {
+      0 => Vec::new(),
+      _ => self.ranked().into_iter().take(STREAK).collect(),
+    }
+  }
+
+  pub fn cold(&self) -> Vec<u8> {
+    match self.rolls {
+      0 => Vec::new(),
+      _ => self.ranked().into_iter().rev().take(STREAK).collect(),
+    }
+  }
+}
+
+impl From<&DiceStats> for DiceStatsState {
+  fn from(stats: &DiceStats) -> DiceStatsState {
+    DiceStatsState {
+      rolls: stats.rolls,
+      totals: (2..=12).map(|total| (total, stats.total(total))).collect(),
+      faces: (1..=6).map(|face| (face, stats.face(face))).collect(),
+      hot: stats.hot(),
+      cold: stats.cold(),
+      since_seven: stats.since_seven,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::DiceStats;
+  use crate::Roll;
+
+  fn rolls(faces: &[(u8, u8)]) -> Vec<Roll> {
+    faces
+      .iter()
+      .map(|(left, right)| vec![*left, *right].into_iter().collect())
+      .collect()
+  }
+
+  #[test]
+  fn test_counts() {
+    let mut stats = DiceStats::default();
+
+    for roll in rolls(&[(3, 3), (5, 2), (1, 5), (6, 6)]).iter() {
+      stats.record(roll);
+    }
+
+    assert_eq!(stats.rolls(), 4);
+    assert_eq!(stats.total(6), 2);
+    assert_eq!(stats.total(7), 1);
+    assert_eq!(stats.face(3), 2);
+    assert_eq!(stats.face(6), 2);
+    assert_eq!(stats.face(4), 0);
+    assert_eq!(stats.since_seven(), 2);
+  }
+
+  #[test]
+  fn test_invalid_faces_skipped() {
+    let mut stats = DiceStats::default();
+
+    for roll in rolls(&[(0, 3), (7, 2), (255, 255), (4, 3)]).iter() {
+      stats.record(roll);
+    }
+
+    assert_eq!(stats.rolls(), 1);
+    assert_eq!(stats.total(7), 1);
+    assert_eq!(stats.since_seven(), 0);
+  }
+
+  #[test]
+  fn test_from_recent() {
+    let newest_first = rolls(&[(2, 2), (4, 3), (1, 1)]);
+    let stats = DiceStats::from_recent(&newest_first);
+    assert_eq!(stats.since_seven(), 1);
+    assert_eq!(stats.rolls(), 3);
+  }
+
+  #[test]
+  fn test_hot_and_cold() {
+    let stats = DiceStats::from_recent(&rolls(&[(1, 1), (1, 1), (6, 6), (2, 1), (3, 4)]));
+    assert_eq!(stats.hot(), vec![2, 12, 3]);
+    assert_eq!(stats.cold().len(), 3);
+    assert!(!stats.cold().contains(&2));
+    assert!(DiceStats::default().hot().is_empty());
+  }
+}
@@ -1,14 +1,19 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use uuid;
 
 use super::bets::Bet;
 use super::errors;
+use super::field::FieldPaytable;
+use super::hand::Hand;
 use super::player::Player;
 use super::roll::Roll;
 use super::rollers::RandomRoller;
+use super::rules::Rules;
 use super::seat::{Seat, SeatRuns};
+use super::variant::Variant;
 
-use bankah::state::TableState;
+use bankah::state::{HandState, TableState};
 
 #[derive(Debug, Clone)]
 pub struct RunResult<R>
@@ -20,6 +25,11 @@ where
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(try_from = "UncheckedTable", bound(deserialize = "R: Default"))
+)]
 pub struct Table<R>
 where
   R: Clone + Iterator<Item = u8>,
@@ -29,12 +39,20 @@ where
   button: Option<u8>,
   seats: HashMap<String, Seat>,
   rolls: Vec<Roll>,
+  history: usize,
+  rules: Rules,
+  // Dice aren't part of a table's state; a deserialized table gets fresh ones.
+  #[cfg_attr(feature = "serde", serde(skip))]
   dice: R,
+  hand: Option<Hand>,
+  hands: Vec<Hand>,
+  payouts: HashMap<String, u32>,
 }
 
 impl Default for Table<RandomRoller> {
   fn default() -> Self {
-    let rolls = Vec::with_capacity(crate::constants::MAX_ROLL_HISTORY);
+    let history = crate::constants::DEFAULT_ROLL_HISTORY;
+    let rolls = Vec::with_capacity(history);
     let id = uuid::Uuid::new_v4().to_string();
     let seats = HashMap::with_capacity(100);
     Table {
@@ -43,24 +61,39 @@ impl Default for Table<RandomRoller> {
       button: None,
       seats,
       rolls,
+      history,
+      rules: Rules::default(),
       dice: RandomRoller::default(),
+      hand: None,
+      hands: Vec::new(),
+      payouts: HashMap::new(),
     }
   }
 }
 
-fn apply_bet<R>(mut table: Table<R>, player: &Player, bet: &Bet) -> Result<Table<R>, errors::CarryError<Table<R>>>
+// Seats are taken out of the table while the bet (or any other change to the seat) is applied and always put back,
+// successful or not, which means the table itself can be handed back in the error without cloning it.
+fn apply_bet<R, F>(mut table: Table<R>, player: &Player, op: F) -> Result<Table<R>, errors::CarryError<Table<R>>>
 where
   R: Clone + Iterator<Item = u8>,
+  F: FnOnce(Seat, &Rules) -> Result<Seat, errors::CarryError<Seat>>,
 {
-  let seat = table
-    .seats
-    .remove(&player.id)
-    .ok_or_else(|| errors::CarryError::new(table.clone(), errors::RuleViolation::InvalidSeat))?;
+  let seat = match table.seats.remove(&player.id) {
+    Some(seat) => seat,
+    None => return Err(errors::CarryError::new(table, errors::RuleViolation::InvalidSeat)),
+  };
 
-  let updated = seat.bet(bet).map_err(|e| e.map(|_| table.clone()))?;
-
-  table.seats.insert(player.id.clone(), updated);
-  Ok(table)
+  match op(seat, &table.rules) {
+    Ok(updated) => {
+      table.seats.insert(player.id.clone(), updated);
+      Ok(table)
+    }
+    Err(error) => {
+      let (seat, error) = error.into_parts();
+      table.seats.insert(player.id.clone(), seat);
+      Err(errors::CarryError::new(table, error))
+    }
+  }
 }
 
 impl<R> Table<R>
@@ -74,7 +107,12 @@ where
       button,
       rolls,
       seats,
+      rules,
       dice: _,
+      hand,
+      hands,
+      payouts,
+      history,
     } = Table::<RandomRoller>::default();
 
     Table {
@@ -84,28 +122,194 @@ where
       roller,
       id,
       rolls,
+      rules,
+      hand,
+      hands,
+      payouts,
+      history,
+    }
+  }
+
+  // Swaps out the dice the table is rolling with, keeping everything else as it was.
+  pub fn replace_dice<D>(self, dice: D) -> Table<D>
+  where
+    D: Clone + Iterator<Item = u8>,
+  {
+    let Table {
+      id,
+      roller,
+      button,
+      seats,
+      rolls,
+      rules,
+      dice: _,
+      hand,
+      hands,
+      payouts,
+      history,
+    } = self;
+
+    Table {
+      id,
+      roller,
+      button,
+      seats,
+      rolls,
+      rules,
+      dice,
+      hand,
+      hands,
+      payouts,
+      history,
     }
   }
 
+  pub fn dice(&self) -> &R {
+    &self.dice
+  }
+
+  pub fn with_variant(self, variant: Variant) -> Self {
+    let rules = Rules { variant, ..self.rules };
+    Table { rules, ..self }
+  }
+
+  pub fn with_field(self, field: FieldPaytable) -> Self {
+    let rules = Rules { field, ..self.rules };
+    Table { rules, ..self }
+  }
+
+  pub fn with_max_odds(self, max_odds: Option<u32>) -> Self {
+    let rules = Rules { max_odds, ..self.rules };
+    Table { rules, ..self }
+  }
+
+  // The number of most recent rolls kept with the table. At least the last roll is always kept.
+  pub fn with_history(mut self, history: usize) -> Self {
+    let history = history.max(1);
+    self.rolls.truncate(history);
+    Table { history, ..self }
+  }
+
+  pub fn history(&self) -> usize {
+    self.history
+  }
+
   pub fn identifier(&self) -> String {
     self.id.to_string()
   }
 
+  pub fn rules(&self) -> &Rules {
+    &self.rules
+  }
+
+  pub fn button(&self) -> Option<u8> {
+    self.button
+  }
+
+  pub fn roller(&self) -> Option<&str> {
+    self.roller.as_deref()
+  }
+
+  // The most recent rolls, newest first.
+  pub fn rolls(&self) -> &[Roll] {
+    &self.rolls
+  }
+
+  // The hand being thrown by the current shooter, if the dice have been rolled since they got them.
+  pub fn hand(&self) -> Option<&Hand> {
+    self.hand.as_ref()
+  }
+
+  // Hands that have finished since they were last taken, oldest first. Finished hands are not part of the serialized
+  // table state; whoever persists the table is expected to take them after rolling.
+  pub fn hands(&self) -> &[Hand] {
+    &self.hands
+  }
+
+  pub fn take_hands(&mut self) -> Vec<Hand> {
+    std::mem::take(&mut self.hands)
+  }
+
+  // Winnings from seats left behind by players who stood up with bets still working, keyed by player id. These are
+  // owed back to the player, and like finished hands are kept until they are taken.
+  pub fn payouts(&self) -> &HashMap<String, u32> {
+    &self.payouts
+  }
+
+  pub fn take_payouts(&mut self) -> HashMap<String, u32> {
+    std::mem::take(&mut self.payouts)
+  }
+
+  pub fn balance(&self, player: &str) -> Option<u32> {
+    self.seats.get(player).map(|seat| seat.balance())
+  }
+
+  pub fn bets(&self, player: &str) -> Option<&[Bet]> {
+    self.seats.get(player).map(|seat| seat.bets())
+  }
+
+  pub fn is_seated(&self, player: &str) -> bool {
+    self.seats.contains_key(player)
+  }
+
+  // Whether the player stood up from their seat while bets that can't be taken down were still working on it.
+  pub fn is_orphaned(&self, player: &str) -> bool {
+    self.seats.get(player).map(Seat::is_orphaned).unwrap_or(false)
+  }
+
   pub fn population(&self) -> usize {
     self.seats.len()
   }
 
+  fn violation(&self, bet: &Bet) -> Option<errors::RuleViolation> {
+    match (self.button, bet) {
+      (Some(button), Bet::Pass(_)) => match bet.pass_target() {
+        None => Some(errors::PASS_LINE_ALREADY_ON),
+        Some(target) if target != button => Some(errors::PUT_TARGET_ERROR),
+        Some(_) => None,
+      },
+      (None, Bet::Pass(_)) if bet.pass_target().is_some() => Some(errors::PUT_OFF_ERROR),
+      (None, Bet::Place(_, _)) => Some(errors::PLACE_OFF_ERROR),
+      (None, Bet::Come(_)) => Some(errors::COME_OFF_ERROR),
+      (None, Bet::PassOdds(_, _)) => Some(errors::PASS_ODDS_OFF_ERROR),
+      (None, Bet::Hardway(_, _)) => Some(errors::HARDWAY_OFF_ERROR),
+      (_, Bet::Place(_, target)) if !self.rules.variant.is_point(*target) => {
+        Some(errors::RuleViolation::InvalidTarget(*target))
+      }
+      (_, Bet::Come(_)) => match bet.come_target() {
+        Some(target) if !self.rules.variant.is_point(target) => Some(errors::RuleViolation::InvalidTarget(target)),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
   pub fn bet(self, player: &Player, bet: &Bet) -> Result<Self, errors::CarryError<Self>> {
-    let valid = match (self.button, bet) {
-      (Some(_), Bet::Pass(_)) => Err(errors::CarryError::new(self, errors::PASS_LINE_ALREADY_ON)),
-      (None, Bet::Place(_, _)) => Err(errors::CarryError::new(self, errors::PLACE_OFF_ERROR)),
-      (None, Bet::Come(_)) => Err(errors::CarryError::new(self, errors::COME_OFF_ERROR)),
-      (None, Bet::PassOdds(_, _)) => Err(errors::CarryError::new(self, errors::PASS_ODDS_OFF_ERROR)),
-      (None, Bet::Hardway(_, _)) => Err(errors::CarryError::new(self, errors::HARDWAY_OFF_ERROR)),
-      _ => Ok(self),
-    };
+    if let Some(violation) = self.violation(bet) {
+      return Err(errors::CarryError::new(self, violation));
+    }
 
-    valid.and_then(|table| apply_bet(table, player, bet))
+    apply_bet(self, player, |seat, rules| seat.bet(bet, rules))
+  }
+
+  // A put bet is a pass or come line bet made directly on a point, with an optional amount of odds behind it.
+  pub fn put(self, player: &Player, bet: &Bet, odds: u32) -> Result<Self, errors::CarryError<Self>> {
+    if let Some(violation) = self.violation(bet) {
+      return Err(errors::CarryError::new(self, violation));
+    }
+
+    if odds == 0 {
+      return apply_bet(self, player, |seat, rules| seat.bet(bet, rules));
+    }
+
+    apply_bet(self, player, |seat, rules| seat.put(bet, odds, rules))
+  }
+
+  pub fn take_down<F>(self, player: &Player, filter: F) -> Result<Self, errors::CarryError<Self>>
+  where
+    F: Fn(&Bet) -> bool,
+  {
+    apply_bet(self, player, |seat, _| Ok(seat.take_down(filter)))
   }
 
   pub fn stand(self, player: &mut Player) -> Self {
@@ -116,6 +320,11 @@ where
       dice,
       rolls,
       seats,
+      rules,
+      hand,
+      hands,
+      payouts,
+      history,
     } = self;
 
     let seats = seats
@@ -133,7 +342,8 @@ where
 
     roller = roller.and_then(|id| if id == player.id { None } else { Some(id) });
 
-    if let Some((id, _)) = seats.iter().next() {
+    // Seats left behind by players who have stood up can't throw the dice.
+    if let Some((id, _)) = seats.iter().find(|(_, seat)| !seat.is_orphaned()) {
       roller = roller.or(Some(id.clone()));
     }
 
@@ -143,72 +353,155 @@ where
       roller,
       rolls,
       seats,
+      rules,
       dice,
+      hand,
+      hands,
+      payouts,
+      history,
     }
   }
 
+  // Sits the player with their whole balance.
   pub fn sit(self, player: &mut Player) -> Self {
-    let Table {
-      dice,
-      roller,
-      id,
-      button,
-      mut seats,
-      rolls,
-    } = self;
+    let amount = player.balance;
+    self.buy_in(player, amount).unwrap_or_else(errors::CarryError::consume)
+  }
 
-    let roller = roller.or(Some(player.id.clone()));
+  // Sits the player with only part of their balance, leaving the rest in their account. Buying in at a table the
+  // player is already seated at adds to their seat.
+  pub fn buy_in(mut self, player: &mut Player, amount: u32) -> Result<Self, errors::CarryError<Self>> {
+    if amount > player.balance {
+      let violation = errors::RuleViolation::PlayerBetViolation(errors::PlayerBetViolation::InsufficientFunds);
+      return Err(errors::CarryError::new(self, violation));
+    }
 
-    seats.insert(player.id.clone(), Seat::with_balance(player.balance));
-    player.balance = 0;
-    Table {
-      id,
-      dice,
-      button,
-      seats,
-      roller,
-      rolls,
+    let seat = self
+      .seats
+      .remove(&player.id)
+      .unwrap_or_else(|| Seat::default().with_nickname(player.nickname.as_str()));
+
+    self.seats.insert(player.id.clone(), seat.rebuy(amount));
+    self.roller = self.roller.or(Some(player.id.clone()));
+    player.balance -= amount;
+    Ok(self)
+  }
+
+  // Moves more of the player's balance onto their seat.
+  pub fn rebuy(self, player: &mut Player, amount: u32) -> Result<Self, errors::CarryError<Self>> {
+    if amount > player.balance {
+      let violation = errors::RuleViolation::PlayerBetViolation(errors::PlayerBetViolation::InsufficientFunds);
+      return Err(errors::CarryError::new(self, violation));
+    }
+
+    let table = apply_bet(self, player, |seat, _| Ok(seat.rebuy(amount)))?;
+    player.balance -= amount;
+    Ok(table)
+  }
+
+  // Moves part of the seat's balance back to the player without standing up; working bets are left alone.
+  pub fn cash_out(self, player: &mut Player, amount: u32) -> Result<Self, errors::CarryError<Self>> {
+    let table = apply_bet(self, player, |seat, _| seat.cash_out(amount))?;
+    player.balance += amount;
+    Ok(table)
+  }
+
+  // Sends chips from the player's seat to another player seated at the same table.
+  pub fn transfer(self, player: &Player, recipient: &str, amount: u32) -> Result<Self, errors::CarryError<Self>> {
+    let receiving = self.seats.get(recipient).filter(|seat| !seat.is_orphaned());
+
+    if recipient == player.id || receiving.is_none() {
+      return Err(errors::CarryError::new(self, errors::RuleViolation::InvalidRecipient));
+    }
+
+    let mut table = apply_bet(self, player, |seat, _| seat.cash_out(amount))?;
+
+    if let Some(seat) = table.seats.remove(recipient) {
+      table.seats.insert(recipient.to_string(), seat.rebuy(amount));
+    }
+
+    Ok(table)
+  }
+
+  // Tips go to the house; they leave the player's seat and the table altogether.
+  pub fn tip(self, player: &Player, amount: u32) -> Result<Self, errors::CarryError<Self>> {
+    apply_bet(self, player, |seat, _| seat.cash_out(amount))
+  }
+
+  // Hands the dice to another player sitting at the table, e.g. when the shooter has walked away mid-hand. As with a
+  // shooter standing up, their hand ends on the next roll.
+  pub fn pass_dice(mut self, player: &str) -> Result<Self, errors::CarryError<Self>> {
+    if self.seats.get(player).filter(|seat| !seat.is_orphaned()).is_none() {
+      return Err(errors::CarryError::new(self, errors::RuleViolation::InvalidSeat));
     }
+
+    self.roller = Some(player.to_string());
+    Ok(self)
   }
 
   pub fn roll(mut self) -> RunResult<R> {
-    let roll = vec![self.dice.next(), self.dice.next()]
-      .into_iter()
-      .flatten()
-      .collect::<Roll>();
+    let mut results = HashMap::with_capacity(self.population());
+    self.roll_into(&mut results);
+    RunResult { table: self, results }
+  }
 
-    let result = roll.result(&self.button);
-    let button = result.button(self.button);
+  // Rolls the dice and settles every seat in place. The results map is keyed by player id and is reused across calls;
+  // runs for players that are no longer seated are dropped, and the remaining entries are cleared before settlement.
+  pub fn roll_into(&mut self, results: &mut HashMap<String, SeatRuns>) {
+    let roll = self.dice.by_ref().take(2).collect::<Roll>();
+
+    // A hand left behind by a shooter who has since given up the dice ends without a seven out.
+    if let Some(hand) = self.hand.take() {
+      match Some(hand.shooter()) == self.roller.as_deref() {
+        true => self.hand = Some(hand),
+        false => self.hands.push(hand.finish()),
+      }
+    }
+
+    let before = self.button;
+    let result = roll.result(&self.button, self.rules.variant);
+    self.button = result.button(self.button);
 
     log::debug!("generated roll - {:?}, result: {:?}", roll, result);
-    let pop = self.population();
-
-    let (seats, results) = self.seats.into_iter().map(|(key, seat)| (key, seat.roll(&roll))).fold(
-      (HashMap::with_capacity(pop), HashMap::with_capacity(pop)),
-      |(mut seats, mut totals), res| {
-        let (uuid, (seat, results)) = res;
-        seats.insert(uuid.clone(), seat);
-        totals.insert(uuid.clone(), results);
-        (seats, totals)
-      },
-    );
 
-    let rolls = Some(roll)
-      .into_iter()
-      .chain(self.rolls.into_iter())
-      .take(crate::constants::MAX_ROLL_HISTORY)
-      .collect::<Vec<Roll>>();
-
-    let next = Table {
-      id: self.id,
-      roller: self.roller,
-      dice: self.dice,
-      button,
-      rolls,
-      seats,
-    };
+    let seats = &self.seats;
+    results.retain(|id, _| seats.contains_key(id));
+
+    for (id, seat) in self.seats.iter_mut() {
+      match results.get_mut(id) {
+        Some(runs) => seat.settle(&roll, &self.rules, runs),
+        None => {
+          let mut runs = SeatRuns::default();
+          seat.settle(&roll, &self.rules, &mut runs);
+          results.insert(id.clone(), runs);
+        }
+      }
+    }
+
+    // Nobody is sitting at an orphaned seat to collect from it, so its winnings are paid straight out and the seat goes
+    // away along with its last bet.
+    for (id, seat) in self.seats.iter_mut().filter(|(_, seat)| seat.is_orphaned()) {
+      if seat.balance() > 0 {
+        *self.payouts.entry(id.clone()).or_default() += seat.collect();
+      }
+    }
+
+    self
+      .seats
+      .retain(|_, seat| !seat.is_orphaned() || !seat.bets().is_empty());
 
-    RunResult { table: next, results }
+    if let Some(roller) = self.roller.as_ref() {
+      let hand = self.hand.get_or_insert_with(|| Hand::new(roller.as_str()));
+      hand.record(&roll, before, self.button, results);
+
+      if hand.seven_out() {
+        let finished = self.hand.take().map(Hand::finish);
+        self.hands.extend(finished);
+      }
+    }
+
+    self.rolls.truncate(self.history - 1);
+    self.rolls.insert(0, roll);
   }
 }
 
@@ -217,7 +510,7 @@ where
   R: Clone + Iterator<Item = u8>,
 {
   fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-    writeln!(formatter, "table {}", self.id)?;
+    writeln!(formatter, "table {} ({:?})", self.id, self.rules.variant)?;
     writeln!(formatter, "button:    {:?}", self.button)?;
     writeln!(formatter, "last roll: {:?}", self.rolls.get(0))?;
 
@@ -240,31 +533,120 @@ where
  *
  * It is likely that this would better belong in bankah, where that library is responsible for creating it's structures
  * from what we have here, but that might mean making fields of these types public (`pub`), which is not ideal either.
+ *
+ * With the `serde` feature enabled the engine types can be serialized directly instead, which is lossless where these
+ * conversions are not (dice and finished hands that haven't been taken are not part of the bankah schema).
  */
-impl From<&TableState> for Table<crate::rollers::RandomRoller> {
-  fn from(state: &TableState) -> Self {
+// Checks that don't belong to any single part of a table: every bet and the button must be on a point for the table's
+// variant (bets on anything else would never be settled), and whoever has the dice must be sitting at the table.
+fn check_table(
+  variant: &Variant,
+  seats: &HashMap<String, Seat>,
+  button: Option<u8>,
+  roller: Option<&String>,
+) -> Result<(), errors::ConversionError> {
+  let dead = seats
+    .values()
+    .flat_map(|seat| seat.bets().iter())
+    .find_map(|bet| bet.point().filter(|target| !variant.is_point(*target)));
+
+  if let Some(target) = dead {
+    return Err(errors::ConversionError::InvalidTarget(target));
+  }
+
+  if let Some(button) = button.filter(|button| !variant.is_point(*button)) {
+    return Err(errors::ConversionError::InvalidButton(button));
+  }
+
+  if let Some(roller) = roller.filter(|id| !seats.contains_key(*id)) {
+    return Err(errors::ConversionError::UnknownRoller(roller.clone()));
+  }
+
+  Ok(())
+}
+
+// What a table deserializes as before it is checked; its rolls and bets check themselves.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedTable {
+  id: String,
+  roller: Option<String>,
+  button: Option<u8>,
+  seats: HashMap<String, Seat>,
+  rolls: Vec<Roll>,
+  history: usize,
+  rules: Rules,
+  hand: Option<Hand>,
+  hands: Vec<Hand>,
+  payouts: HashMap<String, u32>,
+}
+
+#[cfg(feature = "serde")]
+impl<R> TryFrom<UncheckedTable> for Table<R>
+where
+  R: Clone + Iterator<Item = u8> + Default,
+{
+  type Error = errors::ConversionError;
+
+  fn try_from(table: UncheckedTable) -> Result<Self, Self::Error> {
+    check_table(&table.rules.variant, &table.seats, table.button, table.roller.as_ref())?;
+
+    Ok(Table {
+      id: table.id,
+      roller: table.roller,
+      button: table.button,
+      seats: table.seats,
+      rolls: table.rolls,
+      history: table.history.max(1),
+      rules: table.rules,
+      dice: R::default(),
+      hand: table.hand,
+      hands: table.hands,
+      payouts: table.payouts,
+    })
+  }
+}
+
+impl TryFrom<&TableState> for Table<crate::rollers::RandomRoller> {
+  type Error = errors::ConversionError;
+
+  fn try_from(state: &TableState) -> Result<Self, Self::Error> {
+    let variant = Variant::from(&state.variant);
+
     let rolls = state
       .rolls
       .iter()
-      .map(|tupe| IntoIterator::into_iter([tupe.0, tupe.1]).collect())
-      .collect();
+      .map(|(left, right)| match (left, right) {
+        (1..=6, 1..=6) => Ok(IntoIterator::into_iter([*left, *right]).collect()),
+        (1..=6, face) | (face, _) => Err(errors::ConversionError::InvalidDie(*face)),
+      })
+      .collect::<Result<Vec<Roll>, errors::ConversionError>>()?;
 
     let seats = state
       .seats
       .iter()
-      .map(|(key, state)| (key.clone(), state.into()))
-      .collect();
+      .map(|(key, state)| Seat::try_from(state).map(|seat| (key.clone(), seat)))
+      .collect::<Result<HashMap<String, Seat>, errors::ConversionError>>()?;
 
-    let roller = state.roller.as_ref().map(|id| id.clone());
+    check_table(&variant, &seats, state.button, state.roller.as_ref())?;
 
-    Table {
+    Ok(Table {
       rolls,
-      roller,
+      roller: state.roller.clone(),
       seats,
       id: state.id.clone(),
       button: state.button,
+      rules: Rules {
+        variant,
+        field: FieldPaytable::from(&state.field),
+        max_odds: state.max_odds,
+      },
       dice: RandomRoller::default(),
-    }
+      hand: state.hand.as_ref().map(Hand::from),
+      hands: Vec::new(),
+      payouts: HashMap::new(),
+      history: state.history.unwrap_or(crate::constants::DEFAULT_ROLL_HISTORY).max(1),
+    })
   }
 }
 
@@ -282,6 +664,14 @@ where
       button: table.button.clone(),
       roller: table.roller.clone(),
       rolls: table.rolls.iter().map(|roll| roll.into()).collect(),
+      variant: (&table.rules.variant).into(),
+      field: (&table.rules.field).into(),
+      max_odds: table.rules.max_odds,
+      history: Some(table.history),
+      hand: table.hand.as_ref().map(|hand| HandState {
+        table: table.id.clone(),
+        ..HandState::from(hand)
+      }),
       ..def
     }
   }
@@ -289,8 +679,10 @@ where
 
 #[cfg(test)]
 mod tests {
-  use super::Table;
-  use crate::{Bet, Player};
+  use super::{Seat, Table};
+  use crate::{errors, Bet, FieldPaytable, Player, Variant};
+  use bankah::state::{BetState, SeatState, TableState, TableVariant, TargetKind};
+  use std::convert::TryFrom;
 
   #[derive(Debug, Default, Clone)]
   struct TestDice(Option<u8>, Option<u8>);
@@ -321,6 +713,361 @@ mod tests {
     assert_eq!(result.results.get(&player.id).expect("missing player").winnings(), 200);
   }
 
+  #[test]
+  fn test_roll_into_reuses_results() {
+    let mut player = Player::default();
+    let mut other = Player::default();
+    let mut table = Table::with_dice(TestDice::from((2, 5)))
+      .sit(&mut player)
+      .sit(&mut other)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap()
+      .stand(&mut other);
+    let mut results = std::collections::HashMap::new();
+    results.insert(other.id.clone(), Default::default());
+    table.roll_into(&mut results);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results.get(&player.id).expect("missing player").winnings(), 200);
+    assert_eq!(table.rolls.len(), 1);
+  }
+
+  #[derive(Debug, Clone)]
+  struct SequenceDice(Vec<u8>);
+
+  impl Iterator for SequenceDice {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      match self.0.is_empty() {
+        true => None,
+        false => Some(self.0.remove(0)),
+      }
+    }
+  }
+
+  #[test]
+  fn test_hand_finished_by_seven_out() {
+    let mut player = Player::default();
+    let mut table = Table::with_dice(SequenceDice(vec![2, 2, 3, 1, 3, 3, 5, 2, 1, 1]))
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap();
+    let mut results = std::collections::HashMap::new();
+
+    table.roll_into(&mut results);
+    table.roll_into(&mut results);
+    let mut table = table.bet(&player, &Bet::start_pass(50)).unwrap();
+    table.roll_into(&mut results);
+
+    let hand = table.hand().expect("missing hand in progress");
+    assert_eq!((hand.rolls(), hand.points_set(), hand.points_made()), (3, 2, 1));
+    assert_eq!(hand.won(), 100);
+    assert!(table.hands().is_empty());
+
+    table.roll_into(&mut results);
+    assert!(table.hand().is_none());
+
+    let hands = table.take_hands();
+    assert_eq!(hands.len(), 1);
+    assert_eq!(hands[0].shooter(), player.id);
+    assert_eq!((hands[0].rolls(), hands[0].won(), hands[0].lost()), (4, 100, 50));
+    assert!(hands[0].seven_out() && hands[0].is_finished());
+    assert!(table.hands().is_empty());
+
+    table.roll_into(&mut results);
+    assert_eq!(table.hand().map(|hand| hand.rolls()), Some(1));
+  }
+
+  #[test]
+  fn test_hand_finished_by_shooter_leaving() {
+    let mut shooter = Player::default();
+    let mut player = Player::default();
+    let mut table = Table::with_dice(SequenceDice(vec![3, 3, 4, 4]))
+      .sit(&mut shooter)
+      .sit(&mut player);
+    let mut results = std::collections::HashMap::new();
+    table.roll_into(&mut results);
+
+    let mut table = table.stand(&mut shooter);
+    table.roll_into(&mut results);
+
+    let hands = table.take_hands();
+    assert_eq!(hands.len(), 1);
+    assert_eq!(hands[0].shooter(), shooter.id);
+    assert!(!hands[0].seven_out());
+    assert_eq!(table.hand().map(|hand| hand.shooter()), Some(player.id.as_str()));
+  }
+
+  #[test]
+  fn test_with_history() {
+    let mut player = Player::default();
+    let mut table = Table::with_dice(SequenceDice(vec![1, 2, 3, 4, 5, 6, 1, 2]))
+      .with_history(2)
+      .sit(&mut player);
+    let mut results = std::collections::HashMap::new();
+
+    for _ in 0..4 {
+      table.roll_into(&mut results);
+    }
+
+    assert_eq!(table.rolls().len(), 2);
+    assert_eq!(table.rolls()[0].total(), 3);
+
+    let table = table.with_history(0);
+    assert_eq!(table.history(), 1);
+    assert_eq!(table.rolls().len(), 1);
+  }
+
+  fn seated(bets: Vec<BetState>) -> TableState {
+    let mut state = TableState::default();
+    let seat = SeatState {
+      bets,
+      ..SeatState::default()
+    };
+    state.seats.insert("player".into(), seat);
+    state.roller = Some("player".into());
+    state
+  }
+
+  #[test]
+  fn test_try_from_state() {
+    let mut state = seated(vec![BetState::Target(TargetKind::Place, 10, 6)]);
+    state.button = Some(6);
+    state.rolls = vec![(3, 3)];
+    let table = Table::try_from(&state).expect("valid state");
+    assert_eq!(table.bets("player"), Some(&[Bet::Place(10, 6)][..]));
+    assert_eq!(table.roller(), Some("player"));
+  }
+
+  #[test]
+  fn test_sit_keeps_nickname() {
+    let mut player = Player {
+      nickname: "stickman".into(),
+      ..Player::default()
+    };
+    let table = Table::with_dice(TestDice::from((3, 3))).sit(&mut player);
+    let state = TableState::from(&table);
+    assert_eq!(
+      state.seats.get(&player.id).map(|seat| seat.nickname.as_str()),
+      Some("stickman")
+    );
+  }
+
+  #[test]
+  fn test_buy_in_partial() {
+    let mut player = Player::with_balance(1000);
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .buy_in(&mut player, 300)
+      .unwrap();
+    assert_eq!((table.balance(&player.id), player.balance), (Some(300), 700));
+    assert_eq!(table.roller(), Some(player.id.as_str()));
+
+    let error = table.buy_in(&mut player, 701).unwrap_err();
+    assert_eq!(
+      error.error,
+      errors::RuleViolation::PlayerBetViolation(errors::PlayerBetViolation::InsufficientFunds)
+    );
+    assert_eq!(player.balance, 700);
+  }
+
+  #[test]
+  fn test_rebuy_and_cash_out() {
+    let mut player = Player::with_balance(1000);
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .buy_in(&mut player, 300)
+      .and_then(|table| table.bet(&player, &Bet::start_pass(100)))
+      .and_then(|table| table.rebuy(&mut player, 200))
+      .unwrap();
+    assert_eq!((table.balance(&player.id), player.balance), (Some(400), 500));
+
+    let table = table.cash_out(&mut player, 350).unwrap();
+    assert_eq!((table.balance(&player.id), player.balance), (Some(50), 850));
+    assert_eq!(table.bets(&player.id), Some(&[Bet::start_pass(100)][..]));
+
+    let table = table.cash_out(&mut player, 51).unwrap_err().consume();
+    assert_eq!((table.balance(&player.id), player.balance), (Some(50), 850));
+
+    let mut stranger = Player::with_balance(100);
+    let error = table.rebuy(&mut stranger, 10).unwrap_err();
+    assert_eq!(error.error, errors::RuleViolation::InvalidSeat);
+    assert_eq!(stranger.balance, 100);
+  }
+
+  #[test]
+  fn test_orphaned_seat_paid_out() {
+    let (mut shooter, mut player) = (Player::default(), Player::with_balance(500));
+    let mut table = Table::with_dice(SequenceDice(vec![2, 2, 6, 5, 3, 1]))
+      .sit(&mut shooter)
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap();
+    let mut results = std::collections::HashMap::new();
+
+    table.roll_into(&mut results);
+    let mut table = table.stand(&mut player);
+    assert_eq!(player.balance, 400);
+    assert!(table.is_seated(&player.id));
+    assert_eq!(table.roller(), Some(shooter.id.as_str()));
+
+    table.roll_into(&mut results);
+    assert!(table.payouts().is_empty());
+    assert!(table.is_seated(&player.id));
+
+    table.roll_into(&mut results);
+    assert_eq!(table.take_payouts().get(&player.id), Some(&200));
+    assert!(!table.is_seated(&player.id));
+    assert_eq!(results.get(&player.id).map(|runs| runs.winnings()), Some(200));
+    assert!(table.payouts().is_empty());
+  }
+
+  #[test]
+  fn test_transfer_and_tip() {
+    let (mut sender, mut recipient) = (Player::with_balance(500), Player::with_balance(100));
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .sit(&mut sender)
+      .sit(&mut recipient)
+      .transfer(&sender, &recipient.id, 200)
+      .and_then(|table| table.tip(&sender, 25))
+      .unwrap();
+    assert_eq!(table.balance(&sender.id), Some(275));
+    assert_eq!(table.balance(&recipient.id), Some(300));
+
+    let table = table.transfer(&sender, &recipient.id, 276).unwrap_err().consume();
+    assert_eq!(table.balance(&sender.id), Some(275));
+
+    let invalid = errors::RuleViolation::InvalidRecipient;
+    let error = table.transfer(&sender, &sender.id, 10).unwrap_err();
+    assert_eq!(error.error, invalid);
+    let error = error.consume().transfer(&sender, "nobody", 10).unwrap_err();
+    assert_eq!(error.error, invalid);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_round_trip() {
+    let mut player = Player::with_balance(500);
+    let table = Table::with_dice(SequenceDice(vec![3, 3, 2, 1]))
+      .with_history(4)
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap()
+      .roll()
+      .table
+      .bet(&player, &Bet::Place(50, 8))
+      .unwrap()
+      .roll()
+      .table;
+
+    let serialized = serde_json::to_value(&table).expect("unable to serialize");
+    let restored: Table<crate::RandomRoller> =
+      serde_json::from_value(serialized.clone()).expect("unable to deserialize");
+
+    assert_eq!(
+      serde_json::to_value(&restored).expect("unable to serialize"),
+      serialized
+    );
+    assert_eq!(restored.bets(&player.id), table.bets(&player.id));
+    assert_eq!(restored.rolls(), table.rolls());
+    assert_eq!(restored.history(), 4);
+    assert_eq!(restored.hand(), table.hand());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_rejects_invalid() {
+    let mut player = Player::with_balance(500);
+    let table = Table::with_dice(SequenceDice(vec![3, 3]))
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap()
+      .roll()
+      .table
+      .bet(&player, &Bet::Place(50, 8))
+      .unwrap();
+    let serialized = serde_json::to_value(&table).expect("unable to serialize");
+
+    let invalid = |path: &str, value: serde_json::Value| {
+      let mut changed = serialized.clone();
+      *changed.pointer_mut(path).expect("missing path") = value;
+      serde_json::from_value::<Table<crate::RandomRoller>>(changed).is_err()
+    };
+
+    assert!(invalid("/rolls/0", serde_json::json!([3, 7])));
+    assert!(invalid("/button", serde_json::json!(7)));
+    assert!(invalid("/roller", serde_json::json!("nobody")));
+    assert!(invalid(
+      &format!("/seats/{}/bets/1", player.id),
+      serde_json::json!({ "Place": [50, 7] })
+    ));
+    assert!(invalid(
+      &format!("/seats/{}/bets/0/Pass/target", player.id),
+      serde_json::json!(1)
+    ));
+  }
+
+  #[test]
+  fn test_try_from_state_variant_target() {
+    let mut state = seated(vec![BetState::Target(TargetKind::Place, 10, 11)]);
+    assert_eq!(
+      Table::try_from(&state).err(),
+      Some(errors::ConversionError::InvalidTarget(11))
+    );
+    state.variant = TableVariant::Crapless;
+    assert!(Table::try_from(&state).is_ok());
+  }
+
+  #[test]
+  fn test_try_from_corrupt_state() {
+    let mut state = seated(vec![]);
+    state.rolls = vec![(3, 9)];
+    assert_eq!(
+      Table::try_from(&state).err(),
+      Some(errors::ConversionError::InvalidDie(9))
+    );
+
+    let mut state = seated(vec![]);
+    state.button = Some(7);
+    assert_eq!(
+      Table::try_from(&state).err(),
+      Some(errors::ConversionError::InvalidButton(7))
+    );
+
+    let mut state = seated(vec![]);
+    state.roller = Some("ghost".into());
+    assert_eq!(
+      Table::try_from(&state).err(),
+      Some(errors::ConversionError::UnknownRoller("ghost".into()))
+    );
+  }
+
+  #[test]
+  fn test_take_down() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((2, 2)))
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap()
+      .roll()
+      .table
+      .bet(&player, &Bet::Place(60, 6))
+      .unwrap()
+      .take_down(&player, |bet| matches!(bet, Bet::Place(_, 6)))
+      .unwrap();
+    assert_eq!(table.balance(&player.id), Some(9900));
+    assert_eq!(table.bets(&player.id), Some(&[Bet::put_pass(100, 4)][..]));
+    assert_eq!(table.button(), Some(4));
+  }
+
+  #[test]
+  fn test_bet_error_keeps_seat() {
+    let mut player = Player::with_balance(50);
+    let result = Table::with_dice(TestDice::from((2, 5)))
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100));
+    let table = result.expect_err("should have failed").consume();
+    assert_eq!(table.seats.get(&player.id), Some(&Seat::with_balance(50)));
+  }
+
   #[test]
   fn test_run_with_losses() {
     let mut player = Player::default();
@@ -385,10 +1132,134 @@ mod tests {
     assert_eq!(table.roller, Some(player.id.clone()));
   }
 
+  #[test]
+  fn test_pass_dice() {
+    let mut roller = Player::default();
+    let mut player = Player::default();
+    let table = Table::default().sit(&mut roller).sit(&mut player);
+    let table = table.pass_dice(&player.id).expect("unable to pass dice");
+    assert_eq!(table.roller, Some(player.id.clone()));
+
+    let error = table.pass_dice("nobody").unwrap_err();
+    assert_eq!(error.error, errors::RuleViolation::InvalidSeat);
+    assert_eq!(error.consume().roller, Some(player.id.clone()));
+  }
+
   #[test]
   fn test_roller_after_sit_stand() {
     let mut roller = Player::default();
     let table = Table::default().sit(&mut roller).stand(&mut roller);
     assert_eq!(table.roller, None);
   }
+
+  #[test]
+  fn test_crapless_come_out_twelve() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((6, 6)))
+      .with_variant(Variant::Crapless)
+      .sit(&mut player)
+      .bet(&player, &Bet::start_pass(100))
+      .unwrap();
+    let result = table.roll();
+    assert_eq!(result.results.get(&player.id).expect("missing player").losses(), 0);
+    assert_eq!(result.table.button, Some(12));
+  }
+
+  #[test]
+  fn test_crapless_place_eleven() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((2, 2)))
+      .with_variant(Variant::Crapless)
+      .sit(&mut player)
+      .roll()
+      .table
+      .bet(&player, &Bet::Place(100, 11));
+    assert!(table.is_ok());
+  }
+
+  #[test]
+  fn test_standard_place_eleven() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((2, 2)))
+      .sit(&mut player)
+      .roll()
+      .table
+      .bet(&player, &Bet::Place(100, 11));
+    assert_eq!(table.unwrap_err().error, errors::RuleViolation::InvalidTarget(11));
+  }
+
+  #[test]
+  fn test_field_triple_two() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((1, 1)))
+      .with_field(FieldPaytable::triple_two())
+      .sit(&mut player)
+      .bet(&player, &Bet::Field(100))
+      .unwrap();
+    let result = table.roll();
+    assert_eq!(result.results.get(&player.id).expect("missing player").winnings(), 400);
+  }
+
+  #[test]
+  fn test_put_without_button() {
+    let mut player = Player::default();
+    let table = Table::default().sit(&mut player).bet(&player, &Bet::put_pass(100, 6));
+    assert_eq!(table.unwrap_err().error, errors::PUT_OFF_ERROR);
+  }
+
+  #[test]
+  fn test_put_pass_on_button() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .sit(&mut player)
+      .roll()
+      .table
+      .put(&player, &Bet::put_pass(100, 6), 200)
+      .unwrap();
+    let seat = table.seats.get(&player.id).expect("missing seat");
+    assert_eq!(
+      seat,
+      &Seat::with_balance(10000)
+        .bet(&Bet::put_pass(100, 6), table.rules())
+        .and_then(|seat| seat.bet(&Bet::PassOdds(200, 6), table.rules()))
+        .unwrap()
+    );
+  }
+
+  #[test]
+  fn test_put_pass_off_button() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .sit(&mut player)
+      .roll()
+      .table
+      .bet(&player, &Bet::put_pass(100, 8));
+    assert_eq!(table.unwrap_err().error, errors::PUT_TARGET_ERROR);
+  }
+
+  #[test]
+  fn test_put_come_odds_limit() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .with_max_odds(Some(2))
+      .sit(&mut player)
+      .roll()
+      .table
+      .put(&player, &Bet::put_come(100, 9), 300);
+    assert_eq!(
+      table.unwrap_err().error,
+      errors::RuleViolation::PlayerBetViolation(errors::PlayerBetViolation::OddsLimitExceeded)
+    );
+  }
+
+  #[test]
+  fn test_put_come_invalid_target() {
+    let mut player = Player::default();
+    let table = Table::with_dice(TestDice::from((3, 3)))
+      .sit(&mut player)
+      .roll()
+      .table
+      .bet(&player, &Bet::put_come(100, 11));
+    assert_eq!(table.unwrap_err().error, errors::RuleViolation::InvalidTarget(11));
+  }
 }
@@ -0,0 +1,62 @@
+use super::checks::{is_crapless_place, is_place};
+
+use bankah::state::TableVariant;
+
+// The variant of a table decides which totals become the point on the come out roll. In crapless craps, the 2, 3, 11
+// and 12 are points like any other box number; only the 7 decides the come out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+  Standard,
+  Crapless,
+}
+
+impl Default for Variant {
+  fn default() -> Self {
+    Variant::Standard
+  }
+}
+
+impl Variant {
+  pub fn is_point(&self, total: u8) -> bool {
+    match self {
+      Variant::Standard => is_place(total),
+      Variant::Crapless => is_crapless_place(total),
+    }
+  }
+}
+
+impl From<&TableVariant> for Variant {
+  fn from(state: &TableVariant) -> Variant {
+    match state {
+      TableVariant::Standard => Variant::Standard,
+      TableVariant::Crapless => Variant::Crapless,
+    }
+  }
+}
+
+impl From<&Variant> for TableVariant {
+  fn from(variant: &Variant) -> TableVariant {
+    match variant {
+      Variant::Standard => TableVariant::Standard,
+      Variant::Crapless => TableVariant::Crapless,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::Variant;
+
+  #[test]
+  fn standard_points() {
+    let points = (2..=12).filter(|t| Variant::Standard.is_point(*t)).collect::<Vec<u8>>();
+    assert_eq!(points, vec![4, 5, 6, 8, 9, 10]);
+  }
+
+  #[test]
+  fn crapless_points() {
+    let points = (2..=12).filter(|t| Variant::Crapless.is_point(*t)).collect::<Vec<u8>>();
+    assert_eq!(points, vec![2, 3, 4, 5, 6, 8, 9, 10, 11, 12]);
+  }
+}
@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Every `.txt` script in `tests/scripts` is run through the binary and its transcript compared against the `.jsonl`
+// file next to it. A `# dice: ...` line at the top of a script provides the dice. Set `TWOWAIYO_UPDATE_GOLDEN=1` to
+// rewrite the expected transcripts after an intentional change.
+fn scripts() -> Vec<PathBuf> {
+  let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("scripts");
+  let mut scripts = std::fs::read_dir(directory)
+    .expect("unable to read scripts directory")
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().map(|ext| ext == "txt").unwrap_or(false))
+    .collect::<Vec<PathBuf>>();
+  scripts.sort();
+  scripts
+}
+
+fn dice(script: &Path) -> Option<String> {
+  let contents = std::fs::read_to_string(script).expect("unable to read script");
+  let first = contents.lines().next()?;
+  first.strip_prefix("# dice:").map(|faces| faces.trim().to_string())
+}
+
+#[test]
+fn golden_transcripts() {
+  let update = std::env::var("TWOWAIYO_UPDATE_GOLDEN").is_ok();
+  let scripts = scripts();
+  assert!(!scripts.is_empty(), "no scripts found");
+
+  for script in scripts {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_twowaiyo"));
+    command.arg("--script").arg(&script).env_remove("RUST_LOG");
+
+    if let Some(faces) = dice(&script) {
+      command.arg("--dice").arg(faces);
+    }
+
+    let output = command.output().expect("unable to run twowaiyo");
+    assert!(output.status.success(), "script {:?} failed", script);
+
+    let transcript = String::from_utf8(output.stdout).expect("invalid transcript");
+    let golden = script.with_extension("jsonl");
+
+    if update {
+      std::fs::write(&golden, &transcript).expect("unable to write golden transcript");
+      continue;
+    }
+
+    let expected = std::fs::read_to_string(&golden).expect("missing golden transcript");
+    assert_eq!(transcript, expected, "transcript mismatch for {:?}", script);
+  }
+}